@@ -0,0 +1,67 @@
+//! Compares reading a 16 KiB int array (4096 ints, as in a chunk section)
+//! one int at a time, as the reader used to, with the reader's bulk read.
+//! Run with `cargo bench --bench array_read`.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::time::{Duration, Instant};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use minecraft::nbt::{reader, writer, RootValue, Value};
+
+
+const INTS: usize = 4096;
+const ITERATIONS: usize = 2_000;
+const ROUNDS: usize = 5;
+
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    (0..ROUNDS).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+
+/// The old way: a read per element, through a `dyn Read`.
+fn read_element_by_element(reader: &mut dyn Read) -> Vec<i32> {
+    let length = reader.read_u32::<BigEndian>().unwrap() as usize;
+    let mut ints = Vec::with_capacity(length);
+    for _ in 0..length {
+        ints.push(reader.read_i32::<BigEndian>().unwrap());
+    }
+    ints
+}
+
+
+fn main() {
+    let ints = (0..INTS as i32).map(|i| i.wrapping_mul(0x3c6e_f372)).collect::<Vec<_>>();
+    let mut compound = HashMap::new();
+    compound.insert("a".to_string(), Value::IntArray(ints.clone()));
+    let root = RootValue { name: String::new(), value: Value::Compound(compound) };
+    let mut stream = Vec::new();
+    writer::write_nbt_stream(&mut stream, &root).unwrap();
+    // The root compound's header, then the entry's type and name.
+    let array_start = 3 + 1 + 2 + 1;
+
+    let parsed = reader::parse_nbt_stream(&mut Cursor::new(&stream)).unwrap();
+    assert_eq!(root, parsed);
+    assert_eq!(ints, read_element_by_element(&mut Cursor::new(&stream[array_start..])));
+
+    let old = time(|| {
+        for _ in 0..ITERATIONS {
+            read_element_by_element(&mut Cursor::new(&stream[array_start..]));
+        }
+    });
+    let new = time(|| {
+        for _ in 0..ITERATIONS {
+            reader::parse_nbt_stream(&mut Cursor::new(&stream)).unwrap();
+        }
+    });
+
+    println!("{} reads of a {} int array, best of {} rounds:", ITERATIONS, INTS, ROUNDS);
+    println!("  element by element: {:?}", old);
+    println!("  parse_nbt_stream:   {:?}", new);
+}
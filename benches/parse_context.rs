@@ -0,0 +1,60 @@
+//! Compares parsing many small NBT streams with and without a shared
+//! `ParseContext`. Run with `cargo bench --bench parse_context`.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use minecraft::nbt::{reader, writer, List, RootValue, Value};
+
+
+const STREAMS: usize = 10_000;
+const ROUNDS: usize = 5;
+
+
+/// A small, string-heavy stream, something like a villager's data.
+fn sample_stream(seed: usize) -> Vec<u8> {
+    let mut compound = HashMap::new();
+    compound.insert("id".to_string(), Value::String("minecraft:villager".to_string()));
+    compound.insert("CustomName".to_string(), Value::String(format!("Villager {}", seed)));
+    let tags = (0..16).map(|i| format!("tag_{}_{}", seed, i)).collect();
+    compound.insert("Tags".to_string(), Value::List(List::String(tags)));
+    compound.insert("Age".to_string(), Value::Int(seed as i32));
+    let root = RootValue { name: String::new(), value: Value::Compound(compound) };
+    let mut bytes = Vec::new();
+    writer::write_nbt_stream(&mut bytes, &root).unwrap();
+    bytes
+}
+
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    (0..ROUNDS).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+
+fn main() {
+    let streams = (0..STREAMS).map(sample_stream).collect::<Vec<_>>();
+    let options = reader::ReaderOptions::default();
+
+    let without = time(|| {
+        for stream in &streams {
+            reader::parse_nbt_stream_with_options(&mut Cursor::new(stream), &options).unwrap();
+        }
+    });
+    let mut context = reader::ParseContext::new();
+    let with = time(|| {
+        for stream in &streams {
+            reader::parse_nbt_stream_with_context(
+                &mut Cursor::new(stream), &options, &mut context,
+            ).unwrap();
+        }
+    });
+
+    println!("{} streams, best of {} rounds:", STREAMS, ROUNDS);
+    println!("  without a shared context: {:?}", without);
+    println!("  with a shared context:    {:?}", with);
+}
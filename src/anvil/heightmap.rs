@@ -0,0 +1,22 @@
+//! Decoding chunks' heightmaps, such as `Heightmaps.WORLD_SURFACE`.
+
+use super::palette::unpack_indices;
+
+
+/// Columns in a chunk.
+const COLUMNS: usize = 256;
+const BITS_PER_HEIGHT: u32 = 9;
+
+
+/// Unpack a heightmap's `LongArray`: 256 nine bit values, seven to a long,
+/// indexed by `x + z * 16` within the chunk. Each is the height of the
+/// column's highest matching block plus one, counted from the bottom of the
+/// world (`y = -64` since 1.18, 0 before), so 0 for an empty column.
+///
+/// If `data` holds fewer than 256 values, the rest are 0.
+pub fn decode_heightmap(data: &[i64]) -> [u16; COLUMNS] {
+    let mut heights = [0; COLUMNS];
+    let indices = unpack_indices(data, BITS_PER_HEIGHT, COLUMNS);
+    heights[..indices.len()].copy_from_slice(&indices);
+    heights
+}
@@ -0,0 +1,447 @@
+//! Reading and writing Anvil region files (`region/r.<x>.<z>.mca`), each holding the
+//! chunks of a 32×32 chunk area.
+//!
+//! A region file starts with two 4 KiB tables, one entry per chunk: where
+//! the chunk is in the file (in 4 KiB sectors), and when it was last saved.
+//! Each chunk is then stored as a length, a compression type and its
+//! compressed NBT.
+//!
+//! The game has no checksums of its own, but this crate can keep the CRC-32
+//! of each chunk in a sidecar file, to detect corruption later; see
+//! `RegionFile::keep_checksums`.
+
+use std::collections::HashMap;
+use std::convert::From;
+use std::fs::{self, File};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::write::ZlibEncoder;
+use flate2::Crc;
+
+use crate::nbt::reader::{self, Compression, NbtReadError};
+use crate::nbt::writer::{self, NbtWriteError};
+use crate::nbt::RootValue;
+
+
+pub mod heightmap;
+pub mod palette;
+#[cfg(test)]
+mod tests;
+
+
+const SECTOR_SIZE: u64 = 4096;
+/// Chunks per side of a region.
+pub const REGION_SIZE: u32 = 32;
+const CHUNK_COUNT: usize = (REGION_SIZE * REGION_SIZE) as usize;
+
+
+#[derive(Debug)]
+pub enum RegionError {
+    IoError(io::Error),
+    /// Chunk coordinates within a region run from 0 to 31.
+    ChunkOutOfRange {
+        x: u32,
+        z: u32,
+    },
+    /// The location table lists the chunk, but its length is 0.
+    ZeroLengthChunk,
+    /// A chunk's length didn't fit in the sectors allotted to it.
+    InvalidChunkLength(u32),
+    /// A compression type other than gzip (1), zlib (2) or none (3).
+    UnsupportedCompression(u8),
+    /// The chunk is too big for the region file, and is stored in a separate
+    /// `c.<x>.<z>.mcc` file alongside it.
+    ExternalChunk,
+    /// A chunk compressed to more than the 255 sectors a location can hold.
+    ChunkTooLarge(usize),
+    Nbt(NbtReadError),
+    NbtWrite(NbtWriteError),
+}
+
+
+impl From<io::Error> for RegionError {
+    fn from(err: io::Error) -> RegionError {
+        RegionError::IoError(err)
+    }
+}
+
+
+impl From<NbtReadError> for RegionError {
+    fn from(err: NbtReadError) -> RegionError {
+        RegionError::Nbt(err)
+    }
+}
+
+
+impl From<NbtWriteError> for RegionError {
+    fn from(err: NbtWriteError) -> RegionError {
+        RegionError::NbtWrite(err)
+    }
+}
+
+
+/// What a sector of a region file holds; see `RegionFile::sector_map`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorState {
+    /// Part of the location and timestamp tables.
+    Header,
+    /// Part of the chunk at `(x, z)`.
+    Used {
+        chunk: (u8, u8),
+    },
+    Free,
+}
+
+
+/// A checksum sidecar being kept up to date by the writer.
+struct Sidecar {
+    path: PathBuf,
+    checksums: Vec<u32>,
+}
+
+
+pub struct RegionFile<R: Read + Seek> {
+    inner: R,
+    /// Per chunk, the offset of its first sector in the high three bytes and
+    /// its number of sectors in the low byte; 0 if it isn't present.
+    locations: Vec<u32>,
+    timestamps: Vec<u32>,
+    sidecar: Option<Sidecar>,
+}
+
+
+fn chunk_index(x: u32, z: u32) -> Result<usize, RegionError> {
+    if x >= REGION_SIZE || z >= REGION_SIZE {
+        return Err(RegionError::ChunkOutOfRange { x, z });
+    }
+    Ok((x + z * REGION_SIZE) as usize)
+}
+
+
+/// The inverse of `chunk_index`.
+fn chunk_coords(index: usize) -> (u8, u8) {
+    ((index as u32 % REGION_SIZE) as u8, (index as u32 / REGION_SIZE) as u8)
+}
+
+
+fn sector_count(length: usize) -> usize {
+    length.div_ceil(SECTOR_SIZE as usize)
+}
+
+
+fn unix_time() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0)
+}
+
+
+/// Serialize and zlib-compress `chunk`, with its length and compression type
+/// in front, as it's stored in a region file.
+fn encode_chunk(chunk: &RootValue) -> Result<Vec<u8>, RegionError> {
+    let mut encoder = ZlibEncoder::new(vec![0, 0, 0, 0, 2], flate2::Compression::default());
+    writer::write_nbt_stream(&mut encoder, chunk)?;
+    let mut data = encoder.finish()?;
+    let length = (data.len() - 4) as u32;
+    data[..4].copy_from_slice(&length.to_be_bytes());
+    Ok(data)
+}
+
+
+fn read_table<R: Read>(reader: &mut R) -> io::Result<Vec<u32>> {
+    let mut table = vec![0; CHUNK_COUNT];
+    reader.read_u32_into::<BigEndian>(&mut table)?;
+    Ok(table)
+}
+
+
+fn write_table<W: Write>(writer: &mut W, table: &[u32]) -> io::Result<()> {
+    for &entry in table {
+        writer.write_u32::<BigEndian>(entry)?;
+    }
+    Ok(())
+}
+
+
+/// The checksum kept for a chunk: the CRC-32 of the chunk as it's stored,
+/// length and compression type included.
+fn checksum(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+
+/// Write a checksum sidecar: a table like the region file's own, of each
+/// chunk's checksum, or 0 if it isn't present.
+fn write_sidecar(path: &Path, checksums: &[u32]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(CHUNK_COUNT * 4);
+    write_table(&mut bytes, checksums)?;
+    fs::write(path, bytes)
+}
+
+
+impl<R: Read + Seek> RegionFile<R> {
+    /// Read the region file's header from `reader`. Chunks are read as
+    /// they're asked for.
+    pub fn new(mut reader: R) -> Result<RegionFile<R>, RegionError> {
+        reader.seek(SeekFrom::Start(0))?;
+        let locations = read_table(&mut reader)?;
+        let timestamps = read_table(&mut reader)?;
+        Ok(RegionFile { inner: reader, locations, timestamps, sidecar: None })
+    }
+
+    /// Give back the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Whether the chunk at `x`, `z` (relative to the region) has been
+    /// generated.
+    pub fn has_chunk(&self, x: u32, z: u32) -> Result<bool, RegionError> {
+        Ok(self.locations[chunk_index(x, z)?] != 0)
+    }
+
+    /// When the chunk at `x`, `z` was last saved, in seconds since the Unix
+    /// epoch, or `None` if it isn't present.
+    pub fn timestamp(&self, x: u32, z: u32) -> Result<Option<u32>, RegionError> {
+        let index = chunk_index(x, z)?;
+        Ok(match self.locations[index] {
+            0 => None,
+            _ => Some(self.timestamps[index]),
+        })
+    }
+
+    /// What each sector of the file holds, as far as the location table says,
+    /// up to the end of the last chunk. Chunks that overlap each other are
+    /// shown in the later one's sectors.
+    pub fn sector_map(&self) -> Vec<SectorState> {
+        let mut map = vec![SectorState::Header; 2];
+        for (index, &location) in self.locations.iter().enumerate() {
+            if location == 0 {
+                continue;
+            }
+            let start = (location >> 8) as usize;
+            let end = start + (location & 0xff) as usize;
+            if map.len() < end {
+                map.resize(end, SectorState::Free);
+            }
+            let chunk = chunk_coords(index);
+            // A location pointing into the header is corrupt; leave it be.
+            for sector in map.iter_mut().take(end).skip(start.max(2)) {
+                *sector = SectorState::Used { chunk };
+            }
+        }
+        map
+    }
+
+    /// Read and parse the chunk at `x`, `z` (relative to the region), or
+    /// `None` if it hasn't been generated.
+    pub fn chunk(&mut self, x: u32, z: u32) -> Result<Option<RootValue>, RegionError> {
+        let index = chunk_index(x, z)?;
+        let data = match self.raw_chunk(index)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let compression = match data[4] {
+            1 => Compression::Gzip,
+            2 => Compression::Zlib,
+            3 => Compression::None,
+            t if t & 0x80 != 0 => return Err(RegionError::ExternalChunk),
+            t => return Err(RegionError::UnsupportedCompression(t)),
+        };
+        Ok(Some(reader::parse_nbt_bytes_with(&data[5..], compression)?))
+    }
+
+    /// Every chunk's checksum, or 0 for those that aren't present.
+    fn checksums(&mut self) -> Result<Vec<u32>, RegionError> {
+        (0..CHUNK_COUNT)
+            .map(|index| Ok(self.raw_chunk(index)?.map_or(0, |data| checksum(&data))))
+            .collect()
+    }
+
+    /// Write the checksum of every chunk to `sidecar`, for
+    /// `verify_checksums` to check against later.
+    pub fn write_checksums(&mut self, sidecar: &Path) -> Result<(), RegionError> {
+        let checksums = self.checksums()?;
+        Ok(write_sidecar(sidecar, &checksums)?)
+    }
+
+    /// Compare each chunk with its checksum in `sidecar`, as written by
+    /// `write_checksums` or kept by `keep_checksums`. Returns the `(x, z)`
+    /// of each chunk that doesn't match, including chunks that have
+    /// appeared or disappeared since, and chunks that can't be read at all.
+    pub fn verify_checksums(&mut self, sidecar: &Path) -> Result<Vec<(u8, u8)>, RegionError> {
+        let expected = read_table(&mut File::open(sidecar)?)?;
+        let mut mismatched = Vec::new();
+        for (index, &expected) in expected.iter().enumerate() {
+            let matches = match self.raw_chunk(index) {
+                Ok(data) => data.map_or(0, |data| checksum(&data)) == expected,
+                Err(_) => false,
+            };
+            if !matches {
+                mismatched.push(chunk_coords(index));
+            }
+        }
+        Ok(mismatched)
+    }
+
+    /// Read the chunk at `index` as it's stored: its length, compression type
+    /// and compressed NBT, without the padding after it.
+    fn raw_chunk(&mut self, index: usize) -> Result<Option<Vec<u8>>, RegionError> {
+        let location = self.locations[index];
+        if location == 0 {
+            return Ok(None);
+        }
+        let offset = u64::from(location >> 8) * SECTOR_SIZE;
+        let capacity = u64::from(location & 0xff) * SECTOR_SIZE;
+        self.inner.seek(SeekFrom::Start(offset))?;
+        let length = self.inner.read_u32::<BigEndian>()?;
+        if length == 0 {
+            return Err(RegionError::ZeroLengthChunk);
+        }
+        // The length counts the compression type byte, but not itself. Only
+        // that much is read: the rest of the last sector is padding.
+        if u64::from(length) + 4 > capacity {
+            return Err(RegionError::InvalidChunkLength(length));
+        }
+        let mut data = vec![0; length as usize + 4];
+        data[..4].copy_from_slice(&length.to_be_bytes());
+        self.inner.read_exact(&mut data[4..])?;
+        Ok(Some(data))
+    }
+}
+
+
+impl<W: Read + Write + Seek> RegionFile<W> {
+    /// Start a new, empty region file in `writer`: both tables, zeroed.
+    pub fn create(mut writer: W) -> Result<RegionFile<W>, RegionError> {
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(&[0; 2 * SECTOR_SIZE as usize])?;
+        Ok(RegionFile {
+            inner: writer,
+            locations: vec![0; CHUNK_COUNT],
+            timestamps: vec![0; CHUNK_COUNT],
+            sidecar: None,
+        })
+    }
+
+    /// Save `chunk` as the chunk at `x`, `z` (relative to the region),
+    /// zlib-compressed. It's written over the chunk's old sectors if it still
+    /// fits in them, and after the last chunk in the file otherwise.
+    pub fn write_chunk(&mut self, x: u32, z: u32, chunk: &RootValue)
+            -> Result<(), RegionError> {
+        let index = chunk_index(x, z)?;
+        let data = encode_chunk(chunk)?;
+        let sectors = sector_count(data.len());
+        let old = self.locations[index];
+        let start = if old != 0 && sectors <= (old & 0xff) as usize {
+            old >> 8
+        } else {
+            self.locations.iter().enumerate()
+                .filter(|&(i, &l)| i != index && l != 0)
+                .map(|(_, &l)| (l >> 8) + (l & 0xff))
+                .max()
+                .unwrap_or(2)
+        };
+        self.write_raw_chunk(index, start, &data, unix_time())?;
+        self.save_sidecar()
+    }
+
+    /// Write every chunk's checksum to `sidecar`, as `write_checksums` does,
+    /// and from then on update it whenever this writes a chunk.
+    pub fn keep_checksums(&mut self, sidecar: &Path) -> Result<(), RegionError> {
+        let checksums = self.checksums()?;
+        self.sidecar = Some(Sidecar { path: sidecar.to_path_buf(), checksums });
+        self.save_sidecar()
+    }
+
+    fn save_sidecar(&self) -> Result<(), RegionError> {
+        if let Some(ref sidecar) = self.sidecar {
+            write_sidecar(&sidecar.path, &sidecar.checksums)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the whole file with the chunks in `edits`, keyed by `(x, z)`,
+    /// replaced or added, and the other chunks packed after the header in
+    /// their current order. Untouched chunks are copied as they're stored,
+    /// without being decompressed; only the edited ones are compressed again.
+    ///
+    /// Returns the length the file should now have. `W` can't be truncated,
+    /// so anything after that (e.g. `File::set_len`) is left to the caller.
+    pub fn rewrite_preserving(&mut self, mut edits: HashMap<(u8, u8), RootValue>)
+            -> Result<u64, RegionError> {
+        let mut order: Vec<usize> = (0..CHUNK_COUNT).filter(|&i| self.locations[i] != 0).collect();
+        order.sort_by_key(|&i| self.locations[i] >> 8);
+        let mut chunks = Vec::with_capacity(order.len() + edits.len());
+        for index in order {
+            match edits.remove(&chunk_coords(index)) {
+                Some(chunk) => chunks.push((index, encode_chunk(&chunk)?, unix_time())),
+                None => {
+                    let data = self.raw_chunk(index)?.expect("present chunk");
+                    chunks.push((index, data, self.timestamps[index]));
+                },
+            }
+        }
+        // Chunks that weren't in the file before.
+        let mut added: Vec<_> = edits.into_iter().collect();
+        added.sort_by_key(|&((x, z), _)| (z, x));
+        for ((x, z), chunk) in added {
+            let index = chunk_index(u32::from(x), u32::from(z))?;
+            chunks.push((index, encode_chunk(&chunk)?, unix_time()));
+        }
+
+        self.locations = vec![0; CHUNK_COUNT];
+        self.timestamps = vec![0; CHUNK_COUNT];
+        if let Some(ref mut sidecar) = self.sidecar {
+            sidecar.checksums = vec![0; CHUNK_COUNT];
+        }
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(&[0; 2 * SECTOR_SIZE as usize])?;
+        let mut start = 2;
+        for (index, data, timestamp) in chunks {
+            self.write_raw_chunk(index, start, &data, timestamp)?;
+            start += sector_count(data.len()) as u32;
+        }
+        self.save_sidecar()?;
+        Ok(u64::from(start) * SECTOR_SIZE)
+    }
+
+    /// Move the chunks together after the header, leaving no free sectors
+    /// between them; see `rewrite_preserving`, which this is with no edits.
+    /// Returns the length the file should now have.
+    pub fn compact(&mut self) -> Result<u64, RegionError> {
+        self.rewrite_preserving(HashMap::new())
+    }
+
+    /// Write `data`, a chunk as `raw_chunk` returns it, to the sectors from
+    /// `start`, and point the tables at it.
+    fn write_raw_chunk(&mut self, index: usize, start: u32, data: &[u8], timestamp: u32)
+            -> Result<(), RegionError> {
+        let sectors = sector_count(data.len());
+        if sectors > 0xff {
+            return Err(RegionError::ChunkTooLarge(data.len()));
+        }
+        self.inner.seek(SeekFrom::Start(u64::from(start) * SECTOR_SIZE))?;
+        self.inner.write_all(data)?;
+        self.inner.write_all(&vec![0; sectors * SECTOR_SIZE as usize - data.len()])?;
+        if let Some(ref mut sidecar) = self.sidecar {
+            sidecar.checksums[index] = checksum(data);
+        }
+        self.set_location(index, start << 8 | sectors as u32, timestamp)
+    }
+
+    fn set_location(&mut self, index: usize, location: u32, timestamp: u32)
+            -> Result<(), RegionError> {
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+        self.inner.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.inner.write_u32::<BigEndian>(location)?;
+        self.inner.seek(SeekFrom::Start(SECTOR_SIZE + index as u64 * 4))?;
+        self.inner.write_u32::<BigEndian>(timestamp)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+//! Decoding and re-encoding the palette indices of chunk sections' block
+//! states and biomes.
+//!
+//! A section stores a palette of distinct block states (or biomes) and, for
+//! each block, an index into it. The indices are packed into a `LongArray`,
+//! each taking the fewest bits that can index the palette (but at least 4,
+//! for block states). Since 1.16, an index never spans two longs: each long
+//! holds `64 / bits_per_index` of them, starting from its low bits, and any
+//! leftover high bits are padding.
+//!
+//! Since 1.18 a section's block states are its `block_states.palette` and
+//! `block_states.data`, with `data` left out if the palette has a single
+//! entry; before, its `Palette` and `BlockStates`.
+
+use crate::nbt::{Compound, List, Value};
+
+
+/// Blocks in a section.
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+
+
+/// Unpack `count` indices of `bits_per_index` bits each from `data`, in
+/// the 1.16+ layout. A width of 0, as written for a palette with a single
+/// entry, gives `count` zeroes.
+///
+/// If `data` is too short to hold `count` indices, only those it holds are
+/// returned.
+///
+/// Panics if `bits_per_index` is more than 16.
+pub fn unpack_indices(data: &[i64], bits_per_index: u32, count: usize) -> Vec<u16> {
+    assert!(bits_per_index <= 16, "{} bits is too wide for a palette index", bits_per_index);
+    if bits_per_index == 0 {
+        return vec![0; count];
+    }
+    let per_long = (64 / bits_per_index) as usize;
+    let mask = (1u64 << bits_per_index) - 1;
+    let mut indices = Vec::with_capacity(count);
+    for &long in data {
+        let take = per_long.min(count - indices.len());
+        for i in 0..take {
+            indices.push(((long as u64 >> (i as u32 * bits_per_index)) & mask) as u16);
+        }
+        if indices.len() == count {
+            break;
+        }
+    }
+    indices
+}
+
+
+/// The inverse of `unpack_indices`: pack `indices` into longs, `bits_per_index`
+/// bits each, in the 1.16+ layout.
+///
+/// Panics if `bits_per_index` is 0 or more than 16.
+pub fn pack_indices(indices: &[u16], bits_per_index: u32) -> Vec<i64> {
+    assert!(
+        (1..=16).contains(&bits_per_index),
+        "{} bits is not a valid palette index width", bits_per_index,
+    );
+    let per_long = (64 / bits_per_index) as usize;
+    indices.chunks(per_long).map(|chunk| {
+        chunk.iter().enumerate().fold(0u64, |long, (i, &index)| {
+            long | u64::from(index) << (i as u32 * bits_per_index)
+        }) as i64
+    }).collect()
+}
+
+
+/// The bits per index for a block state palette of `len` entries.
+fn block_state_bits(len: usize) -> u32 {
+    (usize::BITS - len.saturating_sub(1).leading_zeros()).max(4)
+}
+
+
+/// A section's block states compound, and the keys of its palette and data.
+fn block_states(section: &Compound) -> (&Compound, &'static str, &'static str) {
+    match section.get("block_states") {
+        Some(Value::Compound(states)) => (states, "palette", "data"),
+        _ => (section, "Palette", "BlockStates"),
+    }
+}
+
+
+fn block_states_mut(section: &mut Compound) -> (&mut Compound, &'static str, &'static str) {
+    if !matches!(section.get("block_states"), Some(Value::Compound(_))) {
+        return (section, "Palette", "BlockStates");
+    }
+    match section.get_mut("block_states") {
+        Some(Value::Compound(states)) => (states, "palette", "data"),
+        _ => unreachable!(),
+    }
+}
+
+
+/// Every block's index into `palette`, from `data` (if any), in YZX order.
+/// `None` if any index is past the end of the palette.
+fn block_indices(palette: &[Compound], data: Option<&Value>) -> Option<Vec<u16>> {
+    let indices = match data {
+        Some(Value::LongArray(data)) => {
+            unpack_indices(data, block_state_bits(palette.len()), SECTION_VOLUME)
+        },
+        _ => vec![0; SECTION_VOLUME],
+    };
+    if indices.len() != SECTION_VOLUME
+            || indices.iter().any(|&i| i as usize >= palette.len()) {
+        return None;
+    }
+    Some(indices)
+}
+
+
+/// The block state at `x`, `y`, `z` (each 0–15) within a section in the
+/// 1.16+ layout, e.g. `{Name: "minecraft:stone"}`. `None` if the section
+/// has no block states, or they're corrupt.
+pub fn block_at(section: &Compound, x: usize, y: usize, z: usize) -> Option<&Compound> {
+    let (states, palette_key, data_key) = block_states(section);
+    let palette = match states.get(palette_key) {
+        Some(Value::List(List::Compound(palette))) => palette,
+        _ => return None,
+    };
+    let indices = block_indices(palette, states.get(data_key))?;
+    palette.get(indices[(y * 16 + z) * 16 + x] as usize)
+}
+
+
+/// Drop the entries of a section's block state palette that no block uses,
+/// and merge identical ones, such as are left behind by replacing blocks.
+/// The indices are remapped and repacked with the fewest bits the smaller
+/// palette needs.
+///
+/// Only the 1.16+ layout is understood. A section without block states, or
+/// whose indices don't fit its palette, is left alone.
+pub fn optimize_palette(section: &mut Compound) {
+    let (states, palette_key, data_key) = block_states_mut(section);
+    let palette = match states.get(palette_key) {
+        Some(Value::List(List::Compound(palette))) => palette,
+        _ => return,
+    };
+    let indices = match block_indices(palette, states.get(data_key)) {
+        Some(indices) => indices,
+        None => return,
+    };
+    let mut used = vec![false; palette.len()];
+    for &index in &indices {
+        used[index as usize] = true;
+    }
+    let mut new_palette: Vec<Compound> = Vec::new();
+    let mut remap = vec![0u16; palette.len()];
+    for (old, entry) in palette.iter().enumerate().filter(|&(i, _)| used[i]) {
+        remap[old] = match new_palette.iter().position(|e| e == entry) {
+            Some(new) => new as u16,
+            None => {
+                new_palette.push(entry.clone());
+                (new_palette.len() - 1) as u16
+            },
+        };
+    }
+    let indices: Vec<u16> = indices.iter().map(|&i| remap[i as usize]).collect();
+    if new_palette.len() == 1 && data_key == "data" {
+        states.remove(data_key);
+    } else {
+        let data = pack_indices(&indices, block_state_bits(new_palette.len()));
+        states.insert(data_key.to_string(), Value::LongArray(data));
+    }
+    states.insert(palette_key.to_string(), Value::List(List::Compound(new_palette)));
+}
@@ -0,0 +1,29 @@
+use crate::anvil::heightmap::decode_heightmap;
+
+
+#[test]
+fn test_decode_superflat_heightmap() {
+    // WORLD_SURFACE from a 1.18 superflat chunk: four layers of blocks from
+    // y = -64, so every column has height 4.
+    let mut data = vec![72198606942111748; 36];
+    data.push(537921540);
+    assert_eq!([4; 256], decode_heightmap(&data));
+}
+
+
+#[test]
+fn test_decode_heightmap() {
+    let mut data = vec![0; 37];
+    data[0] = 9218847010173223937;
+    let heights = decode_heightmap(&data);
+    assert_eq!([1, 2, 3, 100, 200, 383, 511, 0], heights[..8]);
+    assert!(heights[8..].iter().all(|&h| h == 0));
+}
+
+
+#[test]
+fn test_decode_short_heightmap() {
+    let heights = decode_heightmap(&[72198606942111748]);
+    assert_eq!([4; 7], heights[..7]);
+    assert!(heights[7..].iter().all(|&h| h == 0));
+}
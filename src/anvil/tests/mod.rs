@@ -0,0 +1,3 @@
+mod region_tests;
+mod palette_tests;
+mod heightmap_tests;
@@ -0,0 +1,116 @@
+use crate::anvil::palette::{block_at, optimize_palette, pack_indices, unpack_indices};
+use crate::nbt::{Compound, List, Value};
+use crate::world::chunk::validate_section_blockstates;
+
+
+#[test]
+fn test_unpack_indices() {
+    assert_eq!(
+        (0..16).collect::<Vec<u16>>(),
+        unpack_indices(&[-81985529216486896], 4, 16),
+    );
+}
+
+
+#[test]
+fn test_unpack_indices_skips_padding() {
+    // 5 bit indices, 12 to a long; the first long's top 4 bits are set, but
+    // are padding.
+    assert_eq!(
+        vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 31, 30],
+        unpack_indices(&[-707829019477668799, 991], 5, 14),
+    );
+}
+
+
+#[test]
+fn test_unpack_indices_short_data() {
+    let mut expected = vec![0; 16];
+    expected[..4].copy_from_slice(&[0, 1, 2, 3]);
+    assert_eq!(expected, unpack_indices(&[0x3210], 4, 20));
+    assert!(unpack_indices(&[], 4, 4096).is_empty());
+}
+
+
+#[test]
+fn test_unpack_single_entry_palette() {
+    assert_eq!(vec![0; 4096], unpack_indices(&[], 0, 4096));
+}
+
+
+#[test]
+fn test_pack_indices() {
+    let indices: Vec<u16> = (0..100).map(|i| i % 31).collect();
+    let packed = pack_indices(&indices, 5);
+    assert_eq!(9, packed.len());
+    assert_eq!(indices, unpack_indices(&packed, 5, 100));
+    assert_eq!(vec![-81985529216486896], pack_indices(&(0..16).collect::<Vec<_>>(), 4));
+}
+
+
+fn block(name: &str) -> Compound {
+    let mut block = Compound::new();
+    block.insert("Name".to_string(), Value::String(name.to_string()));
+    block
+}
+
+
+fn all_blocks(section: &Compound) -> Vec<Compound> {
+    (0..16).flat_map(|y| (0..16).flat_map(move |z| (0..16).map(move |x| (x, y, z))))
+        .map(|(x, y, z)| block_at(section, x, y, z).unwrap().clone())
+        .collect()
+}
+
+
+#[test]
+fn test_optimize_palette() {
+    // 17 block types, and a duplicate of the fourth: 5 bits an index.
+    let mut palette: Vec<Compound> = (0..17).map(|i| block(&format!("test:block_{}", i))).collect();
+    palette.push(block("test:block_3"));
+    let mut indices: Vec<u16> = (0..4096).map(|i| i % 18).collect();
+    // Replace every block_16 with block_0.
+    for index in &mut indices {
+        if *index == 16 {
+            *index = 0;
+        }
+    }
+    let mut section = Compound::new();
+    section.insert("Palette".to_string(), Value::List(List::Compound(palette)));
+    section.insert("BlockStates".to_string(), Value::LongArray(pack_indices(&indices, 5)));
+    let before = all_blocks(&section);
+
+    optimize_palette(&mut section);
+    match section.get("Palette") {
+        Some(Value::List(List::Compound(palette))) => assert_eq!(16, palette.len()),
+        other => panic!("Expected a palette, got {:?}", other),
+    }
+    match section.get("BlockStates") {
+        Some(Value::LongArray(data)) => assert_eq!(256, data.len()),
+        other => panic!("Expected block states, got {:?}", other),
+    }
+    assert_eq!(before, all_blocks(&section));
+    assert_eq!(Ok(()), validate_section_blockstates(&section, 2586));
+}
+
+
+#[test]
+fn test_optimize_palette_single_entry() {
+    let palette = vec![block("minecraft:air"), block("minecraft:stone")];
+    let mut states = Compound::new();
+    states.insert("palette".to_string(), Value::List(List::Compound(palette)));
+    states.insert("data".to_string(), Value::LongArray(pack_indices(&[1; 4096], 4)));
+    let mut section = Compound::new();
+    section.insert("block_states".to_string(), Value::Compound(states));
+
+    optimize_palette(&mut section);
+    let states = match section.get("block_states") {
+        Some(Value::Compound(states)) => states,
+        other => panic!("Expected block states, got {:?}", other),
+    };
+    assert_eq!(
+        Some(&Value::List(List::Compound(vec![block("minecraft:stone")]))),
+        states.get("palette"),
+    );
+    assert_eq!(None, states.get("data"));
+    assert_eq!(Some(&block("minecraft:stone")), block_at(&section, 15, 15, 15));
+}
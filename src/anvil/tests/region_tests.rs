@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Cursor;
+use std::process;
+
+use crate::anvil::{RegionError, RegionFile, SectorState};
+use crate::nbt::builder::CompoundBuilder;
+use crate::nbt::RootValue;
+
+
+const REGION: &[u8] = include_bytes!("r.0.0.mca");
+
+
+fn open_region(bytes: &[u8]) -> RegionFile<Cursor<&[u8]>> {
+    match RegionFile::new(Cursor::new(bytes)) {
+        Ok(region) => region,
+        Err(err) => panic!("{:?}", err),
+    }
+}
+
+
+fn read_chunk(region: &mut RegionFile<Cursor<&[u8]>>, x: u32, z: u32) -> RootValue {
+    match region.chunk(x, z) {
+        Ok(Some(chunk)) => chunk,
+        other => panic!("Expected chunk {}, {}; got {:?}", x, z, other),
+    }
+}
+
+
+fn make_chunk(x: i32, z: i32, data: Vec<i64>) -> RootValue {
+    RootValue {
+        name: String::new(),
+        value: CompoundBuilder::new()
+            .int("DataVersion", 3465)
+            .int("xPos", x)
+            .int("zPos", z)
+            .long_array("Data", data)
+            .build(),
+    }
+}
+
+
+/// The fixture, with chunk 1, 2 taken out of the location table, leaving a
+/// free sector between the others.
+fn gappy_region() -> Vec<u8> {
+    let mut bytes = REGION.to_vec();
+    let index = 1 + 2 * 32;
+    bytes[index * 4..index * 4 + 4].copy_from_slice(&[0; 4]);
+    bytes
+}
+
+
+#[test]
+fn test_read_chunks() {
+    let mut region = open_region(REGION);
+    // Zlib, gzip and uncompressed, respectively.
+    for &(x, z) in &[(0, 0), (1, 2), (3, 0)] {
+        let chunk = read_chunk(&mut region, x, z);
+        assert_eq!(Some(3465), chunk.value.get_path("DataVersion").and_then(|v| v.as_i32()));
+        assert_eq!(Some(x as i32), chunk.value.get_path("xPos").and_then(|v| v.as_i32()));
+        assert_eq!(Some(z as i32), chunk.value.get_path("zPos").and_then(|v| v.as_i32()));
+    }
+}
+
+
+#[test]
+fn test_missing_chunk() {
+    let mut region = open_region(REGION);
+    assert!(!region.has_chunk(31, 31).unwrap());
+    assert!(region.chunk(31, 31).unwrap().is_none());
+    assert_eq!(None, region.timestamp(31, 31).unwrap());
+    assert_eq!(Some(1_700_000_000), region.timestamp(0, 0).unwrap());
+}
+
+
+#[test]
+fn test_chunk_out_of_range() {
+    let mut region = open_region(REGION);
+    match region.chunk(32, 0) {
+        Err(RegionError::ChunkOutOfRange { x: 32, z: 0 }) => (),
+        other => panic!("Expected ChunkOutOfRange, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_external_chunk() {
+    let mut region = open_region(REGION);
+    match region.chunk(5, 5) {
+        Err(RegionError::ExternalChunk) => (),
+        other => panic!("Expected ExternalChunk, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_invalid_chunk_length() {
+    let mut bytes = REGION.to_vec();
+    // Chunk 0, 0 is in the first sector after the header.
+    bytes[8192..8196].copy_from_slice(&5000u32.to_be_bytes());
+    let mut region = open_region(&bytes);
+    match region.chunk(0, 0) {
+        Err(RegionError::InvalidChunkLength(5000)) => (),
+        other => panic!("Expected InvalidChunkLength, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_zero_length_chunk() {
+    let mut bytes = REGION.to_vec();
+    bytes[8192..8196].copy_from_slice(&[0; 4]);
+    let mut region = open_region(&bytes);
+    match region.chunk(0, 0) {
+        Err(RegionError::ZeroLengthChunk) => (),
+        other => panic!("Expected ZeroLengthChunk, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_sector_padding_ignored() {
+    let mut bytes = REGION.to_vec();
+    // Fill the padding after each chunk's declared length with junk.
+    for sector in 2..bytes.len() / 4096 {
+        let start = sector * 4096;
+        let length = u32::from_be_bytes([
+            bytes[start], bytes[start + 1], bytes[start + 2], bytes[start + 3],
+        ]) as usize;
+        for byte in &mut bytes[start + 4 + length..start + 4096] {
+            *byte = 0xff;
+        }
+    }
+    let mut region = open_region(&bytes);
+    for &(x, z) in &[(0, 0), (1, 2), (3, 0)] {
+        read_chunk(&mut region, x, z);
+    }
+}
+
+
+#[test]
+fn test_truncated_header() {
+    match RegionFile::new(Cursor::new(&REGION[..100])) {
+        Err(RegionError::IoError(_)) => (),
+        other => panic!("Expected IoError, got {:?}", other.map(|_| ())),
+    }
+}
+
+
+#[test]
+fn test_create_and_write() {
+    let mut region = RegionFile::create(Cursor::new(Vec::new())).unwrap();
+    let first = make_chunk(0, 0, vec![1, 2, 3]);
+    let second = make_chunk(4, 7, vec![]);
+    // Too big for the sector it replaces, even compressed.
+    let grown = make_chunk(0, 0, (0..2000).map(|i| i * 0x0123_4567_89ab).collect());
+    region.write_chunk(0, 0, &first).unwrap();
+    region.write_chunk(4, 7, &second).unwrap();
+    assert_eq!(first, region.chunk(0, 0).unwrap().unwrap());
+    region.write_chunk(0, 0, &grown).unwrap();
+
+    let bytes = region.into_inner().into_inner();
+    assert_eq!(0, bytes.len() % 4096);
+    // The header, both original chunks, and the grown one after them.
+    assert!(bytes.len() >= 6 * 4096);
+    let mut region = open_region(&bytes);
+    assert_eq!(grown, read_chunk(&mut region, 0, 0));
+    assert_eq!(second, read_chunk(&mut region, 4, 7));
+    assert!(region.timestamp(4, 7).unwrap().is_some());
+    assert!(!region.has_chunk(1, 0).unwrap());
+}
+
+
+#[test]
+fn test_rewrite_preserving() {
+    let mut region = RegionFile::new(Cursor::new(REGION.to_vec())).unwrap();
+    let untouched = [(0, 0), (3, 0), (5, 5)];
+    let before: Vec<_> = untouched.iter()
+        .map(|&(x, z)| region.raw_chunk((x + z * 32) as usize).unwrap())
+        .collect();
+    let edited = make_chunk(1, 2, vec![42]);
+    let added = make_chunk(9, 9, vec![]);
+    let mut edits = HashMap::new();
+    edits.insert((1, 2), edited.clone());
+    edits.insert((9, 9), added.clone());
+    let length = region.rewrite_preserving(edits).unwrap();
+
+    let mut bytes = region.into_inner().into_inner();
+    bytes.truncate(length as usize);
+    let mut region = RegionFile::new(Cursor::new(bytes)).unwrap();
+    for (&(x, z), raw) in untouched.iter().zip(before) {
+        assert_eq!(raw, region.raw_chunk((x + z * 32) as usize).unwrap());
+    }
+    assert_eq!(Some(1_700_000_000), region.timestamp(0, 0).unwrap());
+    assert_eq!(Some(edited), region.chunk(1, 2).unwrap());
+    assert_eq!(Some(added), region.chunk(9, 9).unwrap());
+}
+
+
+#[test]
+fn test_sector_map() {
+    let bytes = gappy_region();
+    let region = open_region(&bytes);
+    assert_eq!(
+        vec![
+            SectorState::Header,
+            SectorState::Header,
+            SectorState::Used { chunk: (0, 0) },
+            SectorState::Free,
+            SectorState::Used { chunk: (3, 0) },
+            SectorState::Used { chunk: (5, 5) },
+        ],
+        region.sector_map(),
+    );
+    let empty = RegionFile::create(Cursor::new(Vec::new())).unwrap();
+    assert_eq!(vec![SectorState::Header; 2], empty.sector_map());
+}
+
+
+#[test]
+fn test_compact() {
+    let bytes = gappy_region();
+    let mut region = RegionFile::new(Cursor::new(bytes.clone())).unwrap();
+    let length = region.compact().unwrap();
+    let mut compacted = region.into_inner().into_inner();
+    compacted.truncate(length as usize);
+    assert!(compacted.len() < bytes.len());
+
+    let mut before = open_region(&bytes);
+    let mut after = open_region(&compacted);
+    assert!(!after.sector_map().contains(&SectorState::Free));
+    for &(x, z) in &[(0, 0), (3, 0)] {
+        assert_eq!(read_chunk(&mut before, x, z), read_chunk(&mut after, x, z));
+        assert_eq!(before.timestamp(x, z).unwrap(), after.timestamp(x, z).unwrap());
+    }
+    assert!(!after.has_chunk(1, 2).unwrap());
+    match after.chunk(5, 5) {
+        Err(RegionError::ExternalChunk) => (),
+        other => panic!("Expected ExternalChunk, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_checksums() {
+    let sidecar = env::temp_dir().join(format!("libminecraft-{}.crc", process::id()));
+    let mut region = RegionFile::create(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(0, 0, &make_chunk(0, 0, vec![1, 2, 3])).unwrap();
+    region.keep_checksums(&sidecar).unwrap();
+    // Written after the sidecar, which should keep up.
+    region.write_chunk(2, 1, &make_chunk(2, 1, vec![4])).unwrap();
+    assert_eq!(Vec::<(u8, u8)>::new(), region.verify_checksums(&sidecar).unwrap());
+
+    let mut bytes = region.into_inner().into_inner();
+    // Flip a bit in chunk 0, 0's compressed data.
+    bytes[8192 + 10] ^= 0x10;
+    let mut region = RegionFile::new(Cursor::new(&bytes[..])).unwrap();
+    let result = region.verify_checksums(&sidecar);
+    fs::remove_file(&sidecar).unwrap();
+    assert_eq!(vec![(0, 0)], result.unwrap());
+}
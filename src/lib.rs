@@ -1 +1,4 @@
+pub mod anvil;
 pub mod nbt;
+pub mod protocol;
+pub mod world;
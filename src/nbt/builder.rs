@@ -0,0 +1,112 @@
+//! Building compounds by hand, e.g.
+//!
+//! ```
+//! use minecraft::nbt::builder::CompoundBuilder;
+//!
+//! let player = CompoundBuilder::new()
+//!     .string("name", "Steve")
+//!     .float("Health", 20.0)
+//!     .list("Pos", vec![0.5, 64.0, 0.5])
+//!     .compound("abilities", CompoundBuilder::new().byte("mayfly", 1))
+//!     .build();
+//! ```
+
+use super::{Compound, List, Value};
+
+
+#[derive(Debug, Default, Clone)]
+pub struct CompoundBuilder {
+    compound: Compound,
+}
+
+
+impl CompoundBuilder {
+    pub fn new() -> CompoundBuilder {
+        CompoundBuilder::default()
+    }
+
+    /// Set `key` to `value`, replacing any value already there.
+    pub fn value<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> CompoundBuilder {
+        self.compound.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn byte<K: Into<String>>(self, key: K, value: i8) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn short<K: Into<String>>(self, key: K, value: i16) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn int<K: Into<String>>(self, key: K, value: i32) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn long<K: Into<String>>(self, key: K, value: i64) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn float<K: Into<String>>(self, key: K, value: f32) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn double<K: Into<String>>(self, key: K, value: f64) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    /// A `TAG_Byte` of 1 or 0.
+    pub fn bool<K: Into<String>>(self, key: K, value: bool) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn string<K: Into<String>, S: Into<String>>(self, key: K, value: S) -> CompoundBuilder {
+        self.value(key, value.into())
+    }
+
+    pub fn byte_array<K: Into<String>>(self, key: K, value: Vec<u8>) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn int_array<K: Into<String>>(self, key: K, value: Vec<i32>) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    pub fn long_array<K: Into<String>>(self, key: K, value: Vec<i64>) -> CompoundBuilder {
+        self.value(key, value)
+    }
+
+    /// A list, e.g. from a `Vec` of any type a list can hold.
+    pub fn list<K: Into<String>, L: Into<List>>(self, key: K, value: L) -> CompoundBuilder {
+        self.value(key, value.into())
+    }
+
+    /// A nested compound, e.g. from another `CompoundBuilder`.
+    pub fn compound<K: Into<String>, C: Into<Compound>>(self, key: K, value: C)
+            -> CompoundBuilder {
+        self.value(key, value.into())
+    }
+
+    /// The compound, as a `Value::Compound`.
+    pub fn build(self) -> Value {
+        Value::Compound(self.compound)
+    }
+
+    pub fn build_compound(self) -> Compound {
+        self.compound
+    }
+}
+
+
+impl From<CompoundBuilder> for Compound {
+    fn from(builder: CompoundBuilder) -> Compound {
+        builder.compound
+    }
+}
+
+
+impl From<CompoundBuilder> for Value {
+    fn from(builder: CompoundBuilder) -> Value {
+        builder.build()
+    }
+}
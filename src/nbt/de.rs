@@ -0,0 +1,274 @@
+//! Deserializing parsed NBT into Rust types with serde.
+//!
+//! Compounds deserialize as structs or maps, lists and arrays as sequences.
+//! Any numeric tag deserializes into any Rust number it fits in, so a
+//! `TAG_Byte` can fill an `i32` field; a `TAG_Byte` can also fill a `bool`.
+//! Byte arrays hold unsigned bytes, as `Value::ByteArray` does.
+
+use std::error;
+use std::fmt;
+
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer, MapDeserializer};
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::{Compound, List, Value};
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DeserializeError {
+    /// The message from a `Deserialize` implementation, e.g. for a missing
+    /// field or a value of the wrong type.
+    Message(String),
+}
+
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeserializeError::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+
+impl error::Error for DeserializeError {}
+
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> DeserializeError {
+        DeserializeError::Message(msg.to_string())
+    }
+}
+
+
+/// Where the deserializer is in the tree. Lists don't store their elements
+/// as `Value`s, so this covers the pieces they're made of too.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    /// A number.
+    Number(&'a Value),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    Ints(&'a [i32]),
+    Longs(&'a [i64]),
+    List(&'a List),
+    Compound(&'a Compound),
+}
+
+
+impl<'a> Node<'a> {
+    fn from_value(value: &'a Value) -> Node<'a> {
+        match value {
+            Value::ByteArray(bytes) => Node::Bytes(bytes),
+            Value::String(s) => Node::Str(s),
+            Value::List(list) => Node::List(list),
+            Value::Compound(compound) => Node::Compound(compound),
+            Value::IntArray(ints) => Node::Ints(ints),
+            Value::LongArray(longs) => Node::Longs(longs),
+            _ => Node::Number(value),
+        }
+    }
+}
+
+
+/// A `serde::Deserializer` reading from a `Value`.
+#[derive(Clone, Copy)]
+pub struct ValueDeserializer<'a> {
+    node: Node<'a>,
+}
+
+
+impl<'a> ValueDeserializer<'a> {
+    pub fn new(value: &'a Value) -> ValueDeserializer<'a> {
+        ValueDeserializer { node: Node::from_value(value) }
+    }
+
+    fn compound_map(compound: &'a Compound) -> MapDeserializer<
+            'a,
+            impl Iterator<Item = (BorrowedStrDeserializer<'a, DeserializeError>, Self)>,
+            DeserializeError> {
+        MapDeserializer::new(compound.iter().map(|(key, value)| {
+            (BorrowedStrDeserializer::new(key.as_str()), ValueDeserializer::new(value))
+        }))
+    }
+}
+
+
+/// The elements of a list, or of an array.
+struct ElementAccess<'a> {
+    list: &'a List,
+    index: usize,
+}
+
+
+impl<'de> SeqAccess<'de> for ElementAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T)
+            -> Result<Option<T::Value>, DeserializeError> {
+        let i = self.index;
+        if i >= self.list.len() {
+            return Ok(None);
+        }
+        self.index += 1;
+        let node = |node| ValueDeserializer { node };
+        match self.list {
+            List::Empty(_) => unreachable!(),
+            List::Byte(items) => seed.deserialize(items[i].into_deserializer()),
+            List::Short(items) => seed.deserialize(items[i].into_deserializer()),
+            List::Int(items) => seed.deserialize(items[i].into_deserializer()),
+            List::Long(items) => seed.deserialize(items[i].into_deserializer()),
+            List::Float(items) => seed.deserialize(items[i].into_deserializer()),
+            List::Double(items) => seed.deserialize(items[i].into_deserializer()),
+            List::ByteArray(items) => seed.deserialize(node(Node::Bytes(&items[i]))),
+            List::String(items) => seed.deserialize(node(Node::Str(&items[i]))),
+            List::List(items) => seed.deserialize(node(Node::List(&items[i]))),
+            List::Compound(items) => seed.deserialize(node(Node::Compound(&items[i]))),
+            List::IntArray(items) => seed.deserialize(node(Node::Ints(&items[i]))),
+            List::LongArray(items) => seed.deserialize(node(Node::Longs(&items[i]))),
+        }.map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.list.len() - self.index)
+    }
+}
+
+
+/// The elements of a byte, int or long array.
+struct ArrayAccess<'a, T> {
+    items: &'a [T],
+    index: usize,
+}
+
+
+impl<'de, T> SeqAccess<'de> for ArrayAccess<'de, T>
+        where T: Copy + IntoDeserializer<'de, DeserializeError> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S)
+            -> Result<Option<S::Value>, DeserializeError> {
+        match self.items.get(self.index) {
+            Some(item) => {
+                self.index += 1;
+                seed.deserialize(item.into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len() - self.index)
+    }
+}
+
+
+fn visit_array<'de, T, V>(items: &'de [T], visitor: V) -> Result<V::Value, DeserializeError>
+        where T: Copy + IntoDeserializer<'de, DeserializeError>, V: Visitor<'de> {
+    visitor.visit_seq(ArrayAccess { items, index: 0 })
+}
+
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        match self.node {
+            Node::Number(Value::Byte(v)) => visitor.visit_i8(*v),
+            Node::Number(Value::Short(v)) => visitor.visit_i16(*v),
+            Node::Number(Value::Int(v)) => visitor.visit_i32(*v),
+            Node::Number(Value::Long(v)) => visitor.visit_i64(*v),
+            Node::Number(Value::Float(v)) => visitor.visit_f32(*v),
+            Node::Number(Value::Double(v)) => visitor.visit_f64(*v),
+            Node::Number(_) => unreachable!(),
+            Node::Str(s) => visitor.visit_borrowed_str(s),
+            Node::Bytes(bytes) => visit_array(bytes, visitor),
+            Node::Ints(ints) => visit_array(ints, visitor),
+            Node::Longs(longs) => visit_array(longs, visitor),
+            Node::List(list) => visitor.visit_seq(ElementAccess { list, index: 0 }),
+            Node::Compound(compound) => {
+                let mut map = ValueDeserializer::compound_map(compound);
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        match self.node {
+            Node::Number(Value::Byte(v)) => visitor.visit_bool(*v != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        match self.node {
+            Node::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// NBT has no null; a missing compound entry is how a `None` is stored,
+    /// so anything present is a `Some`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are read from strings, and other variants from a
+    /// compound with a single entry, named for the variant.
+    fn deserialize_enum<V: Visitor<'de>>(
+            self, _name: &'static str, _variants: &'static [&'static str], visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        match self.node {
+            Node::Str(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+            Node::Compound(compound) => {
+                let map = ValueDeserializer::compound_map(compound);
+                visitor.visit_enum(MapAccessDeserializer::new(map))
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V)
+            -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for ValueDeserializer<'de> {
+    type Deserializer = ValueDeserializer<'de>;
+
+    fn into_deserializer(self) -> ValueDeserializer<'de> {
+        self
+    }
+}
+
+
+/// Deserialize a `T` from a parsed `Value`, such as `RootValue::value`.
+/// Strings and byte arrays may be borrowed from `value`.
+pub fn from_nbt_value<'de, T: Deserialize<'de>>(value: &'de Value)
+        -> Result<T, DeserializeError> {
+    T::deserialize(ValueDeserializer::new(value))
+}
@@ -0,0 +1,100 @@
+//! Flattening an NBT tree into path → text pairs, e.g. for storing in a
+//! database or diffing.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use super::{List, RootValue, Value};
+
+
+pub(super) fn join_path(prefix: &str, segment: &str) -> String {
+    if prefix.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{}.{}", prefix, segment)
+    }
+}
+
+
+fn comma_list<T: Display>(items: &[T]) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(",")
+}
+
+
+fn flatten_items<T, F>(prefix: &str, items: &[T], out: &mut BTreeMap<String, String>, f: F)
+        where F: Fn(&str, &T, &mut BTreeMap<String, String>) {
+    for (index, item) in items.iter().enumerate() {
+        f(&join_path(prefix, &index.to_string()), item, out);
+    }
+}
+
+
+fn insert_leaf<T: ToString>(path: &str, item: &T, out: &mut BTreeMap<String, String>) {
+    out.insert(path.to_string(), item.to_string());
+}
+
+
+fn flatten_list(prefix: &str, list: &List, out: &mut BTreeMap<String, String>) {
+    match list {
+        List::Empty(_) => (),
+        List::Byte(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::Short(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::Int(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::Long(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::Float(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::Double(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::ByteArray(items) => flatten_items(
+            prefix, items, out, |path, item, out| insert_leaf(path, &comma_list(item), out),
+        ),
+        List::String(items) => flatten_items(prefix, items, out, insert_leaf),
+        List::List(items) => flatten_items(prefix, items, out, flatten_list),
+        List::Compound(items) => flatten_items(
+            prefix, items, out, |path, item, out| {
+                for (key, value) in item {
+                    flatten_value(&join_path(path, key), value, out);
+                }
+            },
+        ),
+        List::IntArray(items) => flatten_items(
+            prefix, items, out, |path, item, out| insert_leaf(path, &comma_list(item), out),
+        ),
+        List::LongArray(items) => flatten_items(
+            prefix, items, out, |path, item, out| insert_leaf(path, &comma_list(item), out),
+        ),
+    }
+}
+
+
+fn flatten_value(path: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Byte(v) => insert_leaf(path, v, out),
+        Value::Short(v) => insert_leaf(path, v, out),
+        Value::Int(v) => insert_leaf(path, v, out),
+        Value::Long(v) => insert_leaf(path, v, out),
+        Value::Float(v) => insert_leaf(path, v, out),
+        Value::Double(v) => insert_leaf(path, v, out),
+        Value::ByteArray(v) => insert_leaf(path, &comma_list(v), out),
+        Value::String(v) => insert_leaf(path, v, out),
+        Value::List(list) => flatten_list(path, list, out),
+        Value::Compound(compound) => {
+            for (key, value) in compound {
+                flatten_value(&join_path(path, key), value, out);
+            }
+        },
+        Value::IntArray(v) => insert_leaf(path, &comma_list(v), out),
+        Value::LongArray(v) => insert_leaf(path, &comma_list(v), out),
+    }
+}
+
+
+/// Flatten a tree into a map from each leaf's path to its value as text.
+///
+/// Paths are relative to the root value (the root's name is not included),
+/// with compound keys and list indices joined by `.`, e.g. `"Pos.0"`.
+/// Numbers are written in decimal and arrays as comma-separated lists.
+/// Empty lists and compounds have no leaves, and so do not appear.
+pub fn to_flat_map(root: &RootValue) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    flatten_value("", &root.value, &mut out);
+    out
+}
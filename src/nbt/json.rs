@@ -0,0 +1,82 @@
+//! Conversion to `serde_json::Value`, for handing NBT to JSON tools.
+//!
+//! The conversion is lossy: JSON has a single number type, so a `Byte` and a
+//! `Long` of the same value come out the same, and arrays look like lists.
+//! Non-finite floats, which JSON can't represent, become `null`.
+
+use serde_json::{Map, Number};
+
+use super::{Compound, List, Value};
+
+
+fn json_array<T: Copy>(items: &[T], convert: impl Fn(T) -> serde_json::Value)
+        -> serde_json::Value {
+    serde_json::Value::Array(items.iter().map(|item| convert(*item)).collect())
+}
+
+
+fn json_float(f: f64) -> serde_json::Value {
+    Number::from_f64(f).map_or(serde_json::Value::Null, serde_json::Value::Number)
+}
+
+
+/// Byte arrays are signed, as the game treats them, to match `Value::Byte`.
+fn json_byte_array(bytes: &[u8]) -> serde_json::Value {
+    json_array(bytes, |b| serde_json::Value::from(b as i8))
+}
+
+
+fn json_object(compound: &Compound) -> serde_json::Value {
+    serde_json::Value::Object(
+        compound.iter().map(|(key, value)| (key.clone(), value.into())).collect::<Map<_, _>>()
+    )
+}
+
+
+impl From<&List> for serde_json::Value {
+    fn from(list: &List) -> serde_json::Value {
+        match list {
+            List::Empty(_) => serde_json::Value::Array(Vec::new()),
+            List::Byte(v) => json_array(v, serde_json::Value::from),
+            List::Short(v) => json_array(v, serde_json::Value::from),
+            List::Int(v) => json_array(v, serde_json::Value::from),
+            List::Long(v) => json_array(v, serde_json::Value::from),
+            List::Float(v) => json_array(v, |f| json_float(f64::from(f))),
+            List::Double(v) => json_array(v, json_float),
+            List::ByteArray(v) => {
+                serde_json::Value::Array(v.iter().map(|a| json_byte_array(a)).collect())
+            },
+            List::String(v) => {
+                serde_json::Value::Array(v.iter().map(|s| s.as_str().into()).collect())
+            },
+            List::List(v) => serde_json::Value::Array(v.iter().map(Into::into).collect()),
+            List::Compound(v) => serde_json::Value::Array(v.iter().map(json_object).collect()),
+            List::IntArray(v) => serde_json::Value::Array(
+                v.iter().map(|a| json_array(a, serde_json::Value::from)).collect()
+            ),
+            List::LongArray(v) => serde_json::Value::Array(
+                v.iter().map(|a| json_array(a, serde_json::Value::from)).collect()
+            ),
+        }
+    }
+}
+
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> serde_json::Value {
+        match value {
+            Value::Byte(v) => serde_json::Value::from(*v),
+            Value::Short(v) => serde_json::Value::from(*v),
+            Value::Int(v) => serde_json::Value::from(*v),
+            Value::Long(v) => serde_json::Value::from(*v),
+            Value::Float(v) => json_float(f64::from(*v)),
+            Value::Double(v) => json_float(*v),
+            Value::ByteArray(v) => json_byte_array(v),
+            Value::String(v) => serde_json::Value::from(v.as_str()),
+            Value::List(v) => v.into(),
+            Value::Compound(v) => json_object(v),
+            Value::IntArray(v) => json_array(v, serde_json::Value::from),
+            Value::LongArray(v) => json_array(v, serde_json::Value::from),
+        }
+    }
+}
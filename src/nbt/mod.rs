@@ -1,26 +1,61 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::slice;
 
 
+pub mod builder;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod flat;
+#[cfg(feature = "serde_json")]
+pub mod json;
 pub mod reader;
+#[cfg(feature = "serde")]
+pub mod ser;
+pub mod snbt;
+pub mod tape;
+pub mod writer;
 #[cfg(test)]
 mod tests;
 
 
-const TAG_END: u8 = 0;
-const TAG_BYTE: u8 = 1;
-const TAG_SHORT: u8 = 2;
-const TAG_INT: u8 = 3;
-const TAG_LONG: u8 = 4;
-const TAG_FLOAT: u8 = 5;
-const TAG_DOUBLE: u8 = 6;
-const TAG_BYTE_ARRAY: u8 = 7;
-const TAG_STRING: u8 = 8;
-const TAG_LIST: u8 = 9;
-const TAG_COMPOUND: u8 = 10;
-const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_END: u8 = 0;
+pub const TAG_BYTE: u8 = 1;
+pub const TAG_SHORT: u8 = 2;
+pub const TAG_INT: u8 = 3;
+pub const TAG_LONG: u8 = 4;
+pub const TAG_FLOAT: u8 = 5;
+pub const TAG_DOUBLE: u8 = 6;
+pub const TAG_BYTE_ARRAY: u8 = 7;
+pub const TAG_STRING: u8 = 8;
+pub const TAG_LIST: u8 = 9;
+pub const TAG_COMPOUND: u8 = 10;
+pub const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_LONG_ARRAY: u8 = 12;
 
 
-#[derive(Debug)]
+/// The byte order of numbers and length prefixes in an NBT stream. Java
+/// Edition is big-endian; Bedrock Edition is little-endian.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+
+impl Default for Endianness {
+    /// Java Edition's.
+    fn default() -> Endianness {
+        Endianness::Big
+    }
+}
+
+
+/// Equality is plain `f32`/`f64` equality for floats, so a value containing
+/// a NaN isn't equal to itself.
+#[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     Byte(i8),
     Short(i16),
@@ -33,12 +68,13 @@ pub enum Value {
     List(List),
     Compound(Compound),
     IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
 }
 
 
 /// The root value in NBT files has a name associated with it. It is almost
 /// always the empty string.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct RootValue {
     pub name: String,
     pub value: Value,
@@ -48,13 +84,13 @@ pub struct RootValue {
 pub type Compound = HashMap<String, Value>;
 
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum List {
-    // Sometimes, TAG_Lists of size zero have an internal element type of
-    // TAG_End. I.e., the list is a list of "TAG_End"s, but that makes no
-    // sense. They're only considered valid at size zero, so there's no
-    // associated vector.
-    Empty,
+    // A TAG_List of size zero, with the element type it was stored with.
+    // That's often TAG_End (i.e., a list of "TAG_End"s, which makes no sense,
+    // and is only considered valid at size zero), but keeping it means an
+    // empty list of, say, compounds is written back as one.
+    Empty(u8),
     Byte(Vec<i8>),
     Short(Vec<i16>),
     Int(Vec<i32>),
@@ -66,4 +102,720 @@ pub enum List {
     List(Vec<List>),
     Compound(Vec<Compound>),
     IntArray(Vec<Vec<i32>>),
+    LongArray(Vec<Vec<i64>>),
+}
+
+
+impl Value {
+    /// The `TAG_*` constant for this value's type.
+    pub fn tag_type(&self) -> u8 {
+        match self {
+            Value::Byte(_) => TAG_BYTE,
+            Value::Short(_) => TAG_SHORT,
+            Value::Int(_) => TAG_INT,
+            Value::Long(_) => TAG_LONG,
+            Value::Float(_) => TAG_FLOAT,
+            Value::Double(_) => TAG_DOUBLE,
+            Value::ByteArray(_) => TAG_BYTE_ARRAY,
+            Value::String(_) => TAG_STRING,
+            Value::List(_) => TAG_LIST,
+            Value::Compound(_) => TAG_COMPOUND,
+            Value::IntArray(_) => TAG_INT_ARRAY,
+            Value::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// Minecraft stores booleans as bytes; treat any nonzero byte as true.
+    /// Returns `None` for anything other than a byte.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Byte(b) => Some(*b != 0),
+            _ => None,
+        }
+    }
+
+    // The accessors below return `None` unless the value is of exactly that
+    // type; e.g. `as_i64` doesn't widen a `Value::Int`.
+
+    pub fn as_i8(&self) -> Option<i8> {
+        match self {
+            Value::Byte(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        match self {
+            Value::Short(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte_array(&self) -> Option<&[u8]> {
+        match self {
+            Value::ByteArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&List> {
+        match self {
+            Value::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Value::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_int_array(&self) -> Option<&[i32]> {
+        match self {
+            Value::IntArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Value::LongArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Look up a nested value by a dot-separated path, e.g.
+    /// `"Data.Player.Pos.0"`: each segment is a compound key, or an index
+    /// into a list. The same paths are used by `flat::to_flat_map`.
+    ///
+    /// Returns `None` if a segment is missing, or the value there can't be
+    /// indexed. Lists don't store their elements as `Value`s, so a path
+    /// ending at a list element gives a copy of it.
+    pub fn get_path(&self, path: &str) -> Option<Cow<'_, Value>> {
+        let mut node = PathNode::Value(self);
+        for segment in path.split('.') {
+            node = match node {
+                PathNode::Value(Value::Compound(compound)) | PathNode::Compound(compound) => {
+                    PathNode::Value(compound.get(segment)?)
+                },
+                PathNode::Value(Value::List(list)) | PathNode::List(list) => {
+                    list.path_element(segment.parse().ok()?)?
+                },
+                _ => return None,
+            };
+        }
+        Some(match node {
+            PathNode::Value(value) => Cow::Borrowed(value),
+            PathNode::Compound(compound) => Cow::Owned(Value::Compound(compound.clone())),
+            PathNode::List(list) => Cow::Owned(Value::List(list.clone())),
+            PathNode::Element(value) => Cow::Owned(value),
+        })
+    }
+
+    /// Call `f` with every value in the tree, parents before their children,
+    /// along with its path as used by `get_path` (`""` for `self`).
+    ///
+    /// Compound entries are visited in the `Compound`'s iteration order.
+    /// Lists don't store their elements as `Value`s, so `f` gets a copy of
+    /// each list element.
+    pub fn walk(&self, f: &mut dyn FnMut(&str, &Value)) {
+        walk_value("", self, f);
+    }
+
+    /// Replace every string value equal to `from` with `to`, anywhere in the
+    /// tree (including inside lists). Compound keys are left alone. Returns
+    /// the number of strings replaced.
+    pub fn replace_strings(&mut self, from: &str, to: &str) -> usize {
+        match self {
+            Value::String(s) => replace_string(s, from, to),
+            Value::List(list) => list.replace_strings(from, to),
+            Value::Compound(compound) => replace_strings_in_compound(compound, from, to),
+            _ => 0,
+        }
+    }
+
+    /// Make every float and double in the tree (including inside lists) that
+    /// is NaN the same NaN, and if `zero` is set, every `-0.0` a `0.0`, so
+    /// that trees written by different tools can be compared bit for bit
+    /// with `canonical_eq`. The derived `PartialEq` still never finds NaNs
+    /// equal.
+    pub fn canonicalize_floats(&mut self, zero: bool) {
+        match self {
+            Value::Float(v) => *v = canonical_f32(*v, zero),
+            Value::Double(v) => *v = canonical_f64(*v, zero),
+            Value::List(list) => list.canonicalize_floats(zero),
+            Value::Compound(compound) => canonicalize_floats_in_compound(compound, zero),
+            _ => (),
+        }
+    }
+
+    /// Like `==`, but compares floats and doubles bit for bit: a NaN equals
+    /// a NaN with the same payload, and `0.0` doesn't equal `-0.0`. After
+    /// `canonicalize_floats`, trees that differ only in their NaNs (and, with
+    /// `zero`, the signs of their zeros) are `canonical_eq`.
+    pub fn canonical_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Double(a), Value::Double(b)) => a.to_bits() == b.to_bits(),
+            (Value::List(a), Value::List(b)) => a.canonical_eq(b),
+            (Value::Compound(a), Value::Compound(b)) => compounds_canonical_eq(a, b),
+            _ => self == other,
+        }
+    }
+}
+
+
+/// Compact SNBT, as from `snbt::to_snbt`, e.g. `{name:"Bananrama"}`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&snbt::to_snbt(self))
+    }
+}
+
+
+macro_rules! impl_from {
+    ($target:ident, $($source:ty => $variant:ident),* $(,)*) => ($(
+        impl From<$source> for $target {
+            fn from(value: $source) -> $target {
+                $target::$variant(value)
+            }
+        }
+    )*);
+}
+
+
+impl_from!(
+    Value,
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+    Vec<u8> => ByteArray,
+    String => String,
+    List => List,
+    Compound => Compound,
+    Vec<i32> => IntArray,
+    Vec<i64> => LongArray,
+);
+
+
+/// A `TAG_Byte` of 1 or 0.
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Byte(value as i8)
+    }
+}
+
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+}
+
+
+impl_from!(
+    List,
+    Vec<i8> => Byte,
+    Vec<i16> => Short,
+    Vec<i32> => Int,
+    Vec<i64> => Long,
+    Vec<f32> => Float,
+    Vec<f64> => Double,
+    Vec<Vec<u8>> => ByteArray,
+    Vec<String> => String,
+    Vec<List> => List,
+    Vec<Compound> => Compound,
+    Vec<Vec<i32>> => IntArray,
+    Vec<Vec<i64>> => LongArray,
+);
+
+
+fn replace_string(s: &mut String, from: &str, to: &str) -> usize {
+    if s == from {
+        *s = to.to_string();
+        1
+    } else {
+        0
+    }
+}
+
+
+fn replace_strings_in_compound(compound: &mut Compound, from: &str, to: &str) -> usize {
+    compound.values_mut().map(|value| value.replace_strings(from, to)).sum()
+}
+
+
+fn canonical_f32(v: f32, zero: bool) -> f32 {
+    if v.is_nan() {
+        f32::NAN
+    } else if zero && v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+
+fn canonical_f64(v: f64, zero: bool) -> f64 {
+    if v.is_nan() {
+        f64::NAN
+    } else if zero && v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+
+fn canonicalize_floats_in_compound(compound: &mut Compound, zero: bool) {
+    for value in compound.values_mut() {
+        value.canonicalize_floats(zero);
+    }
+}
+
+
+fn compounds_canonical_eq(a: &Compound, b: &Compound) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(key, value)| b.get(key).is_some_and(|other| value.canonical_eq(other)))
+}
+
+
+/// Convert a list of bytes, ints or longs into the equivalent array, for data
+/// written by tools that chose the wrong representation. Returns whether
+/// `value` was converted; any other value is left alone.
+pub fn list_to_array(value: &mut Value) -> bool {
+    let converted = match value {
+        Value::List(List::Byte(bytes)) => {
+            Value::ByteArray(mem::take(bytes).into_iter().map(|b| b as u8).collect())
+        },
+        Value::List(List::Int(ints)) => Value::IntArray(mem::take(ints)),
+        Value::List(List::Long(longs)) => Value::LongArray(mem::take(longs)),
+        _ => return false,
+    };
+    *value = converted;
+    true
+}
+
+
+/// The inverse of `list_to_array`: convert a byte, int or long array into a
+/// list.
+pub fn array_to_list(value: &mut Value) -> bool {
+    let converted = match value {
+        Value::ByteArray(bytes) => {
+            Value::List(List::Byte(mem::take(bytes).into_iter().map(|b| b as i8).collect()))
+        },
+        Value::IntArray(ints) => Value::List(List::Int(mem::take(ints))),
+        Value::LongArray(longs) => Value::List(List::Long(mem::take(longs))),
+        _ => return false,
+    };
+    *value = converted;
+    true
+}
+
+
+/// Convert a compound whose values are all of one type, such as a map of
+/// counts, into a `HashMap` of `convert`ed values; e.g. with
+/// `Value::as_i32`. Returns `None` if `convert` fails for any value.
+pub fn compound_as_map<T, F>(compound: &Compound, convert: F) -> Option<HashMap<String, T>>
+        where F: Fn(&Value) -> Option<T> {
+    compound.iter()
+        .map(|(key, value)| Some((key.clone(), convert(value)?)))
+        .collect()
+}
+
+
+/// The entries of `compound` that `convert` succeeds for, converted; e.g.
+/// with `Value::as_i32`, all its ints.
+pub fn compound_entries<'a, T, F>(compound: &'a Compound, convert: F)
+        -> impl Iterator<Item = (&'a str, T)> + 'a
+        where F: Fn(&Value) -> Option<T> + 'a {
+    compound.iter().filter_map(move |(key, value)| Some((key.as_str(), convert(value)?)))
+}
+
+
+/// The `TAG_Double` entries of `compound`.
+pub fn compound_doubles(compound: &Compound) -> impl Iterator<Item = (&str, f64)> {
+    compound_entries(compound, Value::as_f64)
+}
+
+
+/// Typed editing of a `Compound`'s entries, e.g.
+/// `data.set_string("LevelName", "Renamed")` or
+/// `*data.get_i32_mut("DataVersion").unwrap() += 1`.
+///
+/// Each `set_*` replaces any existing entry, whatever its type. Each
+/// `get_*_mut` returns `None` unless the entry is of exactly that type, as
+/// the `Value::as_*` accessors do.
+pub trait CompoundExt {
+    fn set_byte<K: Into<String>>(&mut self, key: K, value: i8);
+    fn set_short<K: Into<String>>(&mut self, key: K, value: i16);
+    fn set_int<K: Into<String>>(&mut self, key: K, value: i32);
+    fn set_long<K: Into<String>>(&mut self, key: K, value: i64);
+    fn set_float<K: Into<String>>(&mut self, key: K, value: f32);
+    fn set_double<K: Into<String>>(&mut self, key: K, value: f64);
+    /// A `TAG_Byte` of 1 or 0.
+    fn set_bool<K: Into<String>>(&mut self, key: K, value: bool);
+    fn set_string<K: Into<String>, S: Into<String>>(&mut self, key: K, value: S);
+    fn set_byte_array<K: Into<String>>(&mut self, key: K, value: Vec<u8>);
+    fn set_list<K: Into<String>>(&mut self, key: K, value: List);
+    fn set_compound<K: Into<String>>(&mut self, key: K, value: Compound);
+    fn set_int_array<K: Into<String>>(&mut self, key: K, value: Vec<i32>);
+    fn set_long_array<K: Into<String>>(&mut self, key: K, value: Vec<i64>);
+
+    fn get_i8_mut(&mut self, key: &str) -> Option<&mut i8>;
+    fn get_i16_mut(&mut self, key: &str) -> Option<&mut i16>;
+    fn get_i32_mut(&mut self, key: &str) -> Option<&mut i32>;
+    fn get_i64_mut(&mut self, key: &str) -> Option<&mut i64>;
+    fn get_f32_mut(&mut self, key: &str) -> Option<&mut f32>;
+    fn get_f64_mut(&mut self, key: &str) -> Option<&mut f64>;
+    fn get_byte_array_mut(&mut self, key: &str) -> Option<&mut Vec<u8>>;
+    fn get_string_mut(&mut self, key: &str) -> Option<&mut String>;
+    fn get_list_mut(&mut self, key: &str) -> Option<&mut List>;
+    fn get_compound_mut(&mut self, key: &str) -> Option<&mut Compound>;
+    fn get_int_array_mut(&mut self, key: &str) -> Option<&mut Vec<i32>>;
+    fn get_long_array_mut(&mut self, key: &str) -> Option<&mut Vec<i64>>;
+}
+
+
+macro_rules! compound_setters {
+    ($($name:ident($value_type:ty) => $variant:ident),* $(,)*) => ($(
+        fn $name<K: Into<String>>(&mut self, key: K, value: $value_type) {
+            self.insert(key.into(), Value::$variant(value));
+        }
+    )*);
+}
+
+
+macro_rules! compound_mut_getters {
+    ($($name:ident($value_type:ty) => $variant:ident),* $(,)*) => ($(
+        fn $name(&mut self, key: &str) -> Option<&mut $value_type> {
+            match self.get_mut(key) {
+                Some(Value::$variant(v)) => Some(v),
+                _ => None,
+            }
+        }
+    )*);
+}
+
+
+impl CompoundExt for Compound {
+    compound_setters!(
+        set_byte(i8) => Byte,
+        set_short(i16) => Short,
+        set_int(i32) => Int,
+        set_long(i64) => Long,
+        set_float(f32) => Float,
+        set_double(f64) => Double,
+        set_byte_array(Vec<u8>) => ByteArray,
+        set_list(List) => List,
+        set_compound(Compound) => Compound,
+        set_int_array(Vec<i32>) => IntArray,
+        set_long_array(Vec<i64>) => LongArray,
+    );
+
+    fn set_bool<K: Into<String>>(&mut self, key: K, value: bool) {
+        self.set_byte(key, value as i8);
+    }
+
+    fn set_string<K: Into<String>, S: Into<String>>(&mut self, key: K, value: S) {
+        self.insert(key.into(), Value::String(value.into()));
+    }
+
+    compound_mut_getters!(
+        get_i8_mut(i8) => Byte,
+        get_i16_mut(i16) => Short,
+        get_i32_mut(i32) => Int,
+        get_i64_mut(i64) => Long,
+        get_f32_mut(f32) => Float,
+        get_f64_mut(f64) => Double,
+        get_byte_array_mut(Vec<u8>) => ByteArray,
+        get_string_mut(String) => String,
+        get_list_mut(List) => List,
+        get_compound_mut(Compound) => Compound,
+        get_int_array_mut(Vec<i32>) => IntArray,
+        get_long_array_mut(Vec<i64>) => LongArray,
+    );
+}
+
+
+fn walk_value(path: &str, value: &Value, f: &mut dyn FnMut(&str, &Value)) {
+    f(path, value);
+    match value {
+        Value::List(list) => walk_list(path, list, f),
+        Value::Compound(compound) => walk_compound(path, compound, f),
+        _ => (),
+    }
+}
+
+
+fn walk_compound(path: &str, compound: &Compound, f: &mut dyn FnMut(&str, &Value)) {
+    for (key, value) in compound {
+        walk_value(&flat::join_path(path, key), value, f);
+    }
+}
+
+
+fn walk_list(path: &str, list: &List, f: &mut dyn FnMut(&str, &Value)) {
+    let mut index = 0;
+    while let Some(element) = list.path_element(index) {
+        let path = flat::join_path(path, &index.to_string());
+        match element {
+            PathNode::List(list) => {
+                f(&path, &Value::List(list.clone()));
+                walk_list(&path, list, f);
+            },
+            PathNode::Compound(compound) => {
+                f(&path, &Value::Compound(compound.clone()));
+                walk_compound(&path, compound, f);
+            },
+            PathNode::Element(value) => f(&path, &value),
+            PathNode::Value(value) => f(&path, value),
+        }
+        index += 1;
+    }
+}
+
+
+/// Where `Value::get_path` has got to.
+enum PathNode<'a> {
+    Value(&'a Value),
+    Compound(&'a Compound),
+    List(&'a List),
+    /// A copy of an element of a list of numbers, strings or arrays.
+    Element(Value),
+}
+
+
+impl<'a> PathNode<'a> {
+    fn into_value(self) -> Value {
+        match self {
+            PathNode::Value(value) => value.clone(),
+            PathNode::Compound(compound) => Value::Compound(compound.clone()),
+            PathNode::List(list) => Value::List(list.clone()),
+            PathNode::Element(value) => value,
+        }
+    }
+}
+
+
+macro_rules! collect_list {
+    ($values:ident, $variant:ident) => ({
+        let mut items = Vec::with_capacity($values.len());
+        for value in $values {
+            match value {
+                Value::$variant(item) => items.push(item),
+                _ => return None,
+            }
+        }
+        List::$variant(items)
+    });
+}
+
+
+impl List {
+    /// Build a list from `values`, or `None` if they aren't all of the same
+    /// type. No values make a `List::Empty(TAG_END)`.
+    pub fn from_values(values: Vec<Value>) -> Option<List> {
+        let first = match values.first() {
+            Some(first) => first,
+            None => return Some(List::Empty(TAG_END)),
+        };
+        Some(match first {
+            Value::Byte(_) => collect_list!(values, Byte),
+            Value::Short(_) => collect_list!(values, Short),
+            Value::Int(_) => collect_list!(values, Int),
+            Value::Long(_) => collect_list!(values, Long),
+            Value::Float(_) => collect_list!(values, Float),
+            Value::Double(_) => collect_list!(values, Double),
+            Value::ByteArray(_) => collect_list!(values, ByteArray),
+            Value::String(_) => collect_list!(values, String),
+            Value::List(_) => collect_list!(values, List),
+            Value::Compound(_) => collect_list!(values, Compound),
+            Value::IntArray(_) => collect_list!(values, IntArray),
+            Value::LongArray(_) => collect_list!(values, LongArray),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            List::Empty(_) => 0,
+            List::Byte(items) => items.len(),
+            List::Short(items) => items.len(),
+            List::Int(items) => items.len(),
+            List::Long(items) => items.len(),
+            List::Float(items) => items.len(),
+            List::Double(items) => items.len(),
+            List::ByteArray(items) => items.len(),
+            List::String(items) => items.len(),
+            List::List(items) => items.len(),
+            List::Compound(items) => items.len(),
+            List::IntArray(items) => items.len(),
+            List::LongArray(items) => items.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The tag type of the elements; for an empty list, whichever type it was
+    /// read or built with.
+    pub fn element_tag_type(&self) -> u8 {
+        match self {
+            List::Empty(element_type) => *element_type,
+            List::Byte(_) => TAG_BYTE,
+            List::Short(_) => TAG_SHORT,
+            List::Int(_) => TAG_INT,
+            List::Long(_) => TAG_LONG,
+            List::Float(_) => TAG_FLOAT,
+            List::Double(_) => TAG_DOUBLE,
+            List::ByteArray(_) => TAG_BYTE_ARRAY,
+            List::String(_) => TAG_STRING,
+            List::List(_) => TAG_LIST,
+            List::Compound(_) => TAG_COMPOUND,
+            List::IntArray(_) => TAG_INT_ARRAY,
+            List::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// The elements, each as a `Value`; the inverse of `from_values`.
+    pub fn into_values(self) -> Vec<Value> {
+        macro_rules! into_values {
+            ($($variant:ident),*) => (
+                match self {
+                    List::Empty(_) => Vec::new(),
+                    $(List::$variant(items) => items.into_iter().map(Value::$variant).collect(),)*
+                }
+            );
+        }
+        into_values!(
+            Byte, Short, Int, Long, Float, Double, ByteArray, String, List, Compound, IntArray,
+            LongArray
+        )
+    }
+
+    /// A copy of each element, as a `Value`.
+    pub fn iter_values(&self) -> impl Iterator<Item = Value> + '_ {
+        (0..).map_while(move |index| self.path_element(index)).map(PathNode::into_value)
+    }
+
+    fn path_element(&self, index: usize) -> Option<PathNode<'_>> {
+        Some(match self {
+            List::Empty(_) => return None,
+            List::Byte(v) => PathNode::Element(Value::Byte(*v.get(index)?)),
+            List::Short(v) => PathNode::Element(Value::Short(*v.get(index)?)),
+            List::Int(v) => PathNode::Element(Value::Int(*v.get(index)?)),
+            List::Long(v) => PathNode::Element(Value::Long(*v.get(index)?)),
+            List::Float(v) => PathNode::Element(Value::Float(*v.get(index)?)),
+            List::Double(v) => PathNode::Element(Value::Double(*v.get(index)?)),
+            List::ByteArray(v) => PathNode::Element(Value::ByteArray(v.get(index)?.clone())),
+            List::String(v) => PathNode::Element(Value::String(v.get(index)?.clone())),
+            List::List(v) => PathNode::List(v.get(index)?),
+            List::Compound(v) => PathNode::Compound(v.get(index)?),
+            List::IntArray(v) => PathNode::Element(Value::IntArray(v.get(index)?.clone())),
+            List::LongArray(v) => PathNode::Element(Value::LongArray(v.get(index)?.clone())),
+        })
+    }
+
+    fn replace_strings(&mut self, from: &str, to: &str) -> usize {
+        match self {
+            List::String(strings) => {
+                strings.iter_mut().map(|s| replace_string(s, from, to)).sum()
+            },
+            List::List(lists) => {
+                lists.iter_mut().map(|list| list.replace_strings(from, to)).sum()
+            },
+            List::Compound(compounds) => {
+                compounds.iter_mut()
+                    .map(|compound| replace_strings_in_compound(compound, from, to))
+                    .sum()
+            },
+            _ => 0,
+        }
+    }
+
+    fn canonicalize_floats(&mut self, zero: bool) {
+        match self {
+            List::Float(floats) => floats.iter_mut().for_each(|v| *v = canonical_f32(*v, zero)),
+            List::Double(doubles) => {
+                doubles.iter_mut().for_each(|v| *v = canonical_f64(*v, zero))
+            },
+            List::List(lists) => lists.iter_mut().for_each(|list| list.canonicalize_floats(zero)),
+            List::Compound(compounds) => {
+                for compound in compounds {
+                    canonicalize_floats_in_compound(compound, zero);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn canonical_eq(&self, other: &List) -> bool {
+        match (self, other) {
+            (List::Float(a), List::Float(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+            },
+            (List::Double(a), List::Double(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+            },
+            (List::List(a), List::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.canonical_eq(y))
+            },
+            (List::Compound(a), List::Compound(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| compounds_canonical_eq(x, y))
+            },
+            _ => self == other,
+        }
+    }
+
+    /// Split a list of compounds into pages of (at most) `size` elements.
+    /// Any other kind of list yields no pages.
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunks(&self, size: usize) -> slice::Chunks<'_, Compound> {
+        match self {
+            List::Compound(compounds) => compounds.chunks(size),
+            _ => [].chunks(size),
+        }
+    }
 }
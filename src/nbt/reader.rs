@@ -1,13 +1,17 @@
 extern crate byteorder;
 
-use std::convert::From;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::convert::{From, TryInto};
 use std::io;
-use std::io::Read;
+use std::io::{Cursor, Read};
 use std::mem;
+use std::str;
 use std::string;
 use std::vec::Vec;
 
-use self::byteorder::ReadBytesExt;
+use self::byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
 
 use super::{
     TAG_END,
@@ -22,8 +26,10 @@ use super::{
     TAG_LIST,
     TAG_COMPOUND,
     TAG_INT_ARRAY,
+    TAG_LONG_ARRAY,
 };
-use super::{Value, RootValue, Compound, List};
+use super::{Value, RootValue, Compound, List, Endianness};
+use crate::protocol::varint::{self, VarIntError};
 
 
 #[derive(Debug)]
@@ -32,6 +38,193 @@ pub enum NbtReadError {
     InvalidTagType,
     IoError(io::Error),
     InvalidUtf8(string::FromUtf8Error),
+    /// The stream ended part way through a string or array.
+    UnexpectedEof,
+    /// The stream looked compressed, but failed to decompress.
+    Decompression(io::Error),
+    /// `error` occurred inside nested compounds and lists, described
+    /// outermost first by `stack`. Only returned if
+    /// `ReaderOptions::capture_stack_on_error` is set.
+    WithStack {
+        error: Box<NbtReadError>,
+        stack: Vec<String>,
+    },
+    /// `error` was found `offset` bytes into the stream: how far the reader
+    /// had got when it found it. A bad compound entry is only found once its
+    /// name is read, so the offset is after that. Only returned if
+    /// `ReaderOptions::report_offset_on_error` is set.
+    At {
+        offset: u64,
+        error: Box<NbtReadError>,
+    },
+    /// Compounds and lists were nested deeper than `ReadLimits::max_depth`.
+    MaxDepthExceeded,
+    /// The stream went on after the root tag. Only returned if
+    /// `ReaderOptions::reject_trailing_data` is set.
+    TrailingData,
+    /// An array or list's length would take the reader past
+    /// `ReadLimits::max_bytes`.
+    AllocationLimitExceeded,
+    /// A string, re-encoded as modified UTF-8, was longer than the 65535
+    /// bytes its length can give; see `tape::transcode_endianness`.
+    StringTooLong(usize),
+    /// A VarInt went on for more bytes than its type can take. Only
+    /// returned if `ReaderOptions::varints` is set.
+    VarIntTooLong,
+    /// A VarInt wasn't in its shortest encoding. Only returned if
+    /// `ReaderOptions::reject_non_minimal_varints` is set.
+    NonMinimalVarInt,
+    /// A compound had two entries with this name. Only returned if
+    /// `ReaderOptions::reject_duplicate_keys` is set; otherwise the last one
+    /// wins.
+    DuplicateKey(String),
+    /// A file was gzipped more times over than
+    /// `ReadLimits::max_decompress_layers`.
+    TooManyCompressionLayers,
+}
+
+
+/// Bounds on what the reader will accept, for reading untrusted data.
+#[derive(Debug, Clone)]
+pub struct ReadLimits {
+    /// How deeply compounds and lists of compounds or lists may be nested,
+    /// counting the root.
+    pub max_depth: usize,
+    /// How many bytes the reader may allocate for arrays and lists in all,
+    /// checked against their lengths before allocating. Strings aren't
+    /// counted, as their lengths are limited to 64 KiB anyway, except with
+    /// `ReaderOptions::varints`.
+    pub max_bytes: usize,
+    /// How many layers of gzip `parse_nbt_file_with_options` will unwrap: 1
+    /// for an ordinary gzipped file, 2 for one gzipped again by mistake.
+    pub max_decompress_layers: usize,
+}
+
+
+impl Default for ReadLimits {
+    /// The game's depth limit, a size limit far beyond anything the game
+    /// writes, and a few layers of gzip.
+    fn default() -> ReadLimits {
+        ReadLimits {
+            max_depth: 512,
+            max_bytes: 1 << 30,
+            max_decompress_layers: 4,
+        }
+    }
+}
+
+
+/// Reusable scratch buffers for the reader, for parsing many streams in a
+/// row without reallocating them for each one; see
+/// `parse_nbt_stream_with_context`.
+///
+/// The buffers only hold data while a single string or array is being
+/// decoded, so nothing carries over from one parse to the next, however it
+/// ended. They do keep their capacity: the longest string read so far, and
+/// up to 64 KiB for arrays, which are read in chunks.
+#[derive(Debug, Default)]
+pub struct ParseContext {
+    string_bytes: Vec<u8>,
+    utf16_units: Vec<u16>,
+    number_bytes: Vec<u8>,
+}
+
+
+impl ParseContext {
+    pub fn new() -> ParseContext {
+        ParseContext::default()
+    }
+
+    /// How many bytes the buffers hold on to between parses.
+    pub fn retained_bytes(&self) -> usize {
+        self.string_bytes.capacity()
+            + self.utf16_units.capacity() * mem::size_of::<u16>()
+            + self.number_bytes.capacity()
+    }
+}
+
+
+/// State for one parse: the byte order and whether to read VarInts, what's
+/// left of `ReadLimits::max_bytes`, and the scratch buffers.
+struct ReadState<'c> {
+    endianness: Endianness,
+    varints: bool,
+    minimal_varints: bool,
+    reject_duplicate_keys: bool,
+    remaining: usize,
+    context: &'c mut ParseContext,
+}
+
+
+impl<'c> ReadState<'c> {
+    fn new(options: &ReaderOptions, context: &'c mut ParseContext) -> ReadState<'c> {
+        ReadState {
+            endianness: options.endianness,
+            varints: options.varints,
+            minimal_varints: options.reject_non_minimal_varints,
+            reject_duplicate_keys: options.reject_duplicate_keys,
+            remaining: options.limits.max_bytes,
+            context,
+        }
+    }
+
+    /// Account for `count` elements of `T`, before allocating them.
+    fn take<T>(&mut self, count: usize) -> Result<(), NbtReadError> {
+        match count.checked_mul(mem::size_of::<T>()) {
+            Some(bytes) if bytes <= self.remaining => {
+                self.remaining -= bytes;
+                Ok(())
+            },
+            _ => Err(NbtReadError::AllocationLimitExceeded),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct ReaderOptions {
+    /// On an error inside a compound or list, wrap it in
+    /// `NbtReadError::WithStack`, describing where in the structure the
+    /// reader was.
+    pub capture_stack_on_error: bool,
+    /// Wrap errors in `NbtReadError::At`, giving how far into the stream
+    /// (after any decompression) the reader had got.
+    pub report_offset_on_error: bool,
+    /// Fail with `NbtReadError::TrailingData` if there's anything left in the
+    /// stream after the root tag. Off by default, for streams that carry
+    /// more after the NBT.
+    pub reject_trailing_data: bool,
+    pub limits: ReadLimits,
+    /// The stream's byte order: big-endian for Java Edition, little-endian
+    /// for Bedrock Edition.
+    pub endianness: Endianness,
+    /// Read ints, longs and lengths as VarInts, as Bedrock Edition's network
+    /// NBT does: zig-zag encoded for ints, longs, and array and list
+    /// lengths, and plain for string lengths. See
+    /// `parse_bedrock_network_nbt`.
+    pub varints: bool,
+    /// With `varints`, fail with `NbtReadError::NonMinimalVarInt` on a
+    /// VarInt that isn't in its shortest encoding, which the game never
+    /// writes.
+    pub reject_non_minimal_varints: bool,
+    /// Fail with `NbtReadError::DuplicateKey` if a compound has two entries
+    /// with the same name, rather than keeping the last.
+    pub reject_duplicate_keys: bool,
+}
+
+
+impl ReaderOptions {
+    /// Every check that rejects merely suspicious input, for validating files
+    /// rather than making the best of them: `reject_trailing_data`,
+    /// `reject_non_minimal_varints` and `reject_duplicate_keys`.
+    pub fn strict() -> ReaderOptions {
+        ReaderOptions {
+            reject_trailing_data: true,
+            reject_non_minimal_varints: true,
+            reject_duplicate_keys: true,
+            ..ReaderOptions::default()
+        }
+    }
 }
 
 
@@ -49,6 +242,7 @@ fn tag_constant_to_name(tag_type: u8) -> String {
         TAG_LIST => "TAG_List",
         TAG_COMPOUND => "TAG_Compound",
         TAG_INT_ARRAY => "TAG_Int_Array",
+        TAG_LONG_ARRAY => "TAG_Long_Array",
         _ => return format!("(unknown tag type 0x{:02x})", tag_type),
     })
 }
@@ -61,6 +255,20 @@ impl From<io::Error> for NbtReadError {
 }
 
 
+impl From<VarIntError> for NbtReadError {
+    fn from(err: VarIntError) -> NbtReadError {
+        match err {
+            VarIntError::IoError(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                NbtReadError::UnexpectedEof
+            },
+            VarIntError::IoError(err) => NbtReadError::IoError(err),
+            VarIntError::TooLong => NbtReadError::VarIntTooLong,
+            VarIntError::NonMinimal => NbtReadError::NonMinimalVarInt,
+        }
+    }
+}
+
+
 impl From<string::FromUtf8Error> for NbtReadError {
     fn from(err: string::FromUtf8Error) -> NbtReadError {
         NbtReadError::InvalidUtf8(err)
@@ -69,9 +277,14 @@ impl From<string::FromUtf8Error> for NbtReadError {
 
 
 macro_rules! read_number {
-    ($reader:ident, $read_func:ident) => ({
-        $reader.$read_func::<byteorder::BigEndian>()
-            .map_err(NbtReadError::from)
+    ($reader:ident, $read_func:ident) => (
+        read_number!($reader, Endianness::Big, $read_func)
+    );
+    ($reader:ident, $endianness:expr, $read_func:ident) => ({
+        match $endianness {
+            Endianness::Big => $reader.$read_func::<BigEndian>(),
+            Endianness::Little => $reader.$read_func::<LittleEndian>(),
+        }.map_err(NbtReadError::from)
     });
 }
 
@@ -101,11 +314,35 @@ fn test_read_unsigned() {
 }
 
 
-fn read_n_bytes_to_vector<R: ?Sized + Read>(reader: &mut R, length: usize)
+fn read_exact_or_eof<R: ?Sized + Read>(reader: &mut R, bytes: &mut [u8])
+        -> Result<(), NbtReadError> {
+    match reader.read_exact(bytes) {
+        Ok(()) => Ok(()),
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            Err(NbtReadError::UnexpectedEof)
+        },
+        Err(err) => Err(NbtReadError::from(err)),
+    }
+}
+
+
+/// Read `length` bytes onto the end of `bytes`. The length is untrusted, so
+/// let the buffer grow as the data actually arrives rather than allocating
+/// it all up front.
+fn read_onto_end<R: ?Sized + Read>(reader: &mut R, bytes: &mut Vec<u8>, length: usize)
+        -> Result<(), NbtReadError> {
+    let read = Read::take(&mut *reader, length as u64).read_to_end(bytes)?;
+    if read < length {
+        return Err(NbtReadError::UnexpectedEof);
+    }
+    Ok(())
+}
+
+
+pub(super) fn read_n_bytes_to_vector<R: ?Sized + Read>(reader: &mut R, length: usize)
         -> Result<Vec<u8>, NbtReadError> {
-    let mut bytes = Vec::<u8>::with_capacity(length);
-    bytes.resize(length, 0);
-    reader.read_exact(&mut bytes[..])?;
+    let mut bytes = Vec::new();
+    read_onto_end(reader, &mut bytes, length)?;
     Ok(bytes)
 }
 
@@ -128,81 +365,283 @@ fn is_simple_value(tag_type: u8) -> Result<bool, UnknownTagType> {
         TAG_LIST => false,
         TAG_COMPOUND => false,
         TAG_INT_ARRAY => true,
+        TAG_LONG_ARRAY => true,
         _ => {
             return Err(UnknownTagType {
-                tag_type: tag_type,
+                tag_type,
             });
         },
     })
 }
 
 
-fn read_nbt_string(reader: &mut dyn Read) -> Result<String, NbtReadError> {
+fn read_nbt_string<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<String, NbtReadError> {
     // XXX: The NBT standard say "TAG_Short" for a length, which would imply
     // this length is signed. Which makes no sense.
-    let length = read_number!(reader, read_u16)? as usize;
-    let bytes = read_n_bytes_to_vector(reader, length)?;
-    Ok(String::from_utf8(bytes)?)
+    let length = if state.varints {
+        let length = read_var_int(reader, state)? as u32 as usize;
+        state.take::<u8>(length)?;
+        length
+    } else {
+        read_number!(reader, state.endianness, read_u16)? as usize
+    };
+    let context = &mut *state.context;
+    let bytes = &mut context.string_bytes;
+    bytes.clear();
+    read_onto_end(reader, bytes, length)?;
+    decode_modified_utf8_with(bytes, &mut context.utf16_units)
+}
+
+
+/// Decode a string from Java's "modified UTF-8", which NBT strings use.
+///
+/// This differs from UTF-8 in two ways: NUL is encoded in two bytes, as
+/// `0xc0 0x80`, and characters outside the Basic Multilingual Plane are
+/// encoded as a UTF-16 surrogate pair, each surrogate taking three bytes.
+/// Plain four byte UTF-8 sequences, as written by some other tools, are
+/// accepted too.
+pub fn decode_modified_utf8(bytes: &[u8]) -> Result<String, NbtReadError> {
+    decode_modified_utf8_with(bytes, &mut Vec::new())
+}
+
+
+/// `decode_modified_utf8`, using `units` as scratch space.
+fn decode_modified_utf8_with(bytes: &[u8], units: &mut Vec<u16>)
+        -> Result<String, NbtReadError> {
+    // Most strings are also valid UTF-8.
+    if let Ok(s) = str::from_utf8(bytes) {
+        return Ok(s.to_owned());
+    }
+    let utf8_err = String::from_utf8(bytes.to_vec()).unwrap_err();
+    let invalid = || NbtReadError::InvalidUtf8(utf8_err.clone());
+    units.clear();
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = u32::from(bytes[i]);
+        let (length, initial) = match lead {
+            0x00..=0x7f => (1, lead),
+            0xc0..=0xdf => (2, lead & 0x1f),
+            0xe0..=0xef => (3, lead & 0x0f),
+            0xf0..=0xf4 => (4, lead & 0x07),
+            _ => return Err(invalid()),
+        };
+        let continuation = bytes.get(i + 1..i + length).ok_or_else(invalid)?;
+        let mut c = initial;
+        for b in continuation {
+            if b & 0xc0 != 0x80 {
+                return Err(invalid());
+            }
+            c = (c << 6) | u32::from(b & 0x3f);
+        }
+        if c >= 0x10000 {
+            let c = c - 0x10000;
+            units.push(0xd800 | (c >> 10) as u16);
+            units.push(0xdc00 | (c & 0x3ff) as u16);
+        } else {
+            units.push(c as u16);
+        }
+        i += length;
+    }
+    String::from_utf16(units).map_err(|_| invalid())
+}
+
+
+/// A type of number that arrays and lists hold.
+trait Number: Sized {
+    const SIZE: usize = mem::size_of::<Self>();
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self;
+}
+
+
+macro_rules! impl_number {
+    ($($number_type:ty),*) => ($(
+        impl Number for $number_type {
+            fn from_bytes(bytes: &[u8], endianness: Endianness) -> $number_type {
+                let bytes = bytes.try_into().unwrap();
+                match endianness {
+                    Endianness::Big => <$number_type>::from_be_bytes(bytes),
+                    Endianness::Little => <$number_type>::from_le_bytes(bytes),
+                }
+            }
+        }
+    )*);
+}
+
+
+impl_number!(i8, i16, i32, i64, f32, f64);
+
+
+/// How many bytes of numbers `read_numbers` reads at a time.
+const NUMBER_CHUNK_BYTES: usize = 64 * 1024;
+
+
+/// Read `count` numbers, a chunk of bytes at a time rather than a read per
+/// number. The count is untrusted, so the result grows as the data actually
+/// arrives rather than being allocated up front.
+fn read_numbers<R: Read + ?Sized, T: Number>(reader: &mut R, state: &mut ReadState, count: usize)
+        -> Result<Vec<T>, NbtReadError> {
+    state.take::<T>(count)?;
+    let endianness = state.endianness;
+    let bytes = &mut state.context.number_bytes;
+    let chunk_len = NUMBER_CHUNK_BYTES / T::SIZE;
+    let mut numbers = Vec::with_capacity(cmp::min(count, chunk_len));
+    let mut left = count;
+    while left > 0 {
+        let len = cmp::min(left, chunk_len);
+        bytes.clear();
+        bytes.resize(len * T::SIZE, 0);
+        read_exact_or_eof(reader, &mut bytes[..])?;
+        numbers.extend(bytes.chunks_exact(T::SIZE).map(|b| T::from_bytes(b, endianness)));
+        left -= len;
+    }
+    Ok(numbers)
+}
+
+
+fn read_var_int<R: Read + ?Sized>(reader: &mut R, state: &ReadState)
+        -> Result<i32, NbtReadError> {
+    Ok(if state.minimal_varints {
+        varint::read_minimal_var_int(reader)?
+    } else {
+        varint::read_var_int(reader)?
+    })
+}
+
+
+fn read_zigzag_var_int<R: Read + ?Sized>(reader: &mut R, state: &ReadState)
+        -> Result<i32, NbtReadError> {
+    Ok(if state.minimal_varints {
+        varint::read_minimal_zigzag_var_int(reader)?
+    } else {
+        varint::read_zigzag_var_int(reader)?
+    })
+}
+
+
+fn read_zigzag_var_long<R: Read + ?Sized>(reader: &mut R, state: &ReadState)
+        -> Result<i64, NbtReadError> {
+    Ok(if state.minimal_varints {
+        varint::read_minimal_zigzag_var_long(reader)?
+    } else {
+        varint::read_zigzag_var_long(reader)?
+    })
+}
+
+
+/// Read an array or list's length: a `TAG_Int`, or with `varints` a zig-zag
+/// VarInt.
+fn read_length<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<usize, NbtReadError> {
+    if state.varints {
+        Ok(read_zigzag_var_int(reader, state)? as u32 as usize)
+    } else {
+        Ok(read_number!(reader, state.endianness, read_u32)? as usize)
+    }
+}
+
+
+/// `read_numbers` for ints, which are VarInts with `varints`.
+fn read_ints<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState, count: usize)
+        -> Result<Vec<i32>, NbtReadError> {
+    if !state.varints {
+        return read_numbers(reader, state, count);
+    }
+    state.take::<i32>(count)?;
+    (0..count).map(|_| read_zigzag_var_int(reader, state)).collect()
+}
+
+
+/// `read_numbers` for longs, which are VarLongs with `varints`.
+fn read_longs<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState, count: usize)
+        -> Result<Vec<i64>, NbtReadError> {
+    if !state.varints {
+        return read_numbers(reader, state, count);
+    }
+    state.take::<i64>(count)?;
+    (0..count).map(|_| read_zigzag_var_long(reader, state)).collect()
 }
 
 
-fn read_nbt_byte_array(reader: &mut dyn Read) -> Result<Vec<u8>, NbtReadError> {
+fn read_nbt_byte_array<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<Vec<u8>, NbtReadError> {
     // XXX: The NBT standard say "TAG_Int" for a length, which would imply
     // this length is signed.  Which makes no sense.
-    let length = read_number!(reader, read_u32)? as usize;
+    let length = read_length(reader, state)?;
+    state.take::<u8>(length)?;
     read_n_bytes_to_vector(reader, length)
 }
 
 
-fn read_nbt_int_array(reader: &mut dyn Read) -> Result<Vec<i32>, NbtReadError> {
+fn read_nbt_int_array<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<Vec<i32>, NbtReadError> {
     // XXX: The NBT standard say "TAG_Int" for a length, which would imply
     // this length is signed.  Which makes no sense.
-    let length = read_number!(reader, read_u32)? as usize;
-    let mut vec = Vec::<i32>::with_capacity(length);
-    for _ in 0..length {
-        vec.push(read_number!(reader, read_i32)?);
-    }
-    Ok(vec)
+    let length = read_length(reader, state)?;
+    read_ints(reader, state, length)
 }
 
 
-fn read_simple_value(tag_type: u8, reader: &mut dyn Read)
-        -> Result<Value, NbtReadError> {
+fn read_nbt_long_array<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<Vec<i64>, NbtReadError> {
+    // XXX: The NBT standard say "TAG_Int" for a length, which would imply
+    // this length is signed.  Which makes no sense.
+    let length = read_length(reader, state)?;
+    read_longs(reader, state, length)
+}
+
+
+fn read_scalar_value<R: Read + ?Sized>(
+        tag_type: u8, reader: &mut R, state: &mut ReadState)
+        -> Result<ScalarValue, NbtReadError> {
     Ok(match tag_type {
-        TAG_BYTE => Value::Byte(reader.read_i8()?),
-        TAG_SHORT => Value::Short(read_number!(reader, read_i16)?),
-        TAG_INT => Value::Int(read_number!(reader, read_i32)?),
-        TAG_LONG => Value::Long(read_number!(reader, read_i64)?),
-        TAG_FLOAT => Value::Float(read_number!(reader, read_f32)?),
-        TAG_DOUBLE => Value::Double(read_number!(reader, read_f64)?),
-        TAG_BYTE_ARRAY => Value::ByteArray(read_nbt_byte_array(reader)?),
-        TAG_STRING => Value::String(read_nbt_string(reader)?),
-        TAG_INT_ARRAY => Value::IntArray(read_nbt_int_array(reader)?),
+        TAG_BYTE => ScalarValue::Byte(reader.read_i8()?),
+        TAG_SHORT => ScalarValue::Short(read_number!(reader, state.endianness, read_i16)?),
+        TAG_INT if state.varints => ScalarValue::Int(read_zigzag_var_int(reader, state)?),
+        TAG_INT => ScalarValue::Int(read_number!(reader, state.endianness, read_i32)?),
+        TAG_LONG if state.varints => ScalarValue::Long(read_zigzag_var_long(reader, state)?),
+        TAG_LONG => ScalarValue::Long(read_number!(reader, state.endianness, read_i64)?),
+        TAG_FLOAT => ScalarValue::Float(read_number!(reader, state.endianness, read_f32)?),
+        TAG_DOUBLE => ScalarValue::Double(read_number!(reader, state.endianness, read_f64)?),
+        TAG_BYTE_ARRAY => ScalarValue::ByteArray(read_nbt_byte_array(reader, state)?),
+        TAG_STRING => ScalarValue::String(read_nbt_string(reader, state)?),
+        TAG_INT_ARRAY => ScalarValue::IntArray(read_nbt_int_array(reader, state)?),
+        TAG_LONG_ARRAY => ScalarValue::LongArray(read_nbt_long_array(reader, state)?),
         _ => panic!(
-            "read_simple_value called for non-simple value {}",
+            "read_scalar_value called for non-simple value {}",
             tag_constant_to_name(tag_type)
         ),
     })
 }
 
 
-enum ComplexReadResult {
+fn read_simple_value<R: Read + ?Sized>(
+        tag_type: u8, reader: &mut R, state: &mut ReadState)
+        -> Result<Value, NbtReadError> {
+    read_scalar_value(tag_type, reader, state).map(Value::from)
+}
+
+
+enum ComplexReadResult<R: Read + ?Sized> {
     NotFinished,
-    DescendInto(Box<dyn ReadingComplex>),
+    DescendInto(Box<dyn ReadingComplex<R>>),
     Done,
 }
 
 
-trait ReadingComplex {
-    fn continue_read(&mut self, reader: &mut dyn Read) -> Result<ComplexReadResult, NbtReadError>;
+trait ReadingComplex<R: Read + ?Sized> {
+    fn continue_read(&mut self, reader: &mut R, state: &mut ReadState)
+        -> Result<ComplexReadResult<R>, NbtReadError>;
     fn descended_read_complete(&mut self, value: Value);
     fn final_value(self: Box<Self>) -> Value;
+    /// What's being read, for `NbtReadError::WithStack`.
+    fn describe(&self) -> String;
 }
 
 
-enum ReadStart {
+enum ReadStart<R: Read + ?Sized> {
     Simple(Value),
-    Complex(Box<dyn ReadingComplex>),
+    Complex(Box<dyn ReadingComplex<R>>),
 }
 
 
@@ -217,8 +656,10 @@ macro_rules! read_simple_list {
     (
         $list_enum_type: ident, $list_type:ty,
         $number_to_read:expr,
+        $state:ident,
         $read_func:block
     ) => ({
+        $state.take::<$list_type>($number_to_read)?;
         let mut the_list = Vec::<$list_type>::with_capacity($number_to_read);
         for _ in 0..$number_to_read {
             the_list.push(($read_func)?);
@@ -228,29 +669,33 @@ macro_rules! read_simple_list {
 }
 
 
-fn start_list_read(reader: &mut dyn Read) -> Result<ListStart, NbtReadError> {
+fn start_list_read<R: Read + ?Sized>(reader: &mut R, state: &mut ReadState)
+        -> Result<ListStart, NbtReadError> {
     let inner_tag_type = reader.read_u8()?;
     // XXX: The NBT standard say "TAG_Int" for a length, which would imply
     // this length is signed. Which makes no sense.
-    let number = read_number!(reader, read_u32)? as usize;
+    let number = read_length(reader, state)?;
 
-    if inner_tag_type == TAG_END && number == 0 {
-        return Ok(ListStart::Simple(List::Empty));
+    if number == 0 {
+        if inner_tag_type > TAG_LONG_ARRAY {
+            return Err(NbtReadError::UnknownTagType(inner_tag_type));
+        }
+        return Ok(ListStart::Simple(List::Empty(inner_tag_type)));
     }
 
     Ok(ListStart::Simple(match inner_tag_type {
         TAG_END => return Err(NbtReadError::InvalidTagType),
-        TAG_BYTE => read_simple_list!(Byte, i8, number, { reader.read_i8() }),
-        TAG_SHORT => read_simple_list!(Short, i16, number, { read_number!(reader, read_i16) }),
-        TAG_INT => read_simple_list!(Int, i32, number, { read_number!(reader, read_i32) }),
-        TAG_LONG => read_simple_list!(Long, i64, number, { read_number!(reader, read_i64) }),
-        TAG_FLOAT => read_simple_list!(Float, f32, number, { read_number!(reader, read_f32) }),
-        TAG_DOUBLE => read_simple_list!(Double, f64, number, { read_number!(reader, read_f64) }),
+        TAG_BYTE => List::Byte(read_numbers(reader, state, number)?),
+        TAG_SHORT => List::Short(read_numbers(reader, state, number)?),
+        TAG_INT => List::Int(read_ints(reader, state, number)?),
+        TAG_LONG => List::Long(read_longs(reader, state, number)?),
+        TAG_FLOAT => List::Float(read_numbers(reader, state, number)?),
+        TAG_DOUBLE => List::Double(read_numbers(reader, state, number)?),
         TAG_BYTE_ARRAY => read_simple_list!(
-            ByteArray, Vec<u8>, number, { read_nbt_byte_array(reader) }
+            ByteArray, Vec<u8>, number, state, { read_nbt_byte_array(reader, state) }
         ),
         TAG_STRING => read_simple_list!(
-            String, String, number, { read_nbt_string(reader) }
+            String, String, number, state, { read_nbt_string(reader, state) }
         ),
         TAG_LIST => return Ok(ListStart::ListOfList(ReadingListOfList {
             items_remaining: number,
@@ -261,7 +706,10 @@ fn start_list_read(reader: &mut dyn Read) -> Result<ListStart, NbtReadError> {
             value: Vec::<Compound>::new(),
         })),
         TAG_INT_ARRAY => read_simple_list!(
-            IntArray, Vec<i32>, number, { read_nbt_int_array(reader) }
+            IntArray, Vec<i32>, number, state, { read_nbt_int_array(reader, state) }
+        ),
+        TAG_LONG_ARRAY => read_simple_list!(
+            LongArray, Vec<i64>, number, state, { read_nbt_long_array(reader, state) }
         ),
         _ => return Err(NbtReadError::UnknownTagType(inner_tag_type)),
     }))
@@ -272,20 +720,21 @@ fn start_list_read(reader: &mut dyn Read) -> Result<ListStart, NbtReadError> {
  * Start reading a tag's value, where the value might be simple (TAG_INT) or complex
  * (TAG_COMPOUND).
  */
-fn start_potentially_complex_read(tag_type: u8, reader: &mut dyn Read)
-        -> Result<ReadStart, NbtReadError> {
+fn start_potentially_complex_read<R: Read + ?Sized>(
+        tag_type: u8, reader: &mut R, state: &mut ReadState)
+        -> Result<ReadStart<R>, NbtReadError> {
     let is_simple_tag = match is_simple_value(tag_type) {
         Ok(is_it) => is_it,
-        Err(_) => return Err(NbtReadError::UnknownTagType(tag_type)),
+        Err(err) => return Err(NbtReadError::UnknownTagType(err.tag_type)),
     };
     if is_simple_tag {
         return Ok(
-            ReadStart::Simple(read_simple_value(tag_type, reader)?)
+            ReadStart::Simple(read_simple_value(tag_type, reader, state)?)
         );
     }
     match tag_type {
-        TAG_LIST => return Ok(
-            match start_list_read(reader)? {
+        TAG_LIST => Ok(
+            match start_list_read(reader, state)? {
                 ListStart::Simple(list) => ReadStart::Simple(Value::List(list)),
                 ListStart::ListOfList(reading) => ReadStart::Complex(Box::new(reading)),
                 ListStart::ListOfCompound(reading) => ReadStart::Complex(Box::new(reading)),
@@ -311,26 +760,32 @@ struct ReadingCompound {
 }
 
 
-impl ReadingComplex for ReadingCompound {
-    fn continue_read(&mut self, reader: &mut dyn Read)
-            -> Result<ComplexReadResult, NbtReadError> {
+impl<R: Read + ?Sized> ReadingComplex<R> for ReadingCompound {
+    fn continue_read(&mut self, reader: &mut R, state: &mut ReadState)
+            -> Result<ComplexReadResult<R>, NbtReadError> {
         loop {
             let tag_type = reader.read_u8()?;
             if tag_type == TAG_END {
                 return Ok(ComplexReadResult::Done);
             }
 
-            let tag_name = read_nbt_string(reader)?;
+            let name = read_nbt_string(reader, state)?;
+            if state.reject_duplicate_keys && self.value.contains_key(&name) {
+                return Err(NbtReadError::DuplicateKey(name));
+            }
+            // Set before reading the value, so that describe() can name it
+            // if that fails.
+            self.name_of_current_value = Some(name);
 
             let maybe_complex_read = start_potentially_complex_read(
-                tag_type, reader,
+                tag_type, reader, state,
             )?;
             match maybe_complex_read {
                 ReadStart::Simple(value) => {
+                    let tag_name = self.name_of_current_value.take().unwrap();
                     self.value.insert(tag_name, value);
                 },
                 ReadStart::Complex(read_complex) => {
-                    self.name_of_current_value = Some(tag_name);
                     return Ok(ComplexReadResult::DescendInto(read_complex));
                 }
             }
@@ -346,6 +801,13 @@ impl ReadingComplex for ReadingCompound {
     fn final_value(self: Box<Self>) -> Value {
         Value::Compound(self.value)
     }
+
+    fn describe(&self) -> String {
+        match self.name_of_current_value {
+            Some(ref name) => format!("TAG_Compound, at entry {:?}", name),
+            None => String::from("TAG_Compound"),
+        }
+    }
 }
 
 
@@ -355,15 +817,15 @@ struct ReadingListOfList {
 }
 
 
-impl ReadingComplex for ReadingListOfList {
-    fn continue_read(&mut self, reader: &mut dyn Read)
-            -> Result<ComplexReadResult, NbtReadError> {
+impl<R: Read + ?Sized> ReadingComplex<R> for ReadingListOfList {
+    fn continue_read(&mut self, reader: &mut R, state: &mut ReadState)
+            -> Result<ComplexReadResult<R>, NbtReadError> {
         if self.items_remaining == 0 {
             return Ok(ComplexReadResult::Done);
         }
 
         let maybe_complex_read = start_potentially_complex_read(
-            TAG_LIST, reader
+            TAG_LIST, reader, state,
         )?;
         self.items_remaining -= 1;
         match maybe_complex_read {
@@ -398,6 +860,10 @@ impl ReadingComplex for ReadingListOfList {
     fn final_value(self: Box<Self>) -> Value {
         Value::List(List::List(self.value))
     }
+
+    fn describe(&self) -> String {
+        format!("TAG_List of TAG_List, at index {}", self.value.len())
+    }
 }
 
 
@@ -407,15 +873,15 @@ struct ReadingListOfCompound {
 }
 
 
-impl ReadingComplex for ReadingListOfCompound {
-    fn continue_read(&mut self, reader: &mut dyn Read)
-            -> Result<ComplexReadResult, NbtReadError> {
+impl<R: Read + ?Sized> ReadingComplex<R> for ReadingListOfCompound {
+    fn continue_read(&mut self, reader: &mut R, state: &mut ReadState)
+            -> Result<ComplexReadResult<R>, NbtReadError> {
         if self.items_remaining == 0 {
             return Ok(ComplexReadResult::Done);
         }
 
         let maybe_complex_read = start_potentially_complex_read(
-            TAG_COMPOUND, reader
+            TAG_COMPOUND, reader, state,
         )?;
         self.items_remaining -= 1;
         match maybe_complex_read {
@@ -427,7 +893,7 @@ impl ReadingComplex for ReadingListOfCompound {
                 );
             },
             ReadStart::Complex(reading_complex) => {
-                return Ok(ComplexReadResult::DescendInto(reading_complex));
+                Ok(ComplexReadResult::DescendInto(reading_complex))
             },
         }
     }
@@ -446,32 +912,153 @@ impl ReadingComplex for ReadingListOfCompound {
     fn final_value(self: Box<Self>) -> Value {
         Value::List(List::Compound(self.value))
     }
+
+    fn describe(&self) -> String {
+        format!("TAG_List of TAG_Compound, at index {}", self.value.len())
+    }
+}
+
+
+pub fn parse_nbt_stream<R: Read + ?Sized>(reader: &mut R) -> Result<RootValue, NbtReadError> {
+    parse_nbt_stream_with_options(reader, &ReaderOptions::default())
+}
+
+
+/// Like `parse_nbt_stream`, but with `ReaderOptions::strict`: fails with
+/// `NbtReadError::TrailingData` if the stream doesn't end with the root tag,
+/// or `NbtReadError::DuplicateKey` if a compound repeats a name.
+pub fn parse_nbt_stream_strict<R: Read + ?Sized>(reader: &mut R)
+        -> Result<RootValue, NbtReadError> {
+    parse_nbt_stream_with_options(reader, &ReaderOptions::strict())
+}
+
+
+/// Like `parse_nbt_stream`, with limits other than the default ones.
+pub fn parse_nbt_stream_with_limits<R: Read + ?Sized>(
+        reader: &mut R, limits: &ReadLimits)
+        -> Result<RootValue, NbtReadError> {
+    let options = ReaderOptions {
+        limits: limits.clone(),
+        ..ReaderOptions::default()
+    };
+    parse_nbt_stream_with_options(reader, &options)
 }
 
 
-pub fn parse_nbt_stream(reader: &mut dyn Read) -> Result<RootValue, NbtReadError> {
+fn error_in_structure<R: Read + ?Sized>(
+        options: &ReaderOptions, in_progress_reads: &[Box<dyn ReadingComplex<R>>],
+        error: NbtReadError)
+        -> NbtReadError {
+    if !options.capture_stack_on_error {
+        return error;
+    }
+    NbtReadError::WithStack {
+        error: Box::new(error),
+        stack: in_progress_reads.iter().map(|r| r.describe()).collect(),
+    }
+}
+
+
+/// Like `parse_nbt_stream`, for a stream in the given byte order.
+pub fn parse_nbt_stream_with_endianness<R: Read + ?Sized>(
+        reader: &mut R, endianness: Endianness)
+        -> Result<RootValue, NbtReadError> {
+    let options = ReaderOptions {
+        endianness,
+        ..ReaderOptions::default()
+    };
+    parse_nbt_stream_with_options(reader, &options)
+}
+
+
+/// Like `parse_nbt_stream`, but configurable.
+pub fn parse_nbt_stream_with_options<R: Read + ?Sized>(
+        reader: &mut R, options: &ReaderOptions)
+        -> Result<RootValue, NbtReadError> {
+    parse_nbt_stream_with_context(reader, options, &mut ParseContext::new())
+}
+
+
+/// Like `parse_nbt_stream_with_options`, borrowing scratch buffers from
+/// `context` instead of allocating its own. Passing the same context to each
+/// parse saves allocations when parsing many streams in a row.
+pub fn parse_nbt_stream_with_context<R: Read + ?Sized>(
+        reader: &mut R, options: &ReaderOptions, context: &mut ParseContext)
+        -> Result<RootValue, NbtReadError> {
+    if !options.report_offset_on_error {
+        return parse_root(reader, options, context);
+    }
+    let mut counting_reader = CountingReader { inner: reader, count: 0 };
+    parse_root(&mut counting_reader, options, context).map_err(|error| NbtReadError::At {
+        offset: counting_reader.count,
+        error: Box::new(error),
+    })
+}
+
+
+fn parse_root<R: Read + ?Sized>(
+        reader: &mut R, options: &ReaderOptions, context: &mut ParseContext)
+        -> Result<RootValue, NbtReadError> {
+    let mut state = ReadState::new(options, context);
     let root_tag_type = reader.read_u8()?;
-    let root_tag_name = read_nbt_string(reader)?;
+    let root_tag_name = read_nbt_string(reader, &mut state)?;
+    let value = read_root_value(root_tag_type, reader, options, &mut state)?;
+    if options.reject_trailing_data {
+        expect_end(reader)?;
+    }
+    Ok(RootValue {
+        name: root_tag_name,
+        value,
+    })
+}
+
+
+fn expect_end<R: Read + ?Sized>(reader: &mut R) -> Result<(), NbtReadError> {
+    let mut byte = [0u8];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(()),
+            Ok(_) => return Err(NbtReadError::TrailingData),
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(NbtReadError::from(err)),
+        }
+    }
+}
 
-    let read_start = start_potentially_complex_read(root_tag_type, reader)?;
+
+/// Read the root tag's value, of type `root_tag_type`.
+fn read_root_value<R: Read + ?Sized>(
+        root_tag_type: u8, reader: &mut R, options: &ReaderOptions,
+        state: &mut ReadState)
+        -> Result<Value, NbtReadError> {
+    let read_start = start_potentially_complex_read(root_tag_type, reader, state)?;
     let reading = match read_start {
-        ReadStart::Simple(value) => return Ok(RootValue {
-            name: root_tag_name,
-            value: value,
-        }),
+        ReadStart::Simple(value) => return Ok(value),
         ReadStart::Complex(reading_) => reading_,
     };
-    let mut in_progress_reads = Vec::<Box<dyn ReadingComplex>>::new();
+    if options.limits.max_depth == 0 {
+        return Err(NbtReadError::MaxDepthExceeded);
+    }
+    let mut in_progress_reads = Vec::<Box<dyn ReadingComplex<R>>>::new();
     in_progress_reads.push(reading);
 
     loop {
         let result = {
             let working_read = in_progress_reads.last_mut().unwrap();
-            working_read.continue_read(reader)?
+            match working_read.continue_read(reader, state) {
+                Ok(result) => result,
+                Err(error) => {
+                    return Err(error_in_structure(options, &in_progress_reads, error));
+                },
+            }
         };
         match result {
             ComplexReadResult::NotFinished => (),
             ComplexReadResult::DescendInto(next_read) => {
+                if in_progress_reads.len() >= options.limits.max_depth {
+                    let error = NbtReadError::MaxDepthExceeded;
+                    return Err(error_in_structure(options, &in_progress_reads, error));
+                }
                 in_progress_reads.push(next_read);
             },
             ComplexReadResult::Done => {
@@ -481,14 +1068,652 @@ pub fn parse_nbt_stream(reader: &mut dyn Read) -> Result<RootValue, NbtReadError
                     Some(working_read) => {
                         working_read.descended_read_complete(value);
                     },
-                    None => {
-                        return Ok(RootValue {
-                            name: root_tag_name,
-                            value: value,
-                        });
-                    },
+                    None => return Ok(value),
                 };
             },
         }
     }
 }
+
+
+/// Parse NBT as sent in play packets since protocol 764 (1.20.2): the root
+/// tag's type, then straight away its value, with no name.
+///
+/// The protocol sends a lone `TAG_End` for "no NBT"; that gives
+/// `NbtReadError::InvalidTagType`.
+pub fn parse_network_nbt<R: Read + ?Sized>(reader: &mut R) -> Result<Value, NbtReadError> {
+    let options = ReaderOptions::default();
+    let mut context = ParseContext::new();
+    let mut state = ReadState::new(&options, &mut context);
+    let root_tag_type = reader.read_u8()?;
+    if root_tag_type == TAG_END {
+        return Err(NbtReadError::InvalidTagType);
+    }
+    read_root_value(root_tag_type, reader, &options, &mut state)
+}
+
+
+/// Parse NBT as Bedrock Edition sends it over the network: little-endian,
+/// with ints, longs and lengths as VarInts (see `ReaderOptions::varints`).
+pub fn parse_bedrock_network_nbt<R: Read + ?Sized>(reader: &mut R)
+        -> Result<RootValue, NbtReadError> {
+    let options = ReaderOptions {
+        endianness: Endianness::Little,
+        varints: true,
+        ..ReaderOptions::default()
+    };
+    parse_nbt_stream_with_options(reader, &options)
+}
+
+
+/// A tag that isn't a compound or list, as passed to `NbtVisitor::tag`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScalarValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+
+impl From<ScalarValue> for Value {
+    fn from(value: ScalarValue) -> Value {
+        match value {
+            ScalarValue::Byte(v) => Value::Byte(v),
+            ScalarValue::Short(v) => Value::Short(v),
+            ScalarValue::Int(v) => Value::Int(v),
+            ScalarValue::Long(v) => Value::Long(v),
+            ScalarValue::Float(v) => Value::Float(v),
+            ScalarValue::Double(v) => Value::Double(v),
+            ScalarValue::ByteArray(v) => Value::ByteArray(v),
+            ScalarValue::String(v) => Value::String(v),
+            ScalarValue::IntArray(v) => Value::IntArray(v),
+            ScalarValue::LongArray(v) => Value::LongArray(v),
+        }
+    }
+}
+
+
+/// Callbacks for `visit_nbt_stream`, made as each tag is read.
+///
+/// `name` is the tag's name: the root's name for the root tag, the entry's
+/// key within a compound, and `None` for a list element. Every
+/// `start_compound` is matched by an `end_compound` and every `start_list`
+/// by an `end_list`, unless reading fails part way. Each method does nothing
+/// by default.
+pub trait NbtVisitor {
+    fn start_compound(&mut self, _name: Option<&str>) {}
+    fn end_compound(&mut self) {}
+    /// A list of `len` elements of type `element_type` starts. For an empty
+    /// list, `element_type` may be `TAG_End`.
+    fn start_list(&mut self, _name: Option<&str>, _element_type: u8, _len: usize) {}
+    fn end_list(&mut self) {}
+    fn tag(&mut self, _name: Option<&str>, _value: ScalarValue) {}
+}
+
+
+/// A compound or list being visited. A compound's `keys` are only kept
+/// with `ReaderOptions::reject_duplicate_keys`.
+enum VisitFrame {
+    Compound {
+        keys: HashSet<String>,
+    },
+    List {
+        element_type: u8,
+        len: usize,
+        remaining: usize,
+    },
+}
+
+
+/// Where each value's payload lies in an NBT stream: the `start` and `end`
+/// byte offsets, keyed by the value's path as used by `Value::get_path`
+/// (`""` for the root). A value's payload excludes its tag type and name.
+pub type SpanMap = HashMap<String, (u64, u64)>;
+
+
+/// Builds a `SpanMap` during a visit. `open` holds the path and start
+/// offset of each compound or list not yet finished.
+#[derive(Default)]
+struct SpanRecorder {
+    spans: SpanMap,
+    open: Vec<(String, u64)>,
+}
+
+
+impl SpanRecorder {
+    fn child_path(&self, segment: &str) -> String {
+        match self.open.last() {
+            Some((parent, _)) if !parent.is_empty() => format!("{}.{}", parent, segment),
+            _ => segment.to_string(),
+        }
+    }
+
+    fn close(&mut self, end: u64) {
+        if let Some((path, start)) = self.open.pop() {
+            self.spans.insert(path, (start, end));
+        }
+    }
+}
+
+
+/// Visit a tag's value, of type `tag_type`. A compound, or a list of lists
+/// or compounds, is only started; the frame to visit the rest with is
+/// returned.
+///
+/// If `span` is given, the value's span is recorded under its path, or
+/// opened if the value is only started.
+fn start_visit<R: Read + ?Sized, V: NbtVisitor + ?Sized>(
+        tag_type: u8, name: Option<&str>, reader: &mut CountingReader<R>,
+        state: &mut ReadState, visitor: &mut V, span: Option<(String, &mut SpanRecorder)>)
+        -> Result<Option<VisitFrame>, NbtReadError> {
+    let start = reader.count;
+    match tag_type {
+        TAG_COMPOUND => {
+            visitor.start_compound(name);
+            if let Some((path, spans)) = span {
+                spans.open.push((path, start));
+            }
+            Ok(Some(VisitFrame::Compound { keys: HashSet::new() }))
+        },
+        TAG_LIST => {
+            let element_type = reader.read_u8()?;
+            let len = read_length(reader, state)?;
+            match is_simple_value(element_type) {
+                Err(_) if len == 0 && element_type == TAG_END => (),
+                Err(_) if element_type == TAG_END => return Err(NbtReadError::InvalidTagType),
+                Err(err) => return Err(NbtReadError::UnknownTagType(err.tag_type)),
+                Ok(_) => (),
+            }
+            visitor.start_list(name, element_type, len);
+            if len > 0 && (element_type == TAG_LIST || element_type == TAG_COMPOUND) {
+                if let Some((path, spans)) = span {
+                    spans.open.push((path, start));
+                }
+                return Ok(Some(VisitFrame::List { element_type, len, remaining: len }));
+            }
+            let mut span = span;
+            for index in 0..len {
+                let element_start = reader.count;
+                visitor.tag(None, read_scalar_value(element_type, reader, state)?);
+                if let Some((path, spans)) = &mut span {
+                    let element_path = format!("{}.{}", path, index);
+                    spans.spans.insert(element_path, (element_start, reader.count));
+                }
+            }
+            visitor.end_list();
+            if let Some((path, spans)) = span {
+                spans.spans.insert(path, (start, reader.count));
+            }
+            Ok(None)
+        },
+        _ => {
+            if let Err(err) = is_simple_value(tag_type) {
+                return Err(NbtReadError::UnknownTagType(err.tag_type));
+            }
+            visitor.tag(name, read_scalar_value(tag_type, reader, state)?);
+            if let Some((path, spans)) = span {
+                spans.spans.insert(path, (start, reader.count));
+            }
+            Ok(None)
+        },
+    }
+}
+
+
+fn visit_root<R: Read + ?Sized, V: NbtVisitor + ?Sized>(
+        reader: &mut CountingReader<R>, options: &ReaderOptions, visitor: &mut V,
+        mut spans: Option<&mut SpanRecorder>)
+        -> Result<(), NbtReadError> {
+    let mut context = ParseContext::new();
+    let mut state = ReadState::new(options, &mut context);
+    let root_tag_type = reader.read_u8()?;
+    let root_tag_name = read_nbt_string(reader, &mut state)?;
+    let mut frames = Vec::new();
+    let span = spans.as_deref_mut().map(|spans| (String::new(), spans));
+    let mut next = start_visit(
+        root_tag_type, Some(&root_tag_name), reader, &mut state, visitor, span)?;
+    loop {
+        if let Some(frame) = next.take() {
+            if frames.len() >= options.limits.max_depth {
+                return Err(NbtReadError::MaxDepthExceeded);
+            }
+            frames.push(frame);
+        }
+        next = match frames.last_mut() {
+            None if options.reject_trailing_data => return expect_end(reader),
+            None => return Ok(()),
+            Some(VisitFrame::Compound { keys }) => {
+                let tag_type = reader.read_u8()?;
+                if tag_type == TAG_END {
+                    frames.pop();
+                    visitor.end_compound();
+                    if let Some(spans) = spans.as_deref_mut() {
+                        spans.close(reader.count);
+                    }
+                    continue;
+                }
+                let name = read_nbt_string(reader, &mut state)?;
+                if state.reject_duplicate_keys && !keys.insert(name.clone()) {
+                    return Err(NbtReadError::DuplicateKey(name));
+                }
+                let span = spans.as_deref_mut().map(|spans| (spans.child_path(&name), spans));
+                start_visit(tag_type, Some(&name), reader, &mut state, visitor, span)?
+            },
+            Some(VisitFrame::List { element_type, len, remaining }) => {
+                if *remaining == 0 {
+                    frames.pop();
+                    visitor.end_list();
+                    if let Some(spans) = spans.as_deref_mut() {
+                        spans.close(reader.count);
+                    }
+                    continue;
+                }
+                let index = (*len - *remaining).to_string();
+                *remaining -= 1;
+                let element_type = *element_type;
+                let span = spans.as_deref_mut().map(|spans| (spans.child_path(&index), spans));
+                start_visit(element_type, None, reader, &mut state, visitor, span)?
+            },
+        };
+    }
+}
+
+
+/// Read an NBT stream as `parse_nbt_stream` does, but rather than building a
+/// `Value`, call `visitor` for each tag as it is read. Only the tag being
+/// read is held in memory, so a large stream can be searched without
+/// allocating the whole tree.
+pub fn visit_nbt_stream<R: Read + ?Sized, V: NbtVisitor + ?Sized>(
+        reader: &mut R, visitor: &mut V)
+        -> Result<(), NbtReadError> {
+    visit_nbt_stream_with_options(reader, &ReaderOptions::default(), visitor)
+}
+
+
+/// Like `visit_nbt_stream`, but configurable. `capture_stack_on_error` is
+/// ignored: a visitor can keep track of where it is itself.
+pub fn visit_nbt_stream_with_options<R: Read + ?Sized, V: NbtVisitor + ?Sized>(
+        reader: &mut R, options: &ReaderOptions, visitor: &mut V)
+        -> Result<(), NbtReadError> {
+    let mut counting_reader = CountingReader { inner: reader, count: 0 };
+    let result = visit_root(&mut counting_reader, options, visitor, None);
+    if !options.report_offset_on_error {
+        return result;
+    }
+    result.map_err(|error| NbtReadError::At {
+        offset: counting_reader.count,
+        error: Box::new(error),
+    })
+}
+
+
+/// Like `parse_nbt_stream_with_options`, but also records where in the
+/// stream each value was read from, so that an editor can map a value back
+/// to the bytes it came from. Lists' elements are given spans too, under
+/// paths ending in their index.
+///
+/// Offsets are into the stream as read, after any decompression.
+/// `capture_stack_on_error` is ignored.
+pub fn parse_nbt_stream_with_spans<R: Read + ?Sized>(
+        reader: &mut R, options: &ReaderOptions)
+        -> Result<(RootValue, SpanMap), NbtReadError> {
+    let mut counting_reader = CountingReader { inner: reader, count: 0 };
+    let mut builder = TreeBuilder::new();
+    let mut spans = SpanRecorder::default();
+    let result = visit_root(&mut counting_reader, options, &mut builder, Some(&mut spans));
+    if let Err(error) = result {
+        if !options.report_offset_on_error {
+            return Err(error);
+        }
+        return Err(NbtReadError::At { offset: counting_reader.count, error: Box::new(error) });
+    }
+    // A successful visit always finishes the root.
+    Ok((builder.into_root().unwrap(), spans.spans))
+}
+
+
+/// A compound or list being built by `TreeBuilder`, and its name.
+#[derive(Debug)]
+enum Building {
+    Compound(Option<String>, Compound),
+    List(Option<String>, u8, Vec<Value>),
+}
+
+
+/// An `NbtVisitor` that builds the tree `parse_nbt_stream` would have
+/// returned.
+#[derive(Debug, Default)]
+pub struct TreeBuilder {
+    in_progress: Vec<Building>,
+    root: Option<RootValue>,
+}
+
+
+impl TreeBuilder {
+    pub fn new() -> TreeBuilder {
+        TreeBuilder::default()
+    }
+
+    /// The root tag, or `None` if it hasn't been completely visited.
+    pub fn into_root(self) -> Option<RootValue> {
+        self.root
+    }
+
+    fn add(&mut self, name: Option<String>, value: Value) {
+        match self.in_progress.last_mut() {
+            Some(Building::Compound(_, compound)) => {
+                compound.insert(name.unwrap_or_default(), value);
+            },
+            Some(Building::List(_, _, values)) => values.push(value),
+            None => {
+                self.root = Some(RootValue { name: name.unwrap_or_default(), value });
+            },
+        }
+    }
+}
+
+
+impl NbtVisitor for TreeBuilder {
+    fn start_compound(&mut self, name: Option<&str>) {
+        self.in_progress.push(Building::Compound(name.map(str::to_string), Compound::new()));
+    }
+
+    fn end_compound(&mut self) {
+        if let Some(Building::Compound(name, compound)) = self.in_progress.pop() {
+            self.add(name, Value::Compound(compound));
+        }
+    }
+
+    fn start_list(&mut self, name: Option<&str>, element_type: u8, _len: usize) {
+        self.in_progress.push(Building::List(name.map(str::to_string), element_type, Vec::new()));
+    }
+
+    fn end_list(&mut self) {
+        if let Some(Building::List(name, element_type, values)) = self.in_progress.pop() {
+            let list = if values.is_empty() {
+                List::Empty(element_type)
+            } else {
+                // The reader only visits lists of a single type.
+                List::from_values(values).unwrap()
+            };
+            self.add(name, Value::List(list));
+        }
+    }
+
+    fn tag(&mut self, name: Option<&str>, value: ScalarValue) {
+        self.add(name.map(str::to_string), Value::from(value));
+    }
+}
+
+
+
+/// How an NBT stream is compressed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Compression {
+    None,
+    /// As in `level.dat` and player data files.
+    Gzip,
+    /// As in region file chunks (compression type 2).
+    Zlib,
+}
+
+
+/// Parse an NBT stream with the given compression. `Compression::None` is
+/// the same as `parse_nbt_stream`.
+pub fn parse_nbt_stream_with<R: Read + ?Sized>(
+        reader: &mut R, compression: Compression)
+        -> Result<RootValue, NbtReadError> {
+    match compression {
+        Compression::None => parse_nbt_stream(reader),
+        Compression::Gzip => decompression_error(parse_nbt_stream(&mut GzDecoder::new(reader))),
+        Compression::Zlib => decompression_error(parse_nbt_stream(&mut ZlibDecoder::new(reader))),
+    }
+}
+
+
+/// Tell decompression errors from others in the result of parsing a
+/// decompressed stream.
+fn decompression_error<T>(result: Result<T, NbtReadError>) -> Result<T, NbtReadError> {
+    // flate2 reports corrupt data as InvalidInput or InvalidData, neither of
+    // which the parser itself produces.
+    match result {
+        Err(NbtReadError::IoError(err)) => match err.kind() {
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => {
+                Err(NbtReadError::Decompression(err))
+            },
+            _ => Err(NbtReadError::IoError(err)),
+        },
+        result => result,
+    }
+}
+
+
+/// Parse an (uncompressed) NBT stream that's already in memory.
+pub fn parse_nbt_bytes(bytes: &[u8]) -> Result<RootValue, NbtReadError> {
+    parse_nbt_stream(&mut Cursor::new(bytes))
+}
+
+
+/// Like `parse_nbt_bytes`, for bytes with the given compression.
+pub fn parse_nbt_bytes_with(bytes: &[u8], compression: Compression)
+        -> Result<RootValue, NbtReadError> {
+    parse_nbt_stream_with(&mut Cursor::new(bytes), compression)
+}
+
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+
+/// Read up to the first two bytes of `reader`, for checking for
+/// `GZIP_MAGIC`.
+fn peek_magic<R: Read + ?Sized>(reader: &mut R) -> Result<([u8; 2], usize), NbtReadError> {
+    let mut magic = [0u8; 2];
+    let mut peeked = 0;
+    while peeked < magic.len() {
+        match reader.read(&mut magic[peeked..]) {
+            Ok(0) => break,
+            Ok(read) => peeked += read,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(NbtReadError::from(err)),
+        }
+    }
+    Ok((magic, peeked))
+}
+
+
+/// Parse an NBT file, such as `level.dat`, that may or may not be
+/// gzip-compressed. Compression is detected from the gzip magic number.
+pub fn parse_nbt_file<R: Read>(reader: R) -> Result<RootValue, NbtReadError> {
+    parse_nbt_file_with_options(reader, &ReaderOptions::default())
+}
+
+
+/// Like `parse_nbt_file`, but configurable. A file that's still gzipped
+/// after being decompressed is decompressed again, up to
+/// `ReadLimits::max_decompress_layers` times.
+pub fn parse_nbt_file_with_options<'a, R: Read + 'a>(
+        reader: R, options: &ReaderOptions)
+        -> Result<RootValue, NbtReadError> {
+    let mut reader: Box<dyn Read + 'a> = Box::new(reader);
+    let mut layers = 0;
+    loop {
+        let (magic, peeked) = decompression_error(peek_magic(&mut reader))?;
+        reader = Box::new(Cursor::new(magic).take(peeked as u64).chain(reader));
+        if magic[..peeked] != GZIP_MAGIC {
+            break;
+        }
+        if layers == options.limits.max_decompress_layers {
+            return Err(NbtReadError::TooManyCompressionLayers);
+        }
+        layers += 1;
+        reader = Box::new(GzDecoder::new(reader));
+    }
+    decompression_error(parse_nbt_stream_with_options(&mut reader, options))
+}
+
+
+/// Wraps a reader, keeping count of the bytes read through it.
+struct CountingReader<'a, R: Read + ?Sized> {
+    inner: &'a mut R,
+    count: u64,
+}
+
+
+impl<'a, R: Read + ?Sized> Read for CountingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+
+/// Statistics about the shape of a parsed NBT stream, for choosing sensible
+/// limits when parsing untrusted data.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ParseStats {
+    /// The deepest nesting of any tag; the root tag is at depth 0, its
+    /// entries (or elements) at depth 1, and so on.
+    pub max_depth: usize,
+    /// The length of the longest list.
+    pub max_list_len: usize,
+    /// Every tag, including the root and each list element.
+    pub total_tags: usize,
+    /// The number of bytes read from the stream.
+    pub total_bytes: u64,
+}
+
+
+impl ParseStats {
+    fn saw_tags(&mut self, count: usize, depth: usize) {
+        if count > 0 {
+            self.total_tags += count;
+            self.max_depth = cmp::max(self.max_depth, depth);
+        }
+    }
+
+    fn add_value(&mut self, value: &Value, depth: usize) {
+        self.saw_tags(1, depth);
+        match value {
+            Value::List(list) => self.add_list_elements(list, depth + 1),
+            Value::Compound(compound) => self.add_compound_entries(compound, depth + 1),
+            _ => (),
+        }
+    }
+
+    fn add_compound_entries(&mut self, compound: &Compound, depth: usize) {
+        for value in compound.values() {
+            self.add_value(value, depth);
+        }
+    }
+
+    fn add_list_elements(&mut self, list: &List, depth: usize) {
+        let length = match list {
+            List::Empty(_) => 0,
+            List::Byte(v) => v.len(),
+            List::Short(v) => v.len(),
+            List::Int(v) => v.len(),
+            List::Long(v) => v.len(),
+            List::Float(v) => v.len(),
+            List::Double(v) => v.len(),
+            List::ByteArray(v) => v.len(),
+            List::String(v) => v.len(),
+            List::List(lists) => {
+                for inner in lists {
+                    self.add_list_elements(inner, depth + 1);
+                }
+                lists.len()
+            },
+            List::Compound(compounds) => {
+                for compound in compounds {
+                    self.add_compound_entries(compound, depth + 1);
+                }
+                compounds.len()
+            },
+            List::IntArray(v) => v.len(),
+            List::LongArray(v) => v.len(),
+        };
+        self.max_list_len = cmp::max(self.max_list_len, length);
+        self.saw_tags(length, depth);
+    }
+}
+
+
+/// Like `parse_nbt_stream`, but also returns statistics about the stream.
+pub fn parse_nbt_stream_with_stats<R: Read + ?Sized>(reader: &mut R)
+        -> Result<(RootValue, ParseStats), NbtReadError> {
+    let mut counting_reader = CountingReader { inner: reader, count: 0 };
+    let root = parse_nbt_stream(&mut counting_reader)?;
+    let mut stats = ParseStats {
+        total_bytes: counting_reader.count,
+        ..ParseStats::default()
+    };
+    stats.add_value(&root.value, 0);
+    Ok((root, stats))
+}
+
+/// Checks that `data[offset..]` starts with a string whose length prefix,
+/// read with the given byte order, fits in the data and is valid text.
+/// Returns the offset just past the string.
+fn plausible_string(data: &[u8], offset: usize, endianness: Endianness)
+        -> Option<usize> {
+    let prefix = data.get(offset..offset + 2)?;
+    let length = match endianness {
+        Endianness::Big => BigEndian::read_u16(prefix),
+        Endianness::Little => LittleEndian::read_u16(prefix),
+    } as usize;
+    let end = offset + 2 + length;
+    decode_modified_utf8(data.get(offset + 2..end)?).ok()?;
+    Some(end)
+}
+
+
+fn plausible_header(data: &[u8], endianness: Endianness) -> bool {
+    let root_tag_type = match data.first() {
+        Some(&tag_type) => tag_type,
+        None => return false,
+    };
+    if root_tag_type == TAG_END || is_simple_value(root_tag_type).is_err() {
+        return false;
+    }
+    let after_name = match plausible_string(data, 1, endianness) {
+        Some(end) => end,
+        None => return false,
+    };
+    // Root compounds are the common case; their first entry's header gives
+    // us a second length to check, which matters when the root name is empty
+    // (and so reads as zero either way).
+    if root_tag_type != TAG_COMPOUND {
+        return true;
+    }
+    match data.get(after_name) {
+        None => false,
+        Some(&TAG_END) => true,
+        Some(&tag_type) => {
+            is_simple_value(tag_type).is_ok()
+                && plausible_string(data, after_name + 1, endianness).is_some()
+        },
+    }
+}
+
+
+/// Guess whether `data` holds big-endian (Java) or little-endian (Bedrock)
+/// NBT, by checking which byte order yields a sensible root tag header.
+///
+/// Returns `None` if neither, or both, byte orders look plausible.
+pub fn guess_endianness(data: &[u8]) -> Option<Endianness> {
+    let big = plausible_header(data, Endianness::Big);
+    let little = plausible_header(data, Endianness::Little);
+    match (big, little) {
+        (true, false) => Some(Endianness::Big),
+        (false, true) => Some(Endianness::Little),
+        _ => None,
+    }
+}
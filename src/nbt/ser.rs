@@ -0,0 +1,477 @@
+//! Serializing Rust types as NBT with serde; the counterpart of `de`.
+//!
+//! Structs and maps serialize as compounds, leaving out `None` fields, and
+//! sequences and tuples as lists, whose elements must all serialize as the
+//! same tag type. Signed integers and floats map to the tag of the same
+//! size; unsigned integers take the next larger tag, so that they read back
+//! unchanged, except `u64`, which must fit in a `TAG_Long`. `bool` is a
+//! `TAG_Byte`. Enum variants are written as in JSON: unit variants as their
+//! name, others as a compound with one entry, named for the variant.
+//!
+//! serde can't tell a `Vec<i32>` from an int array, so sequences are always
+//! lists. To write a `TAG_Byte_Array`, `TAG_Int_Array` or `TAG_Long_Array`,
+//! wrap the data in `ByteArray`, `IntArray` or `LongArray`.
+
+use std::error;
+use std::fmt;
+use std::io::Write;
+
+use serde::ser::{self, Serialize};
+use serde::Deserialize;
+
+use super::writer::{self, NbtWriteError};
+use super::{Compound, List, RootValue, Value};
+
+
+#[derive(Debug)]
+pub enum SerializeError {
+    /// The message from a `Serialize` implementation.
+    Message(String),
+    /// A type NBT has no equivalent for, such as `()` or a `u64` past
+    /// `i64::MAX`.
+    Unsupported(&'static str),
+    /// A sequence's elements serialized as different tag types.
+    MixedList,
+    /// A map key didn't serialize as a string.
+    KeyNotString,
+    /// A `None` outside of a struct or map, where it can't be left out.
+    NoneValue,
+    Write(NbtWriteError),
+}
+
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerializeError::Message(message) => f.write_str(message),
+            SerializeError::Unsupported(what) => write!(f, "NBT can't represent {}", what),
+            SerializeError::MixedList => f.write_str("list elements have different types"),
+            SerializeError::KeyNotString => f.write_str("map key isn't a string"),
+            SerializeError::NoneValue => f.write_str("None outside of a struct or map"),
+            SerializeError::Write(err) => write!(f, "writing NBT failed: {:?}", err),
+        }
+    }
+}
+
+
+impl error::Error for SerializeError {}
+
+
+impl ser::Error for SerializeError {
+    fn custom<T: fmt::Display>(msg: T) -> SerializeError {
+        SerializeError::Message(msg.to_string())
+    }
+}
+
+
+impl From<NbtWriteError> for SerializeError {
+    fn from(err: NbtWriteError) -> SerializeError {
+        SerializeError::Write(err)
+    }
+}
+
+
+// The newtype struct names the serializer recognises the wrappers by.
+const BYTE_ARRAY: &str = "$minecraft::nbt::ByteArray";
+const INT_ARRAY: &str = "$minecraft::nbt::IntArray";
+const LONG_ARRAY: &str = "$minecraft::nbt::LongArray";
+
+
+/// Serializes as a `TAG_Byte_Array`, rather than a list.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct ByteArray(pub Vec<u8>);
+
+
+/// Serializes as a `TAG_Int_Array`, rather than a list.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct IntArray(pub Vec<i32>);
+
+
+/// Serializes as a `TAG_Long_Array`, rather than a list.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct LongArray(pub Vec<i64>);
+
+
+struct Bytes<'a>(&'a [u8]);
+
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+
+impl Serialize for ByteArray {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(BYTE_ARRAY, &Bytes(&self.0))
+    }
+}
+
+
+impl Serialize for IntArray {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(INT_ARRAY, &self.0)
+    }
+}
+
+
+impl Serialize for LongArray {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(LONG_ARRAY, &self.0)
+    }
+}
+
+
+/// Serialize `value`, which must not be `None`.
+fn to_value<T: ?Sized + Serialize>(value: &T) -> Result<Value, SerializeError> {
+    value.serialize(ValueSerializer)?.ok_or(SerializeError::NoneValue)
+}
+
+
+/// Wrap `value` in a compound, for an enum variant.
+fn variant_compound(variant: &'static str, value: Value) -> Value {
+    let mut compound = Compound::new();
+    compound.insert(variant.to_string(), value);
+    Value::Compound(compound)
+}
+
+
+/// Builds `Value`s. `None` serializes as `Ok(None)`, so that compounds can
+/// leave it out.
+struct ValueSerializer;
+
+
+/// Collects the elements of a sequence, tuple or tuple variant.
+struct ListSerializer {
+    values: Vec<Value>,
+    variant: Option<&'static str>,
+}
+
+
+impl ListSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Value>, SerializeError> {
+        let list = List::from_values(self.values).ok_or(SerializeError::MixedList)?;
+        Ok(Some(match self.variant {
+            Some(variant) => variant_compound(variant, Value::List(list)),
+            None => Value::List(list),
+        }))
+    }
+}
+
+
+/// Collects the entries of a map, struct or struct variant.
+struct CompoundSerializer {
+    compound: Compound,
+    variant: Option<&'static str>,
+    key: Option<String>,
+}
+
+
+impl CompoundSerializer {
+    fn insert<T: ?Sized + Serialize>(&mut self, key: String, value: &T)
+            -> Result<(), SerializeError> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.compound.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Value>, SerializeError> {
+        let compound = Value::Compound(self.compound);
+        Ok(Some(match self.variant {
+            Some(variant) => variant_compound(variant, compound),
+            None => compound,
+        }))
+    }
+}
+
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = CompoundSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Byte(v as i8)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Byte(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Short(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Int(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Long(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Short(i16::from(v))))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Int(i32::from(v))))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Long(i64::from(v))))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>, SerializeError> {
+        if v > i64::MAX as u64 {
+            return Err(SerializeError::Unsupported("a u64 past i64::MAX"));
+        }
+        Ok(Some(Value::Long(v as i64)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::ByteArray(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>, SerializeError> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T)
+            -> Result<Option<Value>, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>, SerializeError> {
+        Err(SerializeError::Unsupported("()"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str)
+            -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::Compound(Compound::new())))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str)
+            -> Result<Option<Value>, SerializeError> {
+        Ok(Some(Value::String(variant.to_string())))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, value: &T)
+            -> Result<Option<Value>, SerializeError> {
+        let value = to_value(value)?;
+        Ok(Some(match (name, value) {
+            (INT_ARRAY, Value::List(List::Empty(_))) => Value::IntArray(Vec::new()),
+            (INT_ARRAY, Value::List(List::Int(ints))) => Value::IntArray(ints),
+            (LONG_ARRAY, Value::List(List::Empty(_))) => Value::LongArray(Vec::new()),
+            (LONG_ARRAY, Value::List(List::Long(longs))) => Value::LongArray(longs),
+            (_, value) => value,
+        }))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self, _name: &'static str, _index: u32, variant: &'static str, value: &T)
+            -> Result<Option<Value>, SerializeError> {
+        Ok(Some(variant_compound(variant, to_value(value)?)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, SerializeError> {
+        Ok(ListSerializer { values: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize)
+            -> Result<ListSerializer, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+            self, _name: &'static str, _index: u32, variant: &'static str, len: usize)
+            -> Result<ListSerializer, SerializeError> {
+        Ok(ListSerializer { values: Vec::with_capacity(len), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<CompoundSerializer, SerializeError> {
+        Ok(CompoundSerializer { compound: Compound::new(), variant: None, key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize)
+            -> Result<CompoundSerializer, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+            self, _name: &'static str, _index: u32, variant: &'static str, _len: usize)
+            -> Result<CompoundSerializer, SerializeError> {
+        Ok(CompoundSerializer { compound: Compound::new(), variant: Some(variant), key: None })
+    }
+}
+
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T)
+            -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T)
+            -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T)
+            -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T)
+            -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeMap for CompoundSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T)
+            -> Result<(), SerializeError> {
+        match to_value(key)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(SerializeError::KeyNotString),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T)
+            -> Result<(), SerializeError> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeStruct for CompoundSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T)
+            -> Result<(), SerializeError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+impl ser::SerializeStructVariant for CompoundSerializer {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T)
+            -> Result<(), SerializeError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Option<Value>, SerializeError> {
+        self.finish()
+    }
+}
+
+
+/// Serialize `value` as a `Value`.
+pub fn to_nbt_value<T: ?Sized + Serialize>(value: &T) -> Result<Value, SerializeError> {
+    to_value(value)
+}
+
+
+/// Serialize `value` and write it as an (uncompressed) NBT stream, with a
+/// root tag named `root_name`. Files like `level.dat` have a compound as
+/// the root, i.e. `value` should be a struct or map.
+pub fn to_nbt_writer<W: Write, T: ?Sized + Serialize>(
+        writer: &mut W, root_name: &str, value: &T)
+        -> Result<(), SerializeError> {
+    let root = RootValue {
+        name: root_name.to_string(),
+        value: to_value(value)?,
+    };
+    writer::write_nbt_stream(writer, &root)?;
+    Ok(())
+}
@@ -0,0 +1,521 @@
+//! SNBT ("stringified NBT"), the text form of NBT used in commands and data
+//! packs, e.g. `{name:"Steve",Health:20.0f,Inventory:[]}`.
+
+use std::fmt::{Debug, Write};
+
+use super::{Compound, List, Value};
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SnbtParseError {
+    /// The input ended part way through a value.
+    UnexpectedEnd,
+    /// `found` was at byte offset `position`, where `expected` should have
+    /// been.
+    Unexpected {
+        position: usize,
+        found: char,
+        expected: &'static str,
+    },
+    /// A list, starting at `position`, had elements of different types.
+    MixedList {
+        position: usize,
+    },
+    /// A `[B;`, `[I;` or `[L;` array, starting at `position`, had an element
+    /// of the wrong type.
+    MixedArray {
+        position: usize,
+    },
+    /// An unsupported `\` escape in a quoted string.
+    InvalidEscape {
+        position: usize,
+    },
+    /// There was more than a value in the input; the rest starts at
+    /// `position`.
+    TrailingCharacters {
+        position: usize,
+    },
+    /// Compounds and lists were nested more than 512 deep.
+    MaxDepthExceeded,
+}
+
+
+/// The same nesting limit as the game.
+const MAX_DEPTH: usize = 512;
+
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '+'
+}
+
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> SnbtParseError {
+        match self.peek() {
+            Some(found) => SnbtParseError::Unexpected {
+                position: self.position,
+                found,
+                expected,
+            },
+            None => SnbtParseError::UnexpectedEnd,
+        }
+    }
+
+    /// Consume `c`, which must come next (after any whitespace).
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), SnbtParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected(expected));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn descend(&mut self) -> Result<(), SnbtParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(SnbtParseError::MaxDepthExceeded);
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<Value, SnbtParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => Ok(Value::Compound(self.parse_compound()?)),
+            Some('[') => self.parse_list_or_array(),
+            Some('"') | Some('\'') => Ok(Value::String(self.parse_quoted()?)),
+            Some(c) if is_unquoted_char(c) => Ok(literal_value(self.parse_unquoted())),
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, SnbtParseError> {
+        let quote = self.peek().ok_or(SnbtParseError::UnexpectedEnd)?;
+        self.position += 1;
+        let mut s = String::new();
+        loop {
+            let c = self.peek().ok_or(SnbtParseError::UnexpectedEnd)?;
+            self.position += c.len_utf8();
+            if c == quote {
+                return Ok(s);
+            }
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+            let escaped = self.peek().ok_or(SnbtParseError::UnexpectedEnd)?;
+            s.push(match escaped {
+                '\\' | '"' | '\'' => escaped,
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                _ => return Err(SnbtParseError::InvalidEscape { position: self.position }),
+            });
+            self.position += escaped.len_utf8();
+        }
+    }
+
+    fn parse_unquoted(&mut self) -> &'a str {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+            self.position += 1;
+        }
+        &self.input[start..self.position]
+    }
+
+    fn parse_key(&mut self) -> Result<String, SnbtParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') | Some('\'') => self.parse_quoted(),
+            Some(c) if is_unquoted_char(c) => Ok(self.parse_unquoted().to_string()),
+            _ => Err(self.unexpected("a key")),
+        }
+    }
+
+    /// Parse `item`s separated by commas, up to `close`.
+    fn parse_sequence<F>(&mut self, close: char, mut item: F) -> Result<(), SnbtParseError>
+            where F: FnMut(&mut Self) -> Result<(), SnbtParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.position += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok(());
+                },
+                _ => return Err(self.unexpected("',' or a closing bracket")),
+            }
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, SnbtParseError> {
+        self.descend()?;
+        self.expect('{', "'{'")?;
+        let mut compound = Compound::new();
+        self.parse_sequence('}', |parser| {
+            let key = parser.parse_key()?;
+            parser.expect(':', "':'")?;
+            let value = parser.parse_value()?;
+            compound.insert(key, value);
+            Ok(())
+        })?;
+        self.depth -= 1;
+        Ok(compound)
+    }
+
+    fn parse_list_or_array(&mut self) -> Result<Value, SnbtParseError> {
+        self.descend()?;
+        let start = self.position;
+        self.expect('[', "'['")?;
+        let rest = &self.input[self.position..];
+        let array_type = match rest.as_bytes() {
+            [t @ b'B', b';', ..] | [t @ b'I', b';', ..] | [t @ b'L', b';', ..] => Some(*t),
+            _ => None,
+        };
+        if array_type.is_some() {
+            self.position += 2;
+        }
+
+        let mut values = Vec::new();
+        self.parse_sequence(']', |parser| {
+            values.push(parser.parse_value()?);
+            Ok(())
+        })?;
+        self.depth -= 1;
+
+        let mixed_array = SnbtParseError::MixedArray { position: start };
+        match array_type {
+            Some(b'B') => values.into_iter()
+                .map(|v| v.as_i8().map(|b| b as u8).ok_or_else(|| mixed_array.clone()))
+                .collect::<Result<_, _>>()
+                .map(Value::ByteArray),
+            Some(b'I') => values.into_iter()
+                .map(|v| v.as_i32().ok_or_else(|| mixed_array.clone()))
+                .collect::<Result<_, _>>()
+                .map(Value::IntArray),
+            Some(_) => values.into_iter()
+                .map(|v| v.as_i64().ok_or_else(|| mixed_array.clone()))
+                .collect::<Result<_, _>>()
+                .map(Value::LongArray),
+            None => List::from_values(values)
+                .map(Value::List)
+                .ok_or(SnbtParseError::MixedList { position: start }),
+        }
+    }
+}
+
+
+/// Whether `s` is an optionally signed run of digits.
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(|c| c == '-' || c == '+').unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+
+/// Whether `s` is an optionally signed decimal number, with a fractional
+/// part or exponent if `require_point`.
+fn is_decimal(s: &str, require_point: bool) -> bool {
+    let s = s.strip_prefix(|c| c == '-' || c == '+').unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(e) => (&s[..e], Some(&s[e + 1..])),
+        None => (s, None),
+    };
+    if let Some(exponent) = exponent {
+        if !is_integer(exponent) {
+            return false;
+        }
+    }
+    let (whole, fraction) = match mantissa.find('.') {
+        Some(point) => (&mantissa[..point], Some(&mantissa[point + 1..])),
+        None => (mantissa, None),
+    };
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(whole) || !fraction.is_none_or(all_digits) {
+        return false;
+    }
+    if whole.is_empty() && fraction.is_none_or(str::is_empty) {
+        return false;
+    }
+    !require_point || fraction.is_some() || exponent.is_some()
+}
+
+
+/// Interpret an unquoted literal: a number, `true`/`false`, or otherwise a
+/// string. As in the game, a number too large for its type is a string.
+fn literal_value(literal: &str) -> Value {
+    let string = || Value::String(literal.to_string());
+    match literal {
+        "true" => return Value::Byte(1),
+        "false" => return Value::Byte(0),
+        _ => (),
+    }
+    let (body, suffix) = match literal.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&literal[..i], c.to_ascii_lowercase()),
+        _ => (literal, ' '),
+    };
+    match suffix {
+        'b' if is_integer(body) => body.parse().map(Value::Byte).unwrap_or_else(|_| string()),
+        's' if is_integer(body) => body.parse().map(Value::Short).unwrap_or_else(|_| string()),
+        'l' if is_integer(body) => body.parse().map(Value::Long).unwrap_or_else(|_| string()),
+        'f' if is_decimal(body, false) => {
+            body.parse().map(Value::Float).unwrap_or_else(|_| string())
+        },
+        'd' if is_decimal(body, false) => {
+            body.parse().map(Value::Double).unwrap_or_else(|_| string())
+        },
+        _ if is_integer(literal) => literal.parse().map(Value::Int).unwrap_or_else(|_| string()),
+        _ if is_decimal(literal, true) => {
+            literal.parse().map(Value::Double).unwrap_or_else(|_| string())
+        },
+        _ => string(),
+    }
+}
+
+
+/// Parse an SNBT value.
+///
+/// Numbers take their type from a suffix (`b`, `s`, `l`, `f` or `d`, in
+/// either case); without one, whole numbers are ints and decimals are
+/// doubles. `true` and `false` are bytes. Any other unquoted word is a
+/// string.
+pub fn parse_snbt(input: &str) -> Result<Value, SnbtParseError> {
+    let mut parser = Parser { input, position: 0, depth: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.position != input.len() {
+        return Err(SnbtParseError::TrailingCharacters { position: parser.position });
+    }
+    Ok(value)
+}
+
+
+#[derive(Clone, Copy)]
+struct Layout {
+    /// Spaces per level, when pretty printing.
+    indent: Option<usize>,
+    depth: usize,
+}
+
+
+impl Layout {
+    fn nested(self) -> Layout {
+        Layout { depth: self.depth + 1, ..self }
+    }
+
+    fn newline(self, out: &mut String) {
+        if let Some(indent) = self.indent {
+            out.push('\n');
+            out.extend((0..indent * self.depth).map(|_| ' '));
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        if self.indent.is_some() { ", " } else { "," }
+    }
+
+    fn array_open(self, array_type: char) -> String {
+        match self.indent {
+            Some(_) => format!("[{}; ", array_type),
+            None => format!("[{};", array_type),
+        }
+    }
+}
+
+
+/// Write `items` between `open` and `close`, one per line if `multiline`
+/// and pretty printing.
+fn push_sequence<T, F>(
+        out: &mut String, open: &str, close: char, items: &[T], layout: Layout,
+        multiline: bool, mut push_item: F)
+        where F: FnMut(&mut String, &T, Layout) {
+    let multiline = multiline && layout.indent.is_some();
+    if items.is_empty() {
+        out.push_str(open.trim_end());
+        out.push(close);
+        return;
+    }
+    out.push_str(open);
+    let inner = layout.nested();
+    for (i, item) in items.iter().enumerate() {
+        if multiline {
+            if i > 0 {
+                out.push(',');
+            }
+            inner.newline(out);
+        } else if i > 0 {
+            out.push_str(layout.separator());
+        }
+        push_item(out, item, inner);
+    }
+    if multiline {
+        layout.newline(out);
+    }
+    out.push(close);
+}
+
+
+fn push_quoted(out: &mut String, s: &str) {
+    // Like the game, prefer double quotes, unless that needs more escaping.
+    let quote = if s.contains('"') && !s.contains('\'') { '\'' } else { '"' };
+    out.push(quote);
+    for c in s.chars() {
+        if c == quote || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push(quote);
+}
+
+
+fn push_key(out: &mut String, key: &str) {
+    if !key.is_empty() && key.chars().all(is_unquoted_char) {
+        out.push_str(key);
+    } else {
+        push_quoted(out, key);
+    }
+}
+
+
+fn push_compound(out: &mut String, compound: &Compound, layout: Layout) {
+    let mut entries = compound.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    push_sequence(out, "{", '}', &entries, layout, true, |out, (key, value), layout| {
+        push_key(out, key);
+        out.push_str(if layout.indent.is_some() { ": " } else { ":" });
+        push_value(out, value, layout);
+    });
+}
+
+
+/// Write a list of numbers, each followed by `suffix`. (`Debug` is used so
+/// that floats always have a decimal point or exponent.)
+fn push_numbers<T: Debug>(out: &mut String, numbers: &[T], suffix: &str, layout: Layout) {
+    push_sequence(out, "[", ']', numbers, layout, false, |out, n, _| {
+        write!(out, "{:?}{}", n, suffix).unwrap();
+    });
+}
+
+
+fn push_list(out: &mut String, list: &List, layout: Layout) {
+    match list {
+        List::Empty(_) => out.push_str("[]"),
+        List::Byte(items) => push_numbers(out, items, "b", layout),
+        List::Short(items) => push_numbers(out, items, "s", layout),
+        List::Int(items) => push_numbers(out, items, "", layout),
+        List::Long(items) => push_numbers(out, items, "L", layout),
+        List::Float(items) => push_numbers(out, items, "f", layout),
+        List::Double(items) => push_numbers(out, items, "d", layout),
+        List::ByteArray(items) => push_sequence(out, "[", ']', items, layout, true, |out, a, l| {
+            push_byte_array(out, a, l);
+        }),
+        List::String(items) => push_sequence(out, "[", ']', items, layout, false, |out, s, _| {
+            push_quoted(out, s);
+        }),
+        List::List(items) => push_sequence(out, "[", ']', items, layout, true, |out, l, layout| {
+            push_list(out, l, layout);
+        }),
+        List::Compound(items) => push_sequence(out, "[", ']', items, layout, true, |out, c, l| {
+            push_compound(out, c, l);
+        }),
+        List::IntArray(items) => push_sequence(out, "[", ']', items, layout, true, |out, a, l| {
+            push_int_array(out, a, l);
+        }),
+        List::LongArray(items) => push_sequence(out, "[", ']', items, layout, true, |out, a, l| {
+            push_long_array(out, a, l);
+        }),
+    }
+}
+
+
+fn push_byte_array(out: &mut String, bytes: &[u8], layout: Layout) {
+    push_sequence(out, &layout.array_open('B'), ']', bytes, layout, false, |out, b, _| {
+        write!(out, "{}b", *b as i8).unwrap();
+    });
+}
+
+
+fn push_int_array(out: &mut String, ints: &[i32], layout: Layout) {
+    push_sequence(out, &layout.array_open('I'), ']', ints, layout, false, |out, i, _| {
+        write!(out, "{}", i).unwrap();
+    });
+}
+
+
+fn push_long_array(out: &mut String, longs: &[i64], layout: Layout) {
+    push_sequence(out, &layout.array_open('L'), ']', longs, layout, false, |out, l, _| {
+        write!(out, "{}L", l).unwrap();
+    });
+}
+
+
+fn push_value(out: &mut String, value: &Value, layout: Layout) {
+    // Writing to a String can't fail.
+    match value {
+        Value::Byte(v) => write!(out, "{}b", v).unwrap(),
+        Value::Short(v) => write!(out, "{}s", v).unwrap(),
+        Value::Int(v) => write!(out, "{}", v).unwrap(),
+        Value::Long(v) => write!(out, "{}L", v).unwrap(),
+        Value::Float(v) => write!(out, "{:?}f", v).unwrap(),
+        Value::Double(v) => write!(out, "{:?}d", v).unwrap(),
+        Value::ByteArray(v) => push_byte_array(out, v, layout),
+        Value::String(v) => push_quoted(out, v),
+        Value::List(v) => push_list(out, v, layout),
+        Value::Compound(v) => push_compound(out, v, layout),
+        Value::IntArray(v) => push_int_array(out, v, layout),
+        Value::LongArray(v) => push_long_array(out, v, layout),
+    }
+}
+
+
+/// Write `value` as SNBT, on one line. `parse_snbt` reads it back as the
+/// same value, except for NaN and infinite floats, which SNBT can't express.
+///
+/// Compound entries are sorted by key, so the output is deterministic. Keys
+/// are quoted only if they need to be; strings are always quoted.
+pub fn to_snbt(value: &Value) -> String {
+    let mut out = String::new();
+    push_value(&mut out, value, Layout { indent: None, depth: 0 });
+    out
+}
+
+
+/// Like `to_snbt`, but with each compound entry on its own line, indented by
+/// `indent` spaces per level. Lists of compounds, lists and arrays are split
+/// over lines the same way; lists of numbers or strings, and arrays, are
+/// kept on one line.
+pub fn to_snbt_pretty(value: &Value, indent: usize) -> String {
+    let mut out = String::new();
+    push_value(&mut out, value, Layout { indent: Some(indent), depth: 0 });
+    out
+}
@@ -0,0 +1,333 @@
+//! The lowest-level view of an NBT stream: a flat "tape" of tags, in the order
+//! they appear on disk, with their payload bytes left uninterpreted.
+
+use std::io::{Read, Write};
+use std::vec::Vec;
+
+use super::{
+    TAG_END,
+    TAG_BYTE,
+    TAG_SHORT,
+    TAG_INT,
+    TAG_LONG,
+    TAG_FLOAT,
+    TAG_DOUBLE,
+    TAG_BYTE_ARRAY,
+    TAG_STRING,
+    TAG_LIST,
+    TAG_COMPOUND,
+    TAG_INT_ARRAY,
+    TAG_LONG_ARRAY,
+};
+use super::Endianness;
+use super::reader::{NbtReadError, ReadLimits, decode_modified_utf8, read_n_bytes_to_vector};
+use super::writer::encode_modified_utf8;
+
+
+/// One tag, as encountered in the stream.
+///
+/// `payload` holds the tag's payload bytes exactly as they appear on disk,
+/// length prefixes included, but not the payloads of any children:
+///
+/// * numbers, strings and arrays: the whole payload.
+/// * `TAG_List`: the five byte header (element type, then element count).
+///   The elements follow as events one level deeper, without names.
+/// * `TAG_Compound`: empty. The entries follow one level deeper, and are
+///   closed by a `TAG_End` event at that same depth.
+///
+/// The root tag is at depth 0.
+#[derive(Debug)]
+pub struct TagEvent {
+    pub depth: usize,
+    pub tag_type: u8,
+    pub name: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+
+enum Frame {
+    Compound,
+    List {
+        element_type: u8,
+        remaining: usize,
+    },
+}
+
+
+/// Iterator returned by `tag_events`.
+pub struct TagEvents<R: Read> {
+    reader: R,
+    endianness: Endianness,
+    limits: ReadLimits,
+    /// What's left of `limits.max_bytes`.
+    remaining: usize,
+    stack: Vec<Frame>,
+    started: bool,
+    finished: bool,
+}
+
+
+/// Walk an NBT stream, yielding each tag as it is encountered.
+///
+/// Nothing is decoded beyond what is needed to find where each tag ends (and
+/// the tag names). Iteration stops after the root tag is complete, or after
+/// the first error.
+pub fn tag_events<R: Read>(reader: R) -> TagEvents<R> {
+    tag_events_with_endianness(reader, Endianness::Big)
+}
+
+
+/// Like `tag_events`, for a stream in the given byte order. The payloads are
+/// still left as they are on disk, i.e. in that byte order.
+pub fn tag_events_with_endianness<R: Read>(reader: R, endianness: Endianness)
+        -> TagEvents<R> {
+    tag_events_with_limits(reader, endianness, ReadLimits::default())
+}
+
+
+/// Like `tag_events_with_endianness`, within `limits`: array payloads count
+/// towards `max_bytes`, and compounds and lists towards `max_depth`, as for
+/// the tree reader.
+pub fn tag_events_with_limits<R: Read>(reader: R, endianness: Endianness, limits: ReadLimits)
+        -> TagEvents<R> {
+    let remaining = limits.max_bytes;
+    TagEvents {
+        reader,
+        endianness,
+        limits,
+        remaining,
+        stack: Vec::new(),
+        started: false,
+        finished: false,
+    }
+}
+
+
+fn decode_length(bytes: &[u8], endianness: Endianness) -> usize {
+    let fold = |acc, b: &u8| (acc << 8) | *b as usize;
+    match endianness {
+        Endianness::Big => bytes.iter().fold(0usize, fold),
+        Endianness::Little => bytes.iter().rev().fold(0usize, fold),
+    }
+}
+
+
+/// Read a length prefix of `prefix_size` bytes, then that many elements of
+/// `element_size` bytes. If `remaining` is given, the elements are taken out
+/// of it.
+fn read_prefixed(
+        reader: &mut dyn Read, endianness: Endianness,
+        prefix_size: usize, element_size: usize, remaining: Option<&mut usize>)
+        -> Result<Vec<u8>, NbtReadError> {
+    let mut payload = read_n_bytes_to_vector(reader, prefix_size)?;
+    let length = decode_length(&payload, endianness)
+        .checked_mul(element_size)
+        .ok_or(NbtReadError::AllocationLimitExceeded)?;
+    if let Some(remaining) = remaining {
+        *remaining = remaining.checked_sub(length)
+            .ok_or(NbtReadError::AllocationLimitExceeded)?;
+    }
+    // The length is untrusted, so let the buffer grow as the data actually
+    // arrives rather than allocating it all up front.
+    let read = reader.take(length as u64).read_to_end(&mut payload)?;
+    if read < length {
+        return Err(NbtReadError::UnexpectedEof);
+    }
+    Ok(payload)
+}
+
+
+impl<R: Read> TagEvents<R> {
+    fn read_tag(&mut self, tag_type: u8, name: Option<String>)
+            -> Result<TagEvent, NbtReadError> {
+        let depth = self.stack.len();
+        let endianness = self.endianness;
+        let reader: &mut dyn Read = &mut self.reader;
+        let remaining = &mut self.remaining;
+        if (tag_type == TAG_LIST || tag_type == TAG_COMPOUND) && depth >= self.limits.max_depth {
+            return Err(NbtReadError::MaxDepthExceeded);
+        }
+        let payload = match tag_type {
+            TAG_BYTE => read_n_bytes_to_vector(reader, 1)?,
+            TAG_SHORT => read_n_bytes_to_vector(reader, 2)?,
+            TAG_INT | TAG_FLOAT => read_n_bytes_to_vector(reader, 4)?,
+            TAG_LONG | TAG_DOUBLE => read_n_bytes_to_vector(reader, 8)?,
+            TAG_BYTE_ARRAY => read_prefixed(reader, endianness, 4, 1, Some(remaining))?,
+            TAG_STRING => read_prefixed(reader, endianness, 2, 1, None)?,
+            TAG_INT_ARRAY => read_prefixed(reader, endianness, 4, 4, Some(remaining))?,
+            TAG_LONG_ARRAY => read_prefixed(reader, endianness, 4, 8, Some(remaining))?,
+            TAG_LIST => {
+                let header = read_n_bytes_to_vector(reader, 5)?;
+                let element_type = header[0];
+                let remaining = decode_length(&header[1..], endianness);
+                if element_type == TAG_END && remaining != 0 {
+                    return Err(NbtReadError::InvalidTagType);
+                }
+                if element_type > TAG_LONG_ARRAY {
+                    return Err(NbtReadError::UnknownTagType(element_type));
+                }
+                self.stack.push(Frame::List { element_type, remaining });
+                header
+            },
+            TAG_COMPOUND => {
+                self.stack.push(Frame::Compound);
+                Vec::new()
+            },
+            _ => return Err(NbtReadError::UnknownTagType(tag_type)),
+        };
+        Ok(TagEvent { depth, tag_type, name, payload })
+    }
+
+    fn read_name(&mut self) -> Result<String, NbtReadError> {
+        let name = read_prefixed(&mut self.reader, self.endianness, 2, 1, None)?;
+        decode_modified_utf8(&name[2..])
+    }
+
+    fn next_event(&mut self) -> Result<Option<TagEvent>, NbtReadError> {
+        if !self.started {
+            self.started = true;
+            let mut tag_type = [0u8];
+            self.reader.read_exact(&mut tag_type)?;
+            let name = self.read_name()?;
+            return self.read_tag(tag_type[0], Some(name)).map(Some);
+        }
+        loop {
+            match self.stack.last_mut() {
+                None => return Ok(None),
+                Some(Frame::Compound) => {
+                    let mut tag_type = [0u8];
+                    self.reader.read_exact(&mut tag_type)?;
+                    if tag_type[0] == TAG_END {
+                        let depth = self.stack.len();
+                        self.stack.pop();
+                        return Ok(Some(TagEvent {
+                            depth,
+                            tag_type: TAG_END,
+                            name: None,
+                            payload: Vec::new(),
+                        }));
+                    }
+                    let name = self.read_name()?;
+                    return self.read_tag(tag_type[0], Some(name)).map(Some);
+                },
+                Some(Frame::List { element_type, remaining }) => {
+                    if *remaining == 0 {
+                        self.stack.pop();
+                        continue;
+                    }
+                    *remaining -= 1;
+                    let element_type = *element_type;
+                    return self.read_tag(element_type, None).map(Some);
+                },
+            }
+        }
+    }
+}
+
+
+impl<R: Read> Iterator for TagEvents<R> {
+    type Item = Result<TagEvent, NbtReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_event();
+        match result {
+            Ok(Some(event)) => {
+                if self.stack.is_empty() {
+                    self.finished = true;
+                }
+                Some(Ok(event))
+            },
+            Ok(None) => {
+                self.finished = true;
+                None
+            },
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            },
+        }
+    }
+}
+
+
+/// Check whether the stream contains any tag of type `tag_type`, without
+/// building the tree. Stops reading at the first match.
+pub fn stream_contains_tag<R: Read>(reader: R, tag_type: u8)
+        -> Result<bool, NbtReadError> {
+    for event in tag_events(reader) {
+        if event?.tag_type == tag_type {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+
+/// Reverse the byte order of each `size` byte field in `bytes`.
+fn swap_fields(bytes: &mut [u8], size: usize) {
+    for field in bytes.chunks_mut(size) {
+        field.reverse();
+    }
+}
+
+
+/// Convert a tape payload between byte orders, in place.
+fn swap_payload(tag_type: u8, payload: &mut [u8]) {
+    match tag_type {
+        TAG_SHORT => swap_fields(payload, 2),
+        TAG_INT | TAG_FLOAT => swap_fields(payload, 4),
+        TAG_LONG | TAG_DOUBLE => swap_fields(payload, 8),
+        TAG_BYTE_ARRAY => swap_fields(&mut payload[..4], 4),
+        TAG_STRING => swap_fields(&mut payload[..2], 2),
+        TAG_INT_ARRAY => swap_fields(payload, 4),
+        TAG_LONG_ARRAY => {
+            swap_fields(&mut payload[..4], 4);
+            swap_fields(&mut payload[4..], 8);
+        },
+        TAG_LIST => swap_fields(&mut payload[1..], 4),
+        _ => (),
+    }
+}
+
+
+/// Re-encode an NBT stream in a different byte order, e.g. Java's
+/// big-endian NBT as Bedrock's little-endian, one tag at a time without
+/// building the tree.
+///
+/// This only changes the encoding; the content (block ids and so on) is
+/// copied as it is. Names are written as modified UTF-8 even if they were
+/// read as plain UTF-8, which can make them longer: too long, and this fails
+/// with `NbtReadError::StringTooLong`.
+pub fn transcode_endianness<R: Read, W: Write>(
+        reader: R, mut writer: W, from: Endianness, to: Endianness)
+        -> Result<(), NbtReadError> {
+    for event in tag_events_with_endianness(reader, from) {
+        let mut event = event?;
+        if event.tag_type == TAG_END {
+            writer.write_all(&[TAG_END])?;
+            continue;
+        }
+        if let Some(name) = event.name {
+            let name = encode_modified_utf8(&name);
+            if name.len() > u16::MAX as usize {
+                return Err(NbtReadError::StringTooLong(name.len()));
+            }
+            let length = name.len() as u16;
+            let length = match to {
+                Endianness::Big => length.to_be_bytes(),
+                Endianness::Little => length.to_le_bytes(),
+            };
+            writer.write_all(&[event.tag_type])?;
+            writer.write_all(&length)?;
+            writer.write_all(&name)?;
+        }
+        if from != to {
+            swap_payload(event.tag_type, &mut event.payload);
+        }
+        writer.write_all(&event.payload)?;
+    }
+    Ok(())
+}
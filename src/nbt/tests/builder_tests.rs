@@ -0,0 +1,49 @@
+use crate::nbt::builder::CompoundBuilder;
+use crate::nbt::snbt::parse_snbt;
+use crate::nbt::{List, Value};
+
+
+#[test]
+fn test_build_compound() {
+    let built = CompoundBuilder::new()
+        .byte("Health", 20)
+        .short("Fire", -20)
+        .int("XpLevel", 30)
+        .long("Seed", 1234)
+        .float("XpP", 0.5)
+        .double("x", 1.25)
+        .bool("OnGround", true)
+        .string("name", "Steve")
+        .byte_array("bytes", vec![1, 2])
+        .int_array("UUID", vec![1, 2, 3, 4])
+        .long_array("longs", vec![5])
+        .list("Pos", vec![0.5, 64.0, 0.5])
+        .list("Tags", vec!["a".to_string()])
+        .compound("abilities", CompoundBuilder::new().byte("mayfly", 1))
+        .build();
+    let expected = parse_snbt(
+        "{Health:20b,Fire:-20s,XpLevel:30,Seed:1234L,XpP:0.5f,x:1.25d,OnGround:1b,\
+         name:\"Steve\",bytes:[B;1b,2b],UUID:[I;1,2,3,4],longs:[L;5L],Pos:[0.5d,64.0d,0.5d],\
+         Tags:[\"a\"],abilities:{mayfly:1b}}").unwrap();
+    assert_eq!(expected, built);
+}
+
+
+#[test]
+fn test_build_replaces_keys() {
+    let built = CompoundBuilder::new().int("a", 1).value("a", "one").build_compound();
+    assert_eq!(Some(&Value::String("one".to_string())), built.get("a"));
+    assert_eq!(1, built.len());
+}
+
+
+#[test]
+fn test_value_from() {
+    assert_eq!(Value::Int(42), Value::from(42i32));
+    assert_eq!(Value::Byte(1), Value::from(true));
+    assert_eq!(Value::Double(1.5), Value::from(1.5));
+    assert_eq!(Value::String("hello".to_string()), Value::from("hello"));
+    assert_eq!(Value::IntArray(vec![1, 2, 3]), Value::from(vec![1i32, 2, 3]));
+    assert_eq!(Value::ByteArray(vec![1]), Value::from(vec![1u8]));
+    assert_eq!(Value::List(List::Int(vec![1, 2, 3])), Value::from(List::from(vec![1, 2, 3])));
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use crate::nbt::de::{self, DeserializeError};
+use crate::nbt::{reader, snbt, Value};
+
+
+const BIGTEST: &[u8] = include_bytes!("bigtest.nbt");
+
+
+fn parse(input: &str) -> Value {
+    snbt::parse_snbt(input).unwrap()
+}
+
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Item {
+    id: String,
+    #[serde(rename = "Count")]
+    count: i32,
+    #[serde(rename = "Slot")]
+    slot: Option<u8>,
+}
+
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Player<'a> {
+    name: &'a str,
+    #[serde(rename = "Health")]
+    health: f64,
+    #[serde(rename = "OnGround")]
+    on_ground: bool,
+    #[serde(rename = "Pos")]
+    pos: [f64; 3],
+    #[serde(rename = "Inventory")]
+    inventory: Vec<Item>,
+    #[serde(rename = "UUID")]
+    uuid: Vec<i32>,
+    #[serde(rename = "GameMode")]
+    game_mode: GameMode,
+    #[serde(default)]
+    missing: Vec<String>,
+}
+
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum GameMode {
+    Survival,
+    Creative,
+}
+
+
+#[test]
+fn test_from_nbt_value_struct() {
+    let value = parse(
+        r#"{name: "Steve", Health: 20.0f, OnGround: 1b, Pos: [1.5d, 64.0d, -3.0d],
+            Inventory: [{id: "minecraft:torch", Count: 3b, Slot: 0b},
+                        {id: "minecraft:stick", Count: 1b}],
+            UUID: [I; 1, 2, 3, 4], GameMode: "creative"}"#,
+    );
+    let player: Player = de::from_nbt_value(&value).unwrap();
+    assert_eq!(
+        Player {
+            name: "Steve",
+            health: 20.0,
+            on_ground: true,
+            pos: [1.5, 64.0, -3.0],
+            inventory: vec![
+                Item { id: "minecraft:torch".to_string(), count: 3, slot: Some(0) },
+                Item { id: "minecraft:stick".to_string(), count: 1, slot: None },
+            ],
+            uuid: vec![1, 2, 3, 4],
+            game_mode: GameMode::Creative,
+            missing: vec![],
+        },
+        player,
+    );
+}
+
+
+#[test]
+fn test_from_nbt_value_collections() {
+    let value = parse("{a: [B; 1b, -1b], b: [L; 5L], c: [[1s], [], [2s, 3s]]}");
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Arrays {
+        a: Vec<u8>,
+        b: Vec<i64>,
+        c: Vec<Vec<i16>>,
+    }
+    assert_eq!(
+        Arrays { a: vec![1, 255], b: vec![5], c: vec![vec![1], vec![], vec![2, 3]] },
+        de::from_nbt_value(&value).unwrap(),
+    );
+
+    let map: HashMap<String, Vec<i64>> = de::from_nbt_value(&parse("{x: [I; 1], y: [L;]}"))
+        .unwrap();
+    assert_eq!(Some(&vec![1]), map.get("x"));
+    assert_eq!(Some(&vec![]), map.get("y"));
+}
+
+
+#[test]
+fn test_from_nbt_value_errors() {
+    let out_of_range = de::from_nbt_value::<u8>(&Value::Int(300));
+    assert!(matches!(out_of_range, Err(DeserializeError::Message(_))));
+
+    let missing = de::from_nbt_value::<Item>(&parse("{id: stone}"));
+    assert_eq!(Err(DeserializeError::Message("missing field `Count`".to_string())), missing);
+}
+
+
+#[test]
+fn test_from_nbt_value_bigtest() {
+    let mut bytes = Vec::new();
+    GzDecoder::new(BIGTEST).read_to_end(&mut bytes).unwrap();
+    let root = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+
+    #[derive(Deserialize)]
+    struct Egg {
+        name: String,
+        value: f32,
+    }
+    #[derive(Deserialize)]
+    struct Nested {
+        egg: Egg,
+    }
+    #[derive(Deserialize)]
+    struct BigTest {
+        #[serde(rename = "longTest")]
+        long_test: i64,
+        #[serde(rename = "nested compound test")]
+        nested: Nested,
+        #[serde(rename = "listTest (long)")]
+        list_test: Vec<i64>,
+    }
+    let big: BigTest = de::from_nbt_value(&root.value).unwrap();
+    assert_eq!(9223372036854775807, big.long_test);
+    assert_eq!("Eggbert", big.nested.egg.name);
+    assert_eq!(0.5, big.nested.egg.value);
+    assert_eq!(vec![11, 12, 13, 14, 15], big.list_test);
+}
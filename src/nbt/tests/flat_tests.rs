@@ -0,0 +1,34 @@
+use crate::nbt::{Compound, List, RootValue, Value};
+use crate::nbt::flat;
+
+
+#[test]
+fn test_to_flat_map() {
+    let mut player = Compound::new();
+    player.insert("Health".to_string(), Value::Float(20.0));
+    player.insert("Pos".to_string(), Value::List(List::Double(vec![1.5, 64.0, -3.0])));
+    player.insert("Tags".to_string(), Value::List(List::Empty(0)));
+    let mut data = Compound::new();
+    data.insert("Player".to_string(), Value::Compound(player));
+    data.insert("LevelName".to_string(), Value::String("World".to_string()));
+    data.insert("Bytes".to_string(), Value::ByteArray(vec![1, 2, 255]));
+    let mut root = Compound::new();
+    root.insert("Data".to_string(), Value::Compound(data));
+    let root = RootValue { name: String::new(), value: Value::Compound(root) };
+
+    let flat = flat::to_flat_map(&root);
+    let entries = flat.iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("Data.Bytes", "1,2,255"),
+            ("Data.LevelName", "World"),
+            ("Data.Player.Health", "20"),
+            ("Data.Player.Pos.0", "1.5"),
+            ("Data.Player.Pos.1", "64"),
+            ("Data.Player.Pos.2", "-3"),
+        ],
+        entries,
+    );
+}
@@ -0,0 +1,46 @@
+use std::io::Cursor;
+
+use serde_json::json;
+
+use crate::nbt::{reader, Compound, List, Value};
+
+
+const HELLO_WORLD: &[u8] = include_bytes!("hello_world.nbt");
+
+
+#[test]
+fn test_json_hello_world() {
+    let root = reader::parse_nbt_stream(&mut Cursor::new(HELLO_WORLD)).unwrap();
+    assert_eq!(json!({"name": "Bananrama"}), serde_json::Value::from(&root.value));
+}
+
+
+#[test]
+fn test_json_types() {
+    let mut item = Compound::new();
+    item.insert("id".to_string(), Value::String("minecraft:torch".to_string()));
+    let mut compound = Compound::new();
+    compound.insert("byte".to_string(), Value::Byte(-1));
+    compound.insert("long".to_string(), Value::Long(1 << 40));
+    compound.insert("double".to_string(), Value::Double(0.5));
+    compound.insert("nan".to_string(), Value::Float(f32::NAN));
+    compound.insert("bytes".to_string(), Value::ByteArray(vec![1, 255]));
+    compound.insert("ints".to_string(), Value::IntArray(vec![1, -2]));
+    compound.insert("pos".to_string(), Value::List(List::Double(vec![1.5, 64.0])));
+    compound.insert("items".to_string(), Value::List(List::Compound(vec![item])));
+    compound.insert("empty".to_string(), Value::List(List::Empty(0)));
+    assert_eq!(
+        json!({
+            "byte": -1,
+            "long": 1u64 << 40,
+            "double": 0.5,
+            "nan": null,
+            "bytes": [1, -1],
+            "ints": [1, -2],
+            "pos": [1.5, 64.0],
+            "items": [{"id": "minecraft:torch"}],
+            "empty": [],
+        }),
+        serde_json::Value::from(&Value::Compound(compound)),
+    );
+}
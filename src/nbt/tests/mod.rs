@@ -1 +1,13 @@
 mod reader_tests;
+mod tape_tests;
+mod flat_tests;
+mod value_tests;
+mod writer_tests;
+mod snbt_tests;
+mod builder_tests;
+#[cfg(feature = "serde_json")]
+mod json_tests;
+#[cfg(feature = "serde")]
+mod de_tests;
+#[cfg(feature = "serde")]
+mod ser_tests;
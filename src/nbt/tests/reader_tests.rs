@@ -1,32 +1,770 @@
-use std::io::Cursor;
+use std::io::{Cursor, Read, Write};
 
-use ::nbt;
-use ::nbt::reader;
+use flate2::read::GzDecoder;
+use flate2::write::{GzEncoder, ZlibEncoder};
 
+use crate::nbt;
+use crate::nbt::{reader, tape, writer};
 
-const HELLO_WORLD: &'static [u8] = include_bytes!("hello_world.nbt");
+
+const HELLO_WORLD: &[u8] = include_bytes!("hello_world.nbt");
+const BIGTEST: &[u8] = include_bytes!("bigtest.nbt");
+const BEDROCK_LEVEL_DAT: &[u8] = include_bytes!("bedrock_level.dat");
 
 
 #[test]
 fn test_reader_hello_world() {
-    let mut hello_world = Cursor::new(HELLO_WORLD);
-
-    let root = match reader::parse_nbt_stream(&mut hello_world) {
+    let root = match reader::parse_nbt_bytes(HELLO_WORLD) {
         Ok(result) => result,
-        Err(err) => panic!(err),
+        Err(err) => panic!("{:?}", err),
     };
     assert_eq!(root.name, "hello world");
+    let root_value = root.value.as_compound().expect("Not a compound?");
+    assert_eq!(1, root_value.len());
+    assert_eq!(Some("Bananrama"), root_value.get("name").and_then(nbt::Value::as_str));
+}
+
+
+#[test]
+fn test_guess_endianness() {
+    assert_eq!(
+        Some(nbt::Endianness::Big), reader::guess_endianness(HELLO_WORLD),
+    );
+
+    // {"": {a: 5}}, once in each byte order.
+    let big = [10u8, 0, 0, 3, 0, 1, b'a', 0, 0, 0, 5, 0];
+    let little = [10u8, 0, 0, 3, 1, 0, b'a', 5, 0, 0, 0, 0];
+    assert_eq!(Some(nbt::Endianness::Big), reader::guess_endianness(&big));
+    assert_eq!(Some(nbt::Endianness::Little), reader::guess_endianness(&little));
+
+    // Entry names in modified UTF-8: "\0a", with the NUL encoded as C0 80,
+    // and U+1F600 as a surrogate pair.
+    let big = [10u8, 0, 0, 3, 0, 3, 0xc0, 0x80, b'a', 0, 0, 0, 5, 0];
+    let little = [10u8, 0, 0, 3, 6, 0, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 5, 0, 0, 0, 0];
+    assert_eq!(Some(nbt::Endianness::Big), reader::guess_endianness(&big));
+    assert_eq!(Some(nbt::Endianness::Little), reader::guess_endianness(&little));
+
+    assert_eq!(None, reader::guess_endianness(&[0xffu8, 0xff, 0xff]));
+    assert_eq!(None, reader::guess_endianness(&[]));
+}
+
+
+#[test]
+fn test_parse_stats_hello_world() {
+    let (root, stats) = reader::parse_nbt_stream_with_stats(
+        &mut Cursor::new(HELLO_WORLD),
+    ).unwrap();
+    assert_eq!(root.name, "hello world");
+    assert_eq!(
+        reader::ParseStats {
+            max_depth: 1,
+            max_list_len: 0,
+            total_tags: 2,
+            total_bytes: HELLO_WORLD.len() as u64,
+        },
+        stats,
+    );
+}
+
+
+#[test]
+fn test_spans_hello_world() {
+    let (root, spans) = reader::parse_nbt_stream_with_spans(
+        &mut Cursor::new(HELLO_WORLD), &reader::ReaderOptions::default(),
+    ).unwrap();
+    let expected = reader::parse_nbt_stream(&mut Cursor::new(HELLO_WORLD)).unwrap();
+    assert_eq!(expected, root);
+    assert_eq!(2, spans.len());
+    assert_eq!((21, 32), spans["name"]);
+    assert_eq!(b"\x00\x09Bananrama", &HELLO_WORLD[21..32]);
+    assert_eq!((14, HELLO_WORLD.len() as u64), spans[""]);
+}
+
+
+#[test]
+fn test_spans_bigtest() {
+    let mut data = Vec::new();
+    GzDecoder::new(BIGTEST).read_to_end(&mut data).unwrap();
+    let (root, spans) = reader::parse_nbt_stream_with_spans(
+        &mut Cursor::new(&data), &reader::ReaderOptions::default(),
+    ).unwrap();
+    // Every value has a span, and re-reading just that span's bytes as a
+    // value of the same type gives the value back.
+    let mut count = 0;
+    root.value.walk(&mut |path, value| {
+        count += 1;
+        let (start, end) = spans[path];
+        let bytes = &data[start as usize..end as usize];
+        let mut stream = vec![value.tag_type(), 0, 0];
+        stream.extend_from_slice(bytes);
+        let reread = reader::parse_nbt_stream(&mut Cursor::new(&stream)).unwrap();
+        assert_eq!(value, &reread.value, "{}", path);
+    });
+    assert_eq!(count, spans.len());
+}
+
+
+#[test]
+fn test_parse_stats_bigtest() {
+    let (_, stats) = reader::parse_nbt_stream_with_stats(
+        &mut GzDecoder::new(BIGTEST),
+    ).unwrap();
+    assert_eq!(
+        reader::ParseStats {
+            max_depth: 3,
+            max_list_len: 5,
+            total_tags: 29,
+            total_bytes: 1544,
+        },
+        stats,
+    );
+}
+
+
+#[test]
+fn test_reader_long_arrays() {
+    // {"": {h: [L; 1, -2], l: [[L; 3], [L;]]}}
+    let data = [
+        10u8, 0, 0,
+        12, 0, 1, b'h', 0, 0, 0, 2,
+        0, 0, 0, 0, 0, 0, 0, 1,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+        9, 0, 1, b'l', 12, 0, 0, 0, 2,
+        0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3,
+        0, 0, 0, 0,
+        0,
+    ];
+    let root = reader::parse_nbt_stream(&mut Cursor::new(&data[..])).unwrap();
     let root_value = match root.value {
         nbt::Value::Compound(c) => c,
         _ => panic!("Not a compound?"),
     };
-    assert_eq!(1, root_value.len());
-    let entry = match root_value.get("name") {
-        None => panic!("Expected value not in Compound."),
-        Some(v) => v,
+    match root_value.get("h") {
+        Some(nbt::Value::LongArray(longs)) => assert_eq!(vec![1, -2], *longs),
+        other => panic!("Expected a long array, got {:?}", other),
+    };
+    match root_value.get("l") {
+        Some(nbt::Value::List(nbt::List::LongArray(arrays))) => {
+            assert_eq!(vec![vec![3], vec![]], *arrays);
+        },
+        other => panic!("Expected a list of long arrays, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_parse_nbt_file() {
+    let root = reader::parse_nbt_file(HELLO_WORLD).unwrap();
+    assert_eq!(root.name, "hello world");
+
+    let root = reader::parse_nbt_file(BIGTEST).unwrap();
+    assert_eq!(root.name, "Level");
+}
+
+
+#[test]
+fn test_parse_nbt_file_corrupt_gzip() {
+    let mut corrupt = BIGTEST.to_vec();
+    for byte in corrupt[20..60].iter_mut() {
+        *byte = 0xff;
+    }
+    match reader::parse_nbt_file(&corrupt[..]) {
+        Err(reader::NbtReadError::Decompression(_)) => (),
+        other => panic!("Expected a decompression error, got {:?}", other),
+    };
+}
+
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+
+#[test]
+fn test_parse_nbt_file_gzipped_twice() {
+    let doubled = gzip(&gzip(HELLO_WORLD));
+    let root = reader::parse_nbt_file(&doubled[..]).unwrap();
+    assert_eq!(root.name, "hello world");
+
+    let limits = reader::ReadLimits { max_decompress_layers: 1, ..Default::default() };
+    let options = reader::ReaderOptions { limits, ..Default::default() };
+    match reader::parse_nbt_file_with_options(&doubled[..], &options) {
+        Err(reader::NbtReadError::TooManyCompressionLayers) => (),
+        other => panic!("Expected TooManyCompressionLayers, got {:?}", other),
+    }
+    let root = reader::parse_nbt_file_with_options(&gzip(HELLO_WORLD)[..], &options).unwrap();
+    assert_eq!(root.name, "hello world");
+}
+
+
+#[test]
+fn test_parse_nbt_file_gzip_bomb() {
+    let mut bomb = HELLO_WORLD.to_vec();
+    for _ in 0..100 {
+        bomb = gzip(&bomb);
+    }
+    match reader::parse_nbt_file(&bomb[..]) {
+        Err(reader::NbtReadError::TooManyCompressionLayers) => (),
+        other => panic!("Expected TooManyCompressionLayers, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_parse_nbt_stream_with() {
+    let mut reader = Cursor::new(HELLO_WORLD);
+    let root = reader::parse_nbt_stream_with(&mut reader, reader::Compression::None).unwrap();
+    assert_eq!(root.name, "hello world");
+
+    let mut reader = Cursor::new(BIGTEST);
+    let root = reader::parse_nbt_stream_with(&mut reader, reader::Compression::Gzip).unwrap();
+    assert_eq!(root.name, "Level");
+
+    let mut inflated = Vec::new();
+    GzDecoder::new(BIGTEST).read_to_end(&mut inflated).unwrap();
+    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&inflated).unwrap();
+    let zlib = encoder.finish().unwrap();
+    let mut reader = Cursor::new(zlib);
+    let root = reader::parse_nbt_stream_with(&mut reader, reader::Compression::Zlib).unwrap();
+    assert_eq!(root.name, "Level");
+
+    // Gzip data isn't valid zlib.
+    let mut reader = Cursor::new(BIGTEST);
+    match reader::parse_nbt_stream_with(&mut reader, reader::Compression::Zlib) {
+        Err(reader::NbtReadError::Decompression(_)) => (),
+        other => panic!("Expected a decompression error, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_parse_nbt_bytes_with() {
+    let root = reader::parse_nbt_bytes_with(BIGTEST, reader::Compression::Gzip).unwrap();
+    assert_eq!(root.name, "Level");
+    let root = reader::parse_nbt_bytes_with(HELLO_WORLD, reader::Compression::None).unwrap();
+    assert_eq!(root.name, "hello world");
+    // Compressed data isn't NBT.
+    assert!(reader::parse_nbt_bytes(BIGTEST).is_err());
+}
+
+
+/// A reader that returns at most one byte per `read` call, as a socket might.
+struct OneByteReader<'a>(&'a [u8]);
+
+
+impl<'a> Read for OneByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.0.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+
+#[test]
+fn test_reader_partial_reads() {
+    let bytes = (0..10000).map(|i| i as u8).collect::<Vec<_>>();
+    let mut compound = nbt::Compound::new();
+    compound.insert("bytes".to_string(), nbt::Value::ByteArray(bytes.clone()));
+    let root = nbt::RootValue {
+        name: "partial".to_string(),
+        value: nbt::Value::Compound(compound),
+    };
+    let mut stream = Vec::new();
+    writer::write_nbt_stream(&mut stream, &root).unwrap();
+
+    let root = reader::parse_nbt_stream(&mut OneByteReader(&stream)).unwrap();
+    assert_eq!(root.name, "partial");
+    match root.value {
+        nbt::Value::Compound(compound) => match compound.get("bytes") {
+            Some(nbt::Value::ByteArray(read)) => assert_eq!(&bytes, read),
+            other => panic!("Expected a byte array, got {:?}", other),
+        },
+        other => panic!("Expected a compound, got {:?}", other),
+    };
+
+    match reader::parse_nbt_stream(&mut OneByteReader(&stream[..5000])) {
+        Err(reader::NbtReadError::UnexpectedEof) => (),
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_decode_modified_utf8() {
+    // "a", NUL, "b", then U+1F600 as the surrogate pair D83D DE00.
+    let bytes = b"a\xc0\x80b\xed\xa0\xbd\xed\xb8\x80";
+    assert_eq!("a\0b\u{1f600}", reader::decode_modified_utf8(bytes).unwrap());
+
+    // Plain UTF-8, including four byte sequences, passes through.
+    let utf8 = "\u{e9}\u{1f600}";
+    assert_eq!(utf8, reader::decode_modified_utf8(utf8.as_bytes()).unwrap());
+
+    // An unpaired surrogate.
+    match reader::decode_modified_utf8(b"\xed\xa0\xbd") {
+        Err(reader::NbtReadError::InvalidUtf8(_)) => (),
+        other => panic!("Expected InvalidUtf8, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_capture_stack_on_error() {
+    let mut bytes = vec![10u8, 0, 0];
+    bytes.extend(&[9, 0, 5]);
+    bytes.extend(b"items");
+    bytes.extend(&[10, 0, 0, 0, 2]);
+    bytes.extend(&[1, 0, 1, b'a', 5, 0]);
+    bytes.extend(&[99, 0, 3]);
+    bytes.extend(b"bad");
+
+    match reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])) {
+        Err(reader::NbtReadError::UnknownTagType(99)) => (),
+        other => panic!("Expected UnknownTagType, got {:?}", other),
+    };
+
+    let options = reader::ReaderOptions {
+        capture_stack_on_error: true,
+        ..reader::ReaderOptions::default()
+    };
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options) {
+        Err(reader::NbtReadError::WithStack { error, stack }) => {
+            match *error {
+                reader::NbtReadError::UnknownTagType(99) => (),
+                other => panic!("Expected UnknownTagType, got {:?}", other),
+            };
+            assert_eq!(
+                vec![
+                    "TAG_Compound, at entry \"items\"",
+                    "TAG_List of TAG_Compound, at index 1",
+                    "TAG_Compound, at entry \"bad\"",
+                ],
+                stack,
+            );
+        },
+        other => panic!("Expected an error with a stack, got {:?}", other),
+    };
+}
+
+
+/// A root list, containing a list, containing a list... `depth` lists in all.
+fn nested_lists(depth: usize) -> Vec<u8> {
+    let mut bytes = vec![9u8, 0, 0];
+    for _ in 1..depth {
+        bytes.extend(&[9, 0, 0, 0, 1]);
+    }
+    bytes.extend(&[0, 0, 0, 0, 0]);
+    bytes
+}
+
+
+#[test]
+fn test_max_depth() {
+    let limits = reader::ReadLimits { max_depth: 10, ..reader::ReadLimits::default() };
+    let bytes = nested_lists(10);
+    assert!(reader::parse_nbt_stream_with_limits(&mut Cursor::new(&bytes[..]), &limits).is_ok());
+
+    let bytes = nested_lists(12);
+    match reader::parse_nbt_stream_with_limits(&mut Cursor::new(&bytes[..]), &limits) {
+        Err(reader::NbtReadError::MaxDepthExceeded) => (),
+        other => panic!("Expected MaxDepthExceeded, got {:?}", other),
+    };
+
+    let bytes = nested_lists(100000);
+    match reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])) {
+        Err(reader::NbtReadError::MaxDepthExceeded) => (),
+        other => panic!("Expected MaxDepthExceeded, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_max_bytes() {
+    // A byte array claiming to be 4 GiB long, with no data following.
+    let forged = [7u8, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    match reader::parse_nbt_stream(&mut Cursor::new(&forged[..])) {
+        Err(reader::NbtReadError::AllocationLimitExceeded) => (),
+        other => panic!("Expected AllocationLimitExceeded, got {:?}", other),
+    };
+
+    // A list of 100 ints, inside a compound.
+    let mut bytes = vec![10u8, 0, 0, 9, 0, 1, b'l', 3, 0, 0, 0, 100];
+    bytes.extend(vec![0u8; 400]);
+    bytes.push(0);
+    let limits = reader::ReadLimits { max_bytes: 400, ..reader::ReadLimits::default() };
+    assert!(reader::parse_nbt_stream_with_limits(&mut Cursor::new(&bytes[..]), &limits).is_ok());
+    let limits = reader::ReadLimits { max_bytes: 399, ..reader::ReadLimits::default() };
+    match reader::parse_nbt_stream_with_limits(&mut Cursor::new(&bytes[..]), &limits) {
+        Err(reader::NbtReadError::AllocationLimitExceeded) => (),
+        other => panic!("Expected AllocationLimitExceeded, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_parse_with_shared_context() {
+    let mut context = reader::ParseContext::new();
+    let options = reader::ReaderOptions::default();
+    let parse = |bytes: &[u8], context: &mut reader::ParseContext| {
+        reader::parse_nbt_stream_with_context(&mut Cursor::new(bytes), &options, context)
+    };
+
+    let mut bigtest = Vec::new();
+    GzDecoder::new(BIGTEST).read_to_end(&mut bigtest).unwrap();
+    let expected = reader::parse_nbt_stream(&mut Cursor::new(&bigtest[..])).unwrap();
+    assert_eq!(expected, parse(&bigtest, &mut context).unwrap());
+
+    // A string cut off part way through doesn't leave anything behind.
+    let truncated = [8u8, 0, 1, b'a', 0, 10, b'h', b'e', b'l'];
+    match parse(&truncated, &mut context) {
+        Err(reader::NbtReadError::UnexpectedEof) => (),
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    };
+    let root = parse(HELLO_WORLD, &mut context).unwrap();
+    assert_eq!(root.name, "hello world");
+    assert_eq!(expected, parse(&bigtest, &mut context).unwrap());
+}
+
+
+#[test]
+fn test_forged_array_length() {
+    // A TAG_Long_Array claiming 2^27 elements, the whole default 1 GiB
+    // budget, and a TAG_Byte_Array claiming 2^30 bytes, each followed by a
+    // handful of bytes. Neither is allocated up front.
+    let longs = [12u8, 0, 0, 0x08, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+    let bytes = [7u8, 0, 0, 0x40, 0, 0, 0, 1, 2, 3];
+    let mut context = reader::ParseContext::new();
+    let options = reader::ReaderOptions::default();
+    for data in &[&longs[..], &bytes[..]] {
+        let mut stream = Cursor::new(data);
+        match reader::parse_nbt_stream_with_context(&mut stream, &options, &mut context) {
+            Err(reader::NbtReadError::UnexpectedEof) => (),
+            other => panic!("Expected UnexpectedEof, got {:?}", other),
+        };
+    }
+    assert!(context.retained_bytes() <= 64 * 1024, "{}", context.retained_bytes());
+}
+
+
+#[test]
+fn test_parse_little_endian() {
+    // Bedrock's level.dat has an eight byte header before the NBT: a format
+    // version, then the length of the rest, both little-endian ints.
+    let data = BEDROCK_LEVEL_DAT;
+    let length = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    assert_eq!(data.len() - 8, length as usize);
+    let mut stream = Cursor::new(&data[8..]);
+    let root = reader::parse_nbt_stream_with_endianness(&mut stream, nbt::Endianness::Little)
+        .unwrap();
+    assert_eq!("", root.name);
+    let level = root.value.as_compound().unwrap();
+    assert_eq!(Some("Bedrock level"), level.get("LevelName").and_then(nbt::Value::as_str));
+    assert_eq!(Some(-4172144997902289642), level.get("RandomSeed").and_then(nbt::Value::as_i64));
+    assert_eq!(Some(-12), level.get("SpawnX").and_then(nbt::Value::as_i32));
+    assert_eq!(
+        Some(&nbt::List::Int(vec![1, 20, 81, 1, 0])),
+        level.get("lastOpenedWithVersion").and_then(nbt::Value::as_list),
+    );
+    assert_eq!(
+        Some(&nbt::Value::Float(0.05)),
+        root.value.get_path("abilities.flySpeed").as_deref(),
+    );
+
+    // Converting it to big-endian gives the same tree.
+    let mut big_endian = Vec::new();
+    tape::transcode_endianness(
+        &data[8..], &mut big_endian, nbt::Endianness::Little, nbt::Endianness::Big,
+    ).unwrap();
+    assert_eq!(root, reader::parse_nbt_stream(&mut Cursor::new(&big_endian[..])).unwrap());
+}
+
+
+#[test]
+fn test_parse_network_nbt() {
+    // Hello world's root compound, without its name.
+    let name_length = 2 + "hello world".len();
+    let mut network = vec![HELLO_WORLD[0]];
+    network.extend(&HELLO_WORLD[1 + name_length..]);
+    network.extend(b"next packet");
+
+    let mut stream = Cursor::new(&network[..]);
+    let value = reader::parse_network_nbt(&mut stream).unwrap();
+    let expected = reader::parse_nbt_stream(&mut Cursor::new(HELLO_WORLD)).unwrap();
+    assert_eq!(expected.value, value);
+    // Exactly the NBT was read.
+    assert_eq!((network.len() - b"next packet".len()) as u64, stream.position());
+
+    let int = [3u8, 0, 0, 0, 7];
+    assert_eq!(nbt::Value::Int(7), reader::parse_network_nbt(&mut &int[..]).unwrap());
+    match reader::parse_network_nbt(&mut &[0u8][..]) {
+        Err(reader::NbtReadError::InvalidTagType) => (),
+        other => panic!("Expected InvalidTagType, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_parse_bedrock_network_nbt() {
+    let bytes = [
+        10, 0,
+        8, 4, b'n', b'a', b'm', b'e', 5, b'S', b't', b'e', b'v', b'e',
+        // Zig-zag -3 and 300.
+        3, 2, b'h', b'p', 5,
+        4, 1, b't', 0xd8, 0x04,
+        2, 1, b's', 0x02, 0x01,
+        // Two ints, 1 and -1, then an array of one int, 64.
+        9, 1, b'l', 3, 4, 2, 1,
+        11, 1, b'a', 2, 0x80, 0x01,
+        0,
+    ];
+    let root = reader::parse_bedrock_network_nbt(&mut Cursor::new(&bytes[..])).unwrap();
+    assert_eq!("", root.name);
+    let mut expected = nbt::Compound::new();
+    expected.insert("name".to_string(), nbt::Value::String("Steve".to_string()));
+    expected.insert("hp".to_string(), nbt::Value::Int(-3));
+    expected.insert("t".to_string(), nbt::Value::Long(300));
+    expected.insert("s".to_string(), nbt::Value::Short(0x0102));
+    expected.insert("l".to_string(), nbt::Value::List(nbt::List::Int(vec![1, -1])));
+    expected.insert("a".to_string(), nbt::Value::IntArray(vec![64]));
+    assert_eq!(nbt::Value::Compound(expected), root.value);
+
+    let too_long = [8u8, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    match reader::parse_bedrock_network_nbt(&mut Cursor::new(&too_long[..])) {
+        Err(reader::NbtReadError::VarIntTooLong) => (),
+        other => panic!("Expected VarIntTooLong, got {:?}", other),
+    };
+    match reader::parse_bedrock_network_nbt(&mut Cursor::new(&[8u8, 0x80][..])) {
+        Err(reader::NbtReadError::UnexpectedEof) => (),
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    };
+    // A string's length is checked before it's allocated.
+    let huge = [8u8, 0xff, 0xff, 0xff, 0xff, 0x0f];
+    match reader::parse_bedrock_network_nbt(&mut Cursor::new(&huge[..])) {
+        Err(reader::NbtReadError::AllocationLimitExceeded) => (),
+        other => panic!("Expected AllocationLimitExceeded, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_report_offset_on_error() {
+    // Hello world's only entry, after the 14 byte root header, with a bad
+    // tag type.
+    let mut corrupt = HELLO_WORLD.to_vec();
+    assert_eq!(nbt::TAG_STRING, corrupt[14]);
+    corrupt[14] = 0x42;
+
+    let options = reader::ReaderOptions {
+        report_offset_on_error: true,
+        ..reader::ReaderOptions::default()
+    };
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(&corrupt[..]), &options) {
+        Err(reader::NbtReadError::At { offset, error }) => {
+            // After the entry's name, "name".
+            assert_eq!(15 + 2 + 4, offset);
+            match *error {
+                reader::NbtReadError::UnknownTagType(0x42) => (),
+                other => panic!("Expected UnknownTagType, got {:?}", other),
+            }
+        },
+        other => panic!("Expected At, got {:?}", other),
+    };
+
+    // Cut off part way through the entry's value.
+    let truncated = &HELLO_WORLD[..HELLO_WORLD.len() - 4];
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(truncated), &options) {
+        Err(reader::NbtReadError::At { offset, error }) => {
+            assert_eq!(truncated.len() as u64, offset);
+            assert!(matches!(*error, reader::NbtReadError::UnexpectedEof));
+        },
+        other => panic!("Expected At, got {:?}", other),
+    };
+
+    // Off by default.
+    match reader::parse_nbt_stream(&mut Cursor::new(&corrupt[..])) {
+        Err(reader::NbtReadError::UnknownTagType(0x42)) => (),
+        other => panic!("Expected UnknownTagType, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_visit_builds_same_tree() {
+    let mut bigtest = Vec::new();
+    GzDecoder::new(BIGTEST).read_to_end(&mut bigtest).unwrap();
+    for bytes in &[HELLO_WORLD, &bigtest[..], &nested_lists(5)[..]] {
+        let mut builder = reader::TreeBuilder::new();
+        reader::visit_nbt_stream(&mut Cursor::new(bytes), &mut builder).unwrap();
+        assert_eq!(
+            Some(reader::parse_nbt_bytes(bytes).unwrap()),
+            builder.into_root(),
+        );
+    }
+}
+
+
+/// Finds an int entry of the root compound.
+struct FindRootInt {
+    key: &'static str,
+    depth: usize,
+    found: Option<i32>,
+}
+
+
+impl reader::NbtVisitor for FindRootInt {
+    fn start_compound(&mut self, _name: Option<&str>) {
+        self.depth += 1;
+    }
+
+    fn end_compound(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn start_list(&mut self, _name: Option<&str>, _element_type: u8, _len: usize) {
+        self.depth += 1;
+    }
+
+    fn end_list(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn tag(&mut self, name: Option<&str>, value: reader::ScalarValue) {
+        if let (1, Some(name), reader::ScalarValue::Int(v)) = (self.depth, name, value) {
+            if name == self.key {
+                self.found = Some(v);
+            }
+        }
+    }
+}
+
+
+#[test]
+fn test_visit_finds_field() {
+    let mut visitor = FindRootInt { key: "intTest", depth: 0, found: None };
+    reader::visit_nbt_stream(&mut GzDecoder::new(BIGTEST), &mut visitor).unwrap();
+    assert_eq!(Some(2147483647), visitor.found);
+    assert_eq!(0, visitor.depth);
+}
+
+
+#[test]
+fn test_visit_errors() {
+    let limits = reader::ReadLimits { max_depth: 10, ..reader::ReadLimits::default() };
+    let options = reader::ReaderOptions { limits, ..reader::ReaderOptions::default() };
+    let mut builder = reader::TreeBuilder::new();
+    match reader::visit_nbt_stream_with_options(
+            &mut Cursor::new(&nested_lists(12)[..]), &options, &mut builder) {
+        Err(reader::NbtReadError::MaxDepthExceeded) => (),
+        other => panic!("Expected MaxDepthExceeded, got {:?}", other),
+    };
+    assert_eq!(None, builder.into_root());
+
+    let bytes = [10u8, 0, 0, 99, 0, 3, b'b', b'a', b'd'];
+    match reader::visit_nbt_stream(&mut Cursor::new(&bytes[..]), &mut reader::TreeBuilder::new()) {
+        Err(reader::NbtReadError::UnknownTagType(99)) => (),
+        other => panic!("Expected UnknownTagType, got {:?}", other),
     };
-    match entry {
-        &nbt::Value::String(ref s) => assert_eq!("Bananrama", s),
-        _ => panic!("Entry wasn't a string."),
+}
+
+
+#[test]
+fn test_reject_trailing_data() {
+    let mut bytes = HELLO_WORLD.to_vec();
+    assert!(reader::parse_nbt_stream_strict(&mut Cursor::new(&bytes[..])).is_ok());
+
+    bytes.push(0);
+    assert!(reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).is_ok());
+    match reader::parse_nbt_stream_strict(&mut Cursor::new(&bytes[..])) {
+        Err(reader::NbtReadError::TrailingData) => (),
+        other => panic!("Expected TrailingData, got {:?}", other),
+    };
+
+    let options = reader::ReaderOptions {
+        reject_trailing_data: true,
+        ..reader::ReaderOptions::default()
+    };
+    let mut builder = reader::TreeBuilder::new();
+    match reader::visit_nbt_stream_with_options(
+            &mut Cursor::new(&bytes[..]), &options, &mut builder) {
+        Err(reader::NbtReadError::TrailingData) => (),
+        other => panic!("Expected TrailingData, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_strict_options() {
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes.extend_from_slice(b"junk");
+    let options = reader::ReaderOptions::default();
+    assert!(reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options).is_ok());
+    let options = reader::ReaderOptions::strict();
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options) {
+        Err(reader::NbtReadError::TrailingData) => (),
+        other => panic!("Expected TrailingData, got {:?}", other),
+    };
+    let root = reader::parse_nbt_file_with_options(HELLO_WORLD, &options).unwrap();
+    assert_eq!(root.name, "hello world");
+}
+
+
+#[test]
+fn test_reject_duplicate_keys() {
+    // A root compound with two TAG_Byte entries named "a".
+    let bytes = [10u8, 0, 0, 1, 0, 1, b'a', 1, 1, 0, 1, b'a', 2, 0];
+    let root = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(Some(&nbt::Value::Byte(2)), root.value.as_compound().unwrap().get("a"));
+    let options = reader::ReaderOptions::strict();
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options) {
+        Err(reader::NbtReadError::DuplicateKey(ref name)) if name == "a" => (),
+        other => panic!("Expected DuplicateKey, got {:?}", other),
+    };
+    let mut builder = reader::TreeBuilder::new();
+    match reader::visit_nbt_stream_with_options(
+            &mut Cursor::new(&bytes[..]), &options, &mut builder) {
+        Err(reader::NbtReadError::DuplicateKey(ref name)) if name == "a" => (),
+        other => panic!("Expected DuplicateKey, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_reject_non_minimal_varints() {
+    // Bedrock network NBT: a root compound holding the TAG_Int "a" = 1,
+    // zig-zag encoded with a needless zero byte.
+    let bytes = [10u8, 0, 3, 1, b'a', 0x82, 0x00, 0];
+    let mut options = reader::ReaderOptions {
+        endianness: nbt::Endianness::Little,
+        varints: true,
+        ..reader::ReaderOptions::default()
+    };
+    let root = reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options)
+        .unwrap();
+    assert_eq!(Some(&nbt::Value::Int(1)), root.value.as_compound().unwrap().get("a"));
+    options.reject_non_minimal_varints = true;
+    match reader::parse_nbt_stream_with_options(&mut Cursor::new(&bytes[..]), &options) {
+        Err(reader::NbtReadError::NonMinimalVarInt) => (),
+        other => panic!("Expected NonMinimalVarInt, got {:?}", other),
+    };
+    assert!(reader::ReaderOptions::strict().reject_non_minimal_varints);
+}
+
+
+#[test]
+fn test_reject_concatenated_documents() {
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes.extend(HELLO_WORLD);
+    let mut reader = Cursor::new(&bytes[..]);
+    assert!(reader::parse_nbt_stream(&mut reader).is_ok());
+    assert!(reader::parse_nbt_stream_strict(&mut reader).is_ok());
+    match reader::parse_nbt_stream_strict(&mut Cursor::new(&bytes[..])) {
+        Err(reader::NbtReadError::TrailingData) => (),
+        other => panic!("Expected TrailingData, got {:?}", other),
     };
 }
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use serde::{Deserialize, Serialize};
+
+use crate::nbt::ser::{self, ByteArray, IntArray, LongArray, SerializeError};
+use crate::nbt::{de, reader, snbt, List, Value};
+
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Point,
+    Circle(f32),
+    Rect { w: i32, h: i32 },
+}
+
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    enabled: bool,
+    level: u8,
+    scale: f64,
+    tags: Vec<String>,
+    nickname: Option<String>,
+    heights: LongArray,
+    uuid: IntArray,
+    seed: ByteArray,
+    shapes: Vec<Shape>,
+    limits: BTreeMap<String, i16>,
+}
+
+
+fn sample_config() -> Config {
+    let mut limits = BTreeMap::new();
+    limits.insert("max".to_string(), 10);
+    Config {
+        name: "test".to_string(),
+        enabled: true,
+        level: 200,
+        scale: 0.5,
+        tags: vec!["a".to_string(), "b".to_string()],
+        nickname: None,
+        heights: LongArray(vec![1, 2]),
+        uuid: IntArray(vec![]),
+        seed: ByteArray(vec![0, 255]),
+        shapes: vec![Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }],
+        limits,
+    }
+}
+
+
+#[test]
+fn test_to_nbt_value() {
+    let value = ser::to_nbt_value(&sample_config()).unwrap();
+    let expected = snbt::parse_snbt(
+        r#"{name: "test", enabled: 1b, level: 200s, scale: 0.5d, tags: ["a", "b"],
+            heights: [L; 1L, 2L], uuid: [I;], seed: [B; 0b, -1b],
+            shapes: [{Circle: 1.5f}, {Rect: {w: 2, h: 3}}], limits: {max: 10s}}"#,
+    ).unwrap();
+    assert_eq!(expected, value);
+
+    assert_eq!(Value::String("Point".to_string()), ser::to_nbt_value(&Shape::Point).unwrap());
+    assert_eq!(Value::List(List::Empty(0)), ser::to_nbt_value(&Vec::<i32>::new()).unwrap());
+}
+
+
+#[test]
+fn test_to_nbt_writer_round_trip() {
+    let config = sample_config();
+    let mut bytes = Vec::new();
+    ser::to_nbt_writer(&mut bytes, "config", &config).unwrap();
+
+    let root = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+    assert_eq!("config", root.name);
+    let reread: Config = de::from_nbt_value(&root.value).unwrap();
+    assert_eq!(config, reread);
+}
+
+
+#[test]
+fn test_to_nbt_value_errors() {
+    match ser::to_nbt_value(&(1, "two")) {
+        Err(SerializeError::MixedList) => (),
+        other => panic!("Expected MixedList, got {:?}", other),
+    };
+    let mut map = BTreeMap::new();
+    map.insert(1, 2);
+    match ser::to_nbt_value(&map) {
+        Err(SerializeError::KeyNotString) => (),
+        other => panic!("Expected KeyNotString, got {:?}", other),
+    };
+    match ser::to_nbt_value(&vec![Some(1), None]) {
+        Err(SerializeError::NoneValue) => (),
+        other => panic!("Expected NoneValue, got {:?}", other),
+    };
+    match ser::to_nbt_value(&u64::MAX) {
+        Err(SerializeError::Unsupported(_)) => (),
+        other => panic!("Expected Unsupported, got {:?}", other),
+    };
+}
@@ -0,0 +1,165 @@
+use crate::nbt::snbt::{self, SnbtParseError};
+use crate::nbt::{Compound, List, Value};
+
+
+fn parse(input: &str) -> Value {
+    match snbt::parse_snbt(input) {
+        Ok(value) => value,
+        Err(err) => panic!("{:?}", err),
+    }
+}
+
+
+#[test]
+fn test_snbt_compound() {
+    let value = parse(r#"{name:"Steve",Health:20.0f,Inventory:[]}"#);
+    let mut expected = Compound::new();
+    expected.insert("name".to_string(), Value::String("Steve".to_string()));
+    expected.insert("Health".to_string(), Value::Float(20.0));
+    expected.insert("Inventory".to_string(), Value::List(List::Empty(0)));
+    assert_eq!(Value::Compound(expected), value);
+}
+
+
+#[test]
+fn test_snbt_numbers() {
+    assert_eq!(Value::Byte(-3), parse("-3b"));
+    assert_eq!(Value::Byte(1), parse("true"));
+    assert_eq!(Value::Byte(0), parse("false"));
+    assert_eq!(Value::Short(300), parse("300S"));
+    assert_eq!(Value::Int(42), parse("42"));
+    assert_eq!(Value::Long(-9000000000), parse("-9000000000L"));
+    assert_eq!(Value::Float(1.5), parse("1.5f"));
+    assert_eq!(Value::Double(0.25), parse(".25"));
+    assert_eq!(Value::Double(2.0), parse("2d"));
+    assert_eq!(Value::Double(1e3), parse("1e3"));
+    // Not numbers after all.
+    assert_eq!(Value::String("300b".to_string()), parse("300b"));
+    assert_eq!(Value::String("minecraft:stone".to_string()), parse("'minecraft:stone'"));
+    assert_eq!(Value::String("stone".to_string()), parse("stone"));
+    assert_eq!(Value::String("1.2.3".to_string()), parse("1.2.3"));
+}
+
+
+#[test]
+fn test_snbt_lists_and_arrays() {
+    assert_eq!(Value::List(List::Int(vec![1, 2, 3])), parse("[1, 2, 3]"));
+    assert_eq!(
+        Value::List(List::List(vec![List::Byte(vec![1]), List::Empty(0)])),
+        parse("[[1b], []]"),
+    );
+    assert_eq!(Value::ByteArray(vec![1, 255]), parse("[B; 1b, -1b]"));
+    assert_eq!(Value::IntArray(vec![1, -2]), parse("[I;1,-2]"));
+    assert_eq!(Value::LongArray(vec![]), parse("[L;]"));
+    // A list of strings, not an array.
+    assert_eq!(Value::List(List::String(vec!["B".to_string()])), parse("[B]"));
+}
+
+
+#[test]
+fn test_snbt_nested() {
+    let value = parse(
+        r#"{ "quoted key" : { display: { Name: '{"text":"It\'s a \\ test"}' } },
+             Items: [{id: "minecraft:torch", Count: 1b}, {id: "minecraft:stick", Count: 2b}] }"#,
+    );
+    assert_eq!(
+        Some(r#"{"text":"It's a \ test"}"#),
+        value.get_path("quoted key.display.Name").as_ref().and_then(|v| v.as_str()),
+    );
+    assert_eq!(
+        Some(2),
+        value.get_path("Items.1.Count").and_then(|v| v.as_i8()),
+    );
+}
+
+
+#[test]
+fn test_snbt_errors() {
+    assert_eq!(Err(SnbtParseError::UnexpectedEnd), snbt::parse_snbt("{a:1"));
+    assert_eq!(Err(SnbtParseError::MixedList { position: 0 }), snbt::parse_snbt("[1, 2b]"));
+    assert_eq!(Err(SnbtParseError::MixedArray { position: 0 }), snbt::parse_snbt("[I; 1b]"));
+    assert_eq!(
+        Err(SnbtParseError::TrailingCharacters { position: 4 }),
+        snbt::parse_snbt("{}  }"),
+    );
+    assert_eq!(
+        Err(SnbtParseError::Unexpected { position: 2, found: '}', expected: "':'" }),
+        snbt::parse_snbt("{a}"),
+    );
+    assert_eq!(
+        Err(SnbtParseError::MaxDepthExceeded),
+        snbt::parse_snbt(&"[".repeat(1000)),
+    );
+}
+
+
+#[test]
+fn test_to_snbt() {
+    let value = parse(
+        r#"{Health: 20.0f, Count: 20b, "has space": 3s, Time: 100L, Pos: [1.5d, 2.0d],
+            Name: 'Say "hi"', Ids: [I; 1, 2, 3], Bytes: [B; -1b], Longs: [L; 7L],
+            Empty: [], Items: [{id: "minecraft:torch"}], Age: 7}"#,
+    );
+    let snbt = snbt::to_snbt(&value);
+    assert_eq!(
+        r#"{Age:7,Bytes:[B;-1b],Count:20b,Empty:[],Health:20.0f,Ids:[I;1,2,3],"#.to_string()
+            + r#"Items:[{id:"minecraft:torch"}],Longs:[L;7L],Name:'Say "hi"',"#
+            + r#"Pos:[1.5d,2.0d],Time:100L,"has space":3s}"#,
+        snbt,
+    );
+    assert_eq!(value, parse(&snbt));
+    assert_eq!(value, parse(&snbt::to_snbt_pretty(&value, 4)));
+}
+
+
+#[test]
+fn test_to_snbt_pretty() {
+    let value = parse(
+        r#"{name: "Steve", Pos: [1.0d, 2.0d], Ids: [I; 1, 2], Inventory: [],
+            Items: [{Count: 1b, tag: {}}], Nested: [[1], [2]]}"#,
+    );
+    let expected = r#"{
+  Ids: [I; 1, 2],
+  Inventory: [],
+  Items: [
+    {
+      Count: 1b,
+      tag: {}
+    }
+  ],
+  Nested: [
+    [1],
+    [2]
+  ],
+  Pos: [1.0d, 2.0d],
+  name: "Steve"
+}"#;
+    assert_eq!(expected, snbt::to_snbt_pretty(&value, 2));
+}
+
+
+#[test]
+fn test_to_snbt_escapes() {
+    let value = Value::String(r#"both ' and " \ "#.to_string());
+    let snbt = snbt::to_snbt(&value);
+    assert_eq!(r#""both ' and \" \\ ""#, snbt);
+    assert_eq!(value, parse(&snbt));
+}
+
+
+#[test]
+fn test_to_snbt_float_edge_cases() {
+    // Compared bitwise, since -0.0 == 0.0.
+    for &v in &[-0.0f32, f32::MIN_POSITIVE / 2.0, f32::MAX, 1.5e-42] {
+        match parse(&snbt::to_snbt(&Value::Float(v))) {
+            Value::Float(parsed) => assert_eq!(v.to_bits(), parsed.to_bits()),
+            other => panic!("Expected Float, got {:?}", other),
+        }
+    }
+    for &v in &[-0.0f64, f64::MIN_POSITIVE / 2.0, 5e-324, f64::MAX, -1.2345e300] {
+        match parse(&snbt::to_snbt(&Value::Double(v))) {
+            Value::Double(parsed) => assert_eq!(v.to_bits(), parsed.to_bits()),
+            other => panic!("Expected Double, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::Cursor;
+
+use crate::nbt;
+use crate::nbt::tape;
+
+
+const HELLO_WORLD: &[u8] = include_bytes!("hello_world.nbt");
+
+
+#[test]
+fn test_tape_hello_world() {
+    let events = tape::tag_events(Cursor::new(HELLO_WORLD))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(3, events.len());
+
+    assert_eq!(0, events[0].depth);
+    assert_eq!(nbt::TAG_COMPOUND, events[0].tag_type);
+    assert_eq!(Some("hello world".to_string()), events[0].name);
+    assert!(events[0].payload.is_empty());
+
+    assert_eq!(1, events[1].depth);
+    assert_eq!(nbt::TAG_STRING, events[1].tag_type);
+    assert_eq!(Some("name".to_string()), events[1].name);
+    assert_eq!(b"\x00\x09Bananrama", &events[1].payload[..]);
+
+    assert_eq!(1, events[2].depth);
+    assert_eq!(nbt::TAG_END, events[2].tag_type);
+    assert_eq!(None, events[2].name);
+}
+
+
+#[test]
+fn test_tape_list_elements() {
+    // A root list of two shorts.
+    let data = [
+        9u8, 0, 0,
+        2, 0, 0, 0, 2,
+        0, 1,
+        0, 2,
+    ];
+    let events = tape::tag_events(&data[..])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(3, events.len());
+    assert_eq!(nbt::TAG_LIST, events[0].tag_type);
+    assert_eq!(vec![2u8, 0, 0, 0, 2], events[0].payload);
+    for (event, expected) in events[1..].iter().zip(&[1u8, 2]) {
+        assert_eq!(1, event.depth);
+        assert_eq!(nbt::TAG_SHORT, event.tag_type);
+        assert_eq!(None, event.name);
+        assert_eq!(vec![0u8, *expected], event.payload);
+    }
+}
+
+
+#[test]
+fn test_tape_stops_on_error() {
+    let data = [10u8, 0, 0, 0xee, 0, 0];
+    let mut events = tape::tag_events(&data[..]);
+    assert!(events.next().unwrap().is_ok());
+    match events.next() {
+        Some(Err(nbt::reader::NbtReadError::UnknownTagType(0xee))) => (),
+        other => panic!("Expected an unknown tag type, got {:?}", other),
+    };
+    assert!(events.next().is_none());
+}
+
+
+#[test]
+fn test_stream_contains_tag() {
+    // {"": {a: [I; 1, 2]}}
+    let data = [
+        10u8, 0, 0,
+        11, 0, 1, b'a', 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2,
+        0,
+    ];
+    assert!(tape::stream_contains_tag(&data[..], nbt::TAG_INT_ARRAY).unwrap());
+    assert!(!tape::stream_contains_tag(&data[..], nbt::TAG_STRING).unwrap());
+    assert!(
+        !tape::stream_contains_tag(Cursor::new(HELLO_WORLD), nbt::TAG_INT_ARRAY).unwrap()
+    );
+    assert!(
+        tape::stream_contains_tag(Cursor::new(HELLO_WORLD), nbt::TAG_STRING).unwrap()
+    );
+}
+
+
+#[test]
+fn test_stream_contains_tag_short_circuits() {
+    // The int array comes before a truncated entry, which is never read.
+    let data = [
+        10u8, 0, 0,
+        11, 0, 1, b'a', 0, 0, 0, 0,
+        8, 0, 1, b'b', 0, 9,
+    ];
+    assert!(tape::stream_contains_tag(&data[..], nbt::TAG_INT_ARRAY).unwrap());
+    assert!(tape::stream_contains_tag(&data[..], nbt::TAG_LONG).is_err());
+}
+
+
+#[test]
+fn test_transcode_endianness() {
+    let mut little = Vec::new();
+    tape::transcode_endianness(
+        HELLO_WORLD, &mut little, nbt::Endianness::Big, nbt::Endianness::Little,
+    ).unwrap();
+    assert_eq!(
+        &b"\x0a\x0b\x00hello world\x08\x04\x00name\x09\x00Bananrama\x00"[..],
+        &little[..],
+    );
+    assert_eq!(
+        Some(nbt::Endianness::Little), nbt::reader::guess_endianness(&little),
+    );
+
+    let events = tape::tag_events_with_endianness(&little[..], nbt::Endianness::Little)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(Some("name".to_string()), events[1].name);
+
+    let mut big = Vec::new();
+    tape::transcode_endianness(
+        &little[..], &mut big, nbt::Endianness::Little, nbt::Endianness::Big,
+    ).unwrap();
+    assert_eq!(HELLO_WORLD, &big[..]);
+}
+
+
+#[test]
+fn test_transcode_endianness_long_name() {
+    // Four byte UTF-8 characters take six bytes in modified UTF-8.
+    let name = "\u{1f600}".repeat(16000);
+    let mut bytes = vec![10, 0, 0, 1];
+    bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(&[1, 0]);
+    match tape::transcode_endianness(
+            &bytes[..], Vec::new(), nbt::Endianness::Big, nbt::Endianness::Little) {
+        Err(nbt::reader::NbtReadError::StringTooLong(96000)) => (),
+        other => panic!("Expected StringTooLong, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_transcode_endianness_numbers() {
+    // {"": {l: [1s, 2s], i: [I; 0x01020304]}}
+    let big = [
+        10u8, 0, 0,
+        9, 0, 1, b'l', 2, 0, 0, 0, 2, 0, 1, 0, 2,
+        11, 0, 1, b'i', 0, 0, 0, 1, 1, 2, 3, 4,
+        0,
+    ];
+    let little = [
+        10u8, 0, 0,
+        9, 1, 0, b'l', 2, 2, 0, 0, 0, 1, 0, 2, 0,
+        11, 1, 0, b'i', 1, 0, 0, 0, 4, 3, 2, 1,
+        0,
+    ];
+    let mut out = Vec::new();
+    tape::transcode_endianness(
+        &big[..], &mut out, nbt::Endianness::Big, nbt::Endianness::Little,
+    ).unwrap();
+    assert_eq!(&little[..], &out[..]);
+}
+
+
+#[test]
+fn test_tape_truncated_long_array() {
+    // A long array claiming 0xffffffff elements, with only one following.
+    let mut data = vec![12u8, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    data.extend(&[0; 8]);
+    let limits = nbt::reader::ReadLimits { max_bytes: usize::MAX, ..Default::default() };
+    match tape::tag_events_with_limits(&data[..], nbt::Endianness::Big, limits).next() {
+        Some(Err(nbt::reader::NbtReadError::UnexpectedEof)) => (),
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_tape_limits() {
+    // 32 GiB of longs is well past the default limit.
+    let mut data = vec![12u8, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    data.extend(&[0; 8]);
+    match tape::tag_events(&data[..]).next() {
+        Some(Err(nbt::reader::NbtReadError::AllocationLimitExceeded)) => (),
+        other => panic!("Expected AllocationLimitExceeded, got {:?}", other),
+    }
+
+    // A compound holding two int arrays of 2 ints each: 16 bytes.
+    let data = [
+        10u8, 0, 0,
+        11, 0, 1, b'a', 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2,
+        11, 0, 1, b'b', 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4,
+        0,
+    ];
+    let events = |max_bytes, max_depth| {
+        let limits = nbt::reader::ReadLimits { max_bytes, max_depth, ..Default::default() };
+        tape::tag_events_with_limits(&data[..], nbt::Endianness::Big, limits)
+            .collect::<Result<Vec<_>, _>>()
+    };
+    assert_eq!(4, events(16, 1).unwrap().len());
+    match events(15, 1) {
+        Err(nbt::reader::NbtReadError::AllocationLimitExceeded) => (),
+        other => panic!("Expected AllocationLimitExceeded, got {:?}", other),
+    }
+    match events(16, 0) {
+        Err(nbt::reader::NbtReadError::MaxDepthExceeded) => (),
+        other => panic!("Expected MaxDepthExceeded, got {:?}", other),
+    }
+}
@@ -0,0 +1,363 @@
+use crate::nbt::{self, Compound, CompoundExt, List, Value};
+
+
+fn numbered_compounds(count: i32) -> List {
+    List::Compound((0..count).map(|i| {
+        let mut compound = Compound::new();
+        compound.insert("n".to_string(), Value::Int(i));
+        compound
+    }).collect())
+}
+
+
+#[test]
+fn test_list_chunks() {
+    let list = numbered_compounds(5);
+    let pages = list.chunks(2)
+        .map(|page| {
+            page.iter().map(|c| match c.get("n") {
+                Some(Value::Int(n)) => *n,
+                _ => panic!("Compound lost its number."),
+            }).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(vec![vec![0, 1], vec![2, 3], vec![4]], pages);
+}
+
+
+#[test]
+fn test_list_chunks_of_non_compounds() {
+    assert_eq!(0, List::Empty(0).chunks(2).count());
+    assert_eq!(0, List::Int(vec![1, 2, 3]).chunks(2).count());
+}
+
+
+fn floats_tree(nan: u64, zero: f64) -> Value {
+    let mut pos = Compound::new();
+    pos.set_double("x", f64::from_bits(nan));
+    pos.set_double("y", zero);
+    pos.set_float("rot", f32::from_bits(0x7f80_0000 | nan as u32 & 0x7f_ffff));
+    let mut root = Compound::new();
+    root.set_compound("pos", pos);
+    root.insert(
+        "list".to_string(),
+        Value::List(List::Double(vec![1.5, f64::from_bits(nan), zero])),
+    );
+    Value::Compound(root)
+}
+
+
+#[test]
+fn test_canonicalize_floats() {
+    let mut a = floats_tree(0x7ff8_0000_0000_0001, -0.0);
+    let mut b = floats_tree(0xfff0_0000_dead_beef, 0.0);
+    assert!(!a.canonical_eq(&b));
+    assert!(a.canonical_eq(&a.clone()));
+    assert_ne!(a, a.clone());
+
+    a.canonicalize_floats(false);
+    b.canonicalize_floats(false);
+    // Only the zeros differ now.
+    assert!(!a.canonical_eq(&b));
+    assert!(a.get_path("pos.x").unwrap().canonical_eq(&b.get_path("pos.x").unwrap()));
+    assert!(a.get_path("pos.rot").unwrap().canonical_eq(&b.get_path("pos.rot").unwrap()));
+    assert!(a.get_path("list.1").unwrap().canonical_eq(&b.get_path("list.1").unwrap()));
+    assert!(!a.get_path("pos.y").unwrap().canonical_eq(&b.get_path("pos.y").unwrap()));
+    assert!(!a.get_path("list.2").unwrap().canonical_eq(&b.get_path("list.2").unwrap()));
+
+    a.canonicalize_floats(true);
+    b.canonicalize_floats(true);
+    assert!(a.canonical_eq(&b));
+    assert_eq!(Some(1.5), a.get_path("list.0").and_then(|v| v.as_f64()));
+}
+
+
+#[test]
+fn test_replace_strings() {
+    let mut block = Compound::new();
+    block.insert("Name".to_string(), Value::String("oldmod:ore".to_string()));
+    let mut root = Compound::new();
+    root.insert("id".to_string(), Value::String("oldmod:ore".to_string()));
+    root.insert("other".to_string(), Value::String("oldmod:ore_block".to_string()));
+    root.insert("palette".to_string(), Value::List(List::Compound(vec![block])));
+    root.insert(
+        "names".to_string(),
+        Value::List(List::String(vec!["oldmod:ore".to_string(), "stone".to_string()])),
+    );
+    let mut value = Value::Compound(root);
+
+    assert_eq!(3, value.replace_strings("oldmod:ore", "newmod:ore"));
+    assert_eq!(0, value.replace_strings("oldmod:ore", "newmod:ore"));
+
+    let root = match value {
+        Value::Compound(root) => root,
+        _ => panic!("Not a compound?"),
+    };
+    match root.get("id") {
+        Some(Value::String(s)) => assert_eq!("newmod:ore", s),
+        _ => panic!("id isn't a string."),
+    };
+    match root.get("other") {
+        Some(Value::String(s)) => assert_eq!("oldmod:ore_block", s),
+        _ => panic!("other isn't a string."),
+    };
+    match root.get("names") {
+        Some(Value::List(List::String(names))) => {
+            assert_eq!(vec!["newmod:ore", "stone"], *names);
+        },
+        _ => panic!("names isn't a list of strings."),
+    };
+    match root.get("palette") {
+        Some(Value::List(List::Compound(palette))) => match palette[0].get("Name") {
+            Some(Value::String(s)) => assert_eq!("newmod:ore", s),
+            _ => panic!("Name isn't a string."),
+        },
+        _ => panic!("palette isn't a list of compounds."),
+    };
+}
+
+
+#[test]
+fn test_list_to_array_and_back() {
+    let mut value = Value::List(List::Int(vec![1, -2, 3]));
+    assert!(nbt::list_to_array(&mut value));
+    match value {
+        Value::IntArray(ref ints) => assert_eq!(vec![1, -2, 3], *ints),
+        ref other => panic!("Expected an int array, got {:?}", other),
+    };
+    assert!(nbt::array_to_list(&mut value));
+    match value {
+        Value::List(List::Int(ref ints)) => assert_eq!(vec![1, -2, 3], *ints),
+        ref other => panic!("Expected a list of ints, got {:?}", other),
+    };
+
+    let mut value = Value::List(List::Byte(vec![-1, 2]));
+    assert!(nbt::list_to_array(&mut value));
+    match value {
+        Value::ByteArray(ref bytes) => assert_eq!(vec![0xff, 2], *bytes),
+        ref other => panic!("Expected a byte array, got {:?}", other),
+    };
+
+    let mut value = Value::List(List::Long(vec![1 << 40]));
+    assert!(nbt::list_to_array(&mut value));
+    match value {
+        Value::LongArray(ref longs) => assert_eq!(vec![1 << 40], *longs),
+        ref other => panic!("Expected a long array, got {:?}", other),
+    };
+
+    let mut value = Value::List(List::Double(vec![1.0]));
+    assert!(!nbt::list_to_array(&mut value));
+    assert!(!nbt::array_to_list(&mut value));
+}
+
+
+#[test]
+fn test_as_bool() {
+    assert_eq!(Some(false), Value::Byte(0).as_bool());
+    assert_eq!(Some(true), Value::Byte(1).as_bool());
+    assert_eq!(Some(true), Value::Byte(5).as_bool());
+    assert_eq!(None, Value::Int(1).as_bool());
+}
+
+
+#[test]
+fn test_accessors() {
+    assert_eq!(Some(3), Value::Int(3).as_i32());
+    assert_eq!(None, Value::Int(3).as_i64());
+    assert_eq!(None, Value::Short(3).as_i32());
+    assert_eq!(Some(0.5), Value::Double(0.5).as_f64());
+    assert_eq!(None, Value::Float(0.5).as_f64());
+    assert_eq!(Some("x"), Value::String("x".to_string()).as_str());
+    assert_eq!(None, Value::Byte(0).as_str());
+    assert_eq!(Some(&[1, 2][..]), Value::IntArray(vec![1, 2]).as_int_array());
+
+    let mut compound = Compound::new();
+    compound.insert("name".to_string(), Value::String("Steve".to_string()));
+    compound.insert("list".to_string(), Value::List(List::Int(vec![7])));
+    let value = Value::Compound(compound);
+    let compound = value.as_compound().unwrap();
+    assert_eq!(Some("Steve"), compound.get("name").and_then(Value::as_str));
+    match compound.get("list").and_then(Value::as_list) {
+        Some(List::Int(ints)) => assert_eq!(&vec![7], ints),
+        other => panic!("Expected an int list, got {:?}", other),
+    };
+    assert!(Value::Int(0).as_compound().is_none());
+    assert!(Value::Int(0).as_list().is_none());
+}
+
+
+#[test]
+fn test_get_path() {
+    let mut item = Compound::new();
+    item.insert("id".to_string(), Value::String("minecraft:torch".to_string()));
+    let mut player = Compound::new();
+    player.insert("Pos".to_string(), Value::List(List::Double(vec![1.5, 64.0, -3.5])));
+    player.insert("Inventory".to_string(), Value::List(List::Compound(vec![item])));
+    player.insert("Score".to_string(), Value::Int(12));
+    let mut data = Compound::new();
+    data.insert("Player".to_string(), Value::Compound(player));
+    let mut root = Compound::new();
+    root.insert("Data".to_string(), Value::Compound(data));
+    let root = Value::Compound(root);
+
+    assert_eq!(Some(12), root.get_path("Data.Player.Score").and_then(|v| v.as_i32()));
+    assert_eq!(Some(64.0), root.get_path("Data.Player.Pos.1").and_then(|v| v.as_f64()));
+    assert_eq!(
+        Some("minecraft:torch".to_string()),
+        root.get_path("Data.Player.Inventory.0.id").and_then(|v| v.as_str().map(str::to_string)),
+    );
+    assert!(root.get_path("Data.Player.Inventory.0").unwrap().as_compound().is_some());
+    assert!(root.get_path("Data.Player.Pos").unwrap().as_list().is_some());
+
+    assert!(root.get_path("Data.Missing").is_none());
+    assert!(root.get_path("Data.Player.Pos.3").is_none());
+    assert!(root.get_path("Data.Player.Pos.x").is_none());
+    assert!(root.get_path("Data.Player.Score.0").is_none());
+}
+
+
+#[test]
+fn test_compound_as_map() {
+    let mut counts = Compound::new();
+    counts.insert("minecraft:zombie".to_string(), Value::Int(12));
+    counts.insert("minecraft:creeper".to_string(), Value::Int(3));
+    let map = nbt::compound_as_map(&counts, Value::as_i32).unwrap();
+    assert_eq!(2, map.len());
+    assert_eq!(Some(&12), map.get("minecraft:zombie"));
+    assert_eq!(Some(&3), map.get("minecraft:creeper"));
+
+    counts.insert("minecraft:skeleton".to_string(), Value::Short(1));
+    assert!(nbt::compound_as_map(&counts, Value::as_i32).is_none());
+
+    assert!(nbt::compound_as_map(&Compound::new(), Value::as_i32).unwrap().is_empty());
+}
+
+
+#[test]
+fn test_walk() {
+    let root = nbt::snbt::parse_snbt(
+        r#"{xPos: 3, block_entities: [{id: "minecraft:chest", x: 50, y: 64, z: -3},
+            {id: "minecraft:sign", x: 51, y: 65, z: -4}], Heights: [[1s, 2s]]}"#,
+    ).unwrap();
+
+    let mut visited = Vec::new();
+    root.walk(&mut |path, value| visited.push((path.to_string(), value.tag_type())));
+    visited.sort();
+    let expected = [
+        ("", nbt::TAG_COMPOUND),
+        ("Heights", nbt::TAG_LIST),
+        ("Heights.0", nbt::TAG_LIST),
+        ("Heights.0.0", nbt::TAG_SHORT),
+        ("Heights.0.1", nbt::TAG_SHORT),
+        ("block_entities", nbt::TAG_LIST),
+        ("block_entities.0", nbt::TAG_COMPOUND),
+        ("block_entities.0.id", nbt::TAG_STRING),
+        ("block_entities.0.x", nbt::TAG_INT),
+        ("block_entities.0.y", nbt::TAG_INT),
+        ("block_entities.0.z", nbt::TAG_INT),
+        ("block_entities.1", nbt::TAG_COMPOUND),
+        ("block_entities.1.id", nbt::TAG_STRING),
+        ("block_entities.1.x", nbt::TAG_INT),
+        ("block_entities.1.y", nbt::TAG_INT),
+        ("block_entities.1.z", nbt::TAG_INT),
+        ("xPos", nbt::TAG_INT),
+    ];
+    let expected = expected.iter().map(|(p, t)| (p.to_string(), *t)).collect::<Vec<_>>();
+    assert_eq!(expected, visited);
+
+    // Every block entity's position.
+    let mut positions = Vec::new();
+    root.walk(&mut |_, value| {
+        if let Some(compound) = value.as_compound() {
+            let coord = |key| compound.get(key).and_then(Value::as_i32);
+            if let (Some(x), Some(y), Some(z)) = (coord("x"), coord("y"), coord("z")) {
+                positions.push((x, y, z));
+            }
+        }
+    });
+    positions.sort();
+    assert_eq!(vec![(50, 64, -3), (51, 65, -4)], positions);
+}
+
+
+#[test]
+fn test_compound_entries() {
+    let mut compound = Compound::new();
+    compound.insert("a".to_string(), Value::Double(1.5));
+    compound.insert("b".to_string(), Value::Float(2.5));
+    compound.insert("c".to_string(), Value::Double(-3.0));
+    compound.insert("d".to_string(), Value::Int(4));
+
+    let mut doubles = nbt::compound_doubles(&compound).collect::<Vec<_>>();
+    doubles.sort_by(|a, b| a.0.cmp(b.0));
+    assert_eq!(vec![("a", 1.5), ("c", -3.0)], doubles);
+
+    let ints = nbt::compound_entries(&compound, Value::as_i32).collect::<Vec<_>>();
+    assert_eq!(vec![("d", 4)], ints);
+}
+
+
+#[test]
+fn test_display() {
+    let mut compound = Compound::new();
+    compound.insert("name".to_string(), Value::String("Bananrama".to_string()));
+    assert_eq!(r#"{name:"Bananrama"}"#, Value::Compound(compound).to_string());
+
+    assert_eq!("[I;1,2]", Value::IntArray(vec![1, 2]).to_string());
+    let lists = Value::List(List::List(vec![List::Byte(vec![1]), List::Empty(0)]));
+    assert_eq!("[[1b],[]]", lists.to_string());
+    let quoted = Value::String(r#"say "hi" 'there'"#.to_string());
+    assert_eq!(r#""say \"hi\" 'there'""#, format!("{}", quoted));
+}
+
+
+#[test]
+fn test_list_values() {
+    let list = List::Short(vec![1, 2]);
+    assert_eq!(2, list.len());
+    assert_eq!(nbt::TAG_SHORT, list.element_tag_type());
+    assert_eq!(vec![Value::Short(1), Value::Short(2)], list.iter_values().collect::<Vec<_>>());
+    assert_eq!(vec![Value::Short(1), Value::Short(2)], list.clone().into_values());
+    assert_eq!(Some(list.clone()), List::from_values(list.into_values()));
+
+    let compounds = numbered_compounds(3);
+    assert_eq!(3, compounds.len());
+    assert_eq!(nbt::TAG_COMPOUND, compounds.element_tag_type());
+    assert_eq!(compounds.iter_values().collect::<Vec<_>>(), compounds.into_values());
+}
+
+
+#[test]
+fn test_empty_list_values() {
+    let list = List::Empty(nbt::TAG_STRING);
+    assert!(list.is_empty());
+    assert_eq!(nbt::TAG_STRING, list.element_tag_type());
+    assert_eq!(0, list.iter_values().count());
+    assert!(list.into_values().is_empty());
+}
+
+
+#[test]
+fn test_compound_ext() {
+    let level = nbt::snbt::parse_snbt("{LevelName:\"Old\",DataVersion:3465}").unwrap();
+    let mut level = match level {
+        Value::Compound(compound) => compound,
+        _ => panic!("Not a compound?"),
+    };
+    level.set_string("LevelName", "Renamed");
+    *level.get_i32_mut("DataVersion").unwrap() += 1;
+    level.set_bool("hardcore", true);
+    level.set_list("ServerBrands", List::String(vec!["vanilla".to_string()]));
+    level.get_list_mut("ServerBrands").unwrap();
+    level.get_string_mut("LevelName").unwrap().push('!');
+
+    let expected = nbt::snbt::parse_snbt(
+        "{LevelName:\"Renamed!\",DataVersion:3466,hardcore:1b,ServerBrands:[\"vanilla\"]}");
+    assert_eq!(expected.unwrap(), Value::Compound(level.clone()));
+
+    // Only entries of exactly the right type.
+    assert_eq!(None, level.get_i64_mut("DataVersion"));
+    assert_eq!(None, level.get_i32_mut("missing"));
+    level.set_long("DataVersion", 1);
+    assert_eq!(Some(&mut 1), level.get_i64_mut("DataVersion"));
+}
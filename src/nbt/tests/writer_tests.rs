@@ -0,0 +1,150 @@
+use std::io::Cursor;
+
+use flate2::read::GzDecoder;
+
+use crate::nbt::{reader, tape, writer};
+use crate::nbt::{Compound, List, RootValue, Value};
+
+
+const HELLO_WORLD: &[u8] = include_bytes!("hello_world.nbt");
+const BIGTEST: &[u8] = include_bytes!("bigtest.nbt");
+
+
+fn write(root: &RootValue) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    match writer::write_nbt_stream(&mut bytes, root) {
+        Ok(()) => bytes,
+        Err(err) => panic!("{:?}", err),
+    }
+}
+
+
+#[test]
+fn test_writer_hello_world_round_trip() {
+    let root = reader::parse_nbt_stream(&mut Cursor::new(HELLO_WORLD)).unwrap();
+    assert_eq!(HELLO_WORLD, &write(&root)[..]);
+}
+
+
+#[test]
+fn test_writer_bigtest_round_trip() {
+    // Compound entry order isn't preserved, so compare the trees rather than
+    // the bytes.
+    let root = reader::parse_nbt_stream(&mut GzDecoder::new(BIGTEST)).unwrap();
+    let bytes = write(&root);
+    let reread = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(root, reread);
+}
+
+
+#[test]
+fn test_writer_empty_list() {
+    let root = RootValue {
+        name: "e".to_string(),
+        value: Value::List(List::Empty(0)),
+    };
+    assert_eq!(vec![9u8, 0, 1, b'e', 0, 0, 0, 0, 0], write(&root));
+
+    // The element type of an empty list survives a round trip.
+    let bytes = vec![9u8, 0, 1, b'e', 10, 0, 0, 0, 0];
+    let root = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+    match root.value {
+        Value::List(List::Empty(10)) => (),
+        ref other => panic!("Expected an empty compound list, got {:?}", other),
+    };
+    assert_eq!(bytes, write(&root));
+}
+
+
+#[test]
+fn test_writer_string_too_long() {
+    let root = RootValue {
+        name: String::new(),
+        value: Value::String("x".repeat(70000)),
+    };
+    match writer::write_nbt_stream(&mut Vec::new(), &root) {
+        Err(writer::NbtWriteError::StringTooLong(70000)) => (),
+        other => panic!("Expected StringTooLong, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_writer_sorted_keys() {
+    let mut inner = Compound::new();
+    for key in &["zeta", "Alpha", "mid"] {
+        inner.insert(key.to_string(), Value::Byte(1));
+    }
+    let mut outer = Compound::new();
+    for key in &["b", "c", "a"] {
+        outer.insert(key.to_string(), Value::Byte(0));
+    }
+    outer.insert("inner".to_string(), Value::Compound(inner));
+    let root = RootValue { name: String::new(), value: Value::Compound(outer) };
+
+    let options = writer::WriterOptions { key_order: writer::KeyOrder::Sorted };
+    let mut bytes = Vec::new();
+    writer::write_nbt_stream_with_options(&mut bytes, &root, &options).unwrap();
+
+    let keys = tape::tag_events(&bytes[..])
+        .map(|event| event.unwrap())
+        .filter_map(|event| event.name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["", "a", "b", "c", "inner", "Alpha", "mid", "zeta"],
+        keys,
+    );
+}
+
+
+/// The order `KeyOrder::Vanilla` writes a compound with these keys in.
+fn vanilla_key_order(keys: &[&str]) -> Vec<String> {
+    let compound = keys.iter().map(|key| (key.to_string(), Value::Byte(0))).collect();
+    let root = RootValue { name: String::new(), value: Value::Compound(compound) };
+    let options = writer::WriterOptions { key_order: writer::KeyOrder::Vanilla };
+    let mut bytes = Vec::new();
+    writer::write_nbt_stream_with_options(&mut bytes, &root, &options).unwrap();
+    tape::tag_events(&bytes[..])
+        .map(|event| event.unwrap())
+        .filter_map(|event| event.name)
+        .skip(1)
+        .collect()
+}
+
+
+#[test]
+fn test_writer_vanilla_keys() {
+    // Java's String.hashCode for a one character key is the character, so
+    // "A" (65) and "q" (113) share bucket 1 of 16, ahead of "b" (98).
+    assert_eq!(vec!["A", "q", "b"], vanilla_key_order(&["b", "q", "A"]));
+    // "P" (80) is in bucket 0 of 16, but 16 of 32 once a 13th key grows
+    // the table.
+    let mut keys = vec!["P", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"];
+    assert_eq!(keys, vanilla_key_order(&keys));
+    keys.remove(0);
+    keys.extend(&["l", "P"]);
+    assert_eq!(keys, vanilla_key_order(&keys));
+    assert_eq!(
+        vec!["Status", "zPos", "Level", "DataVersion", "xPos", "sections"],
+        vanilla_key_order(&["DataVersion", "Level", "Status", "sections", "xPos", "zPos"]),
+    );
+}
+
+
+#[test]
+fn test_writer_modified_utf8() {
+    let root = RootValue {
+        name: String::new(),
+        value: Value::String("\0\u{1f600}".to_string()),
+    };
+    let bytes = write(&root);
+    assert_eq!(
+        vec![8u8, 0, 0, 0, 8, 0xc0, 0x80, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80],
+        bytes,
+    );
+    let reread = reader::parse_nbt_stream(&mut Cursor::new(&bytes[..])).unwrap();
+    match reread.value {
+        Value::String(s) => assert_eq!("\0\u{1f600}", s),
+        other => panic!("Expected a string, got {:?}", other),
+    };
+}
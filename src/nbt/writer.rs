@@ -0,0 +1,266 @@
+extern crate byteorder;
+
+use std::convert::From;
+use std::io;
+use std::io::Write;
+
+use self::byteorder::{BigEndian, WriteBytesExt};
+
+use super::TAG_END;
+use super::{Value, RootValue, Compound, List};
+
+
+#[derive(Debug)]
+pub enum NbtWriteError {
+    IoError(io::Error),
+    /// A string's encoding was longer than its 16 bit length prefix allows.
+    StringTooLong(usize),
+    /// A list or array had more elements than its 32 bit length prefix
+    /// allows.
+    ArrayTooLong(usize),
+}
+
+
+impl From<io::Error> for NbtWriteError {
+    fn from(err: io::Error) -> NbtWriteError {
+        NbtWriteError::IoError(err)
+    }
+}
+
+
+/// The order compound entries are written in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyOrder {
+    /// The `Compound`'s own iteration order. Since a `Compound` is a
+    /// `HashMap`, this is arbitrary and may differ between runs.
+    AsStored,
+    /// Sorted by key, by byte value. Output is deterministic, which makes
+    /// written files easy to diff.
+    Sorted,
+    /// The order the game writes, so that rewritten files diff cleanly
+    /// against ones it saved: the iteration order of a Java `HashMap`
+    /// holding the keys, which is what the game's compounds are. This is a
+    /// best effort; keys that share a bucket are written sorted, where the
+    /// game writes them in the order they were inserted.
+    Vanilla,
+}
+
+
+/// Where Java's `HashMap` puts `key` in a table of `capacity` buckets: its
+/// `String.hashCode`, with the high half folded into the low.
+fn java_hash_bucket(key: &str, capacity: usize) -> usize {
+    let hash = key.encode_utf16().fold(0i32, |hash, unit| {
+        hash.wrapping_mul(31).wrapping_add(i32::from(unit))
+    }) as u32;
+    (hash ^ (hash >> 16)) as usize & (capacity - 1)
+}
+
+
+/// The table size of a Java `HashMap` after `len` insertions: 16 buckets,
+/// doubling whenever it becomes more than three quarters full.
+fn java_hash_capacity(len: usize) -> usize {
+    let mut capacity = 16;
+    while len > capacity / 4 * 3 {
+        capacity *= 2;
+    }
+    capacity
+}
+
+
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    pub key_order: KeyOrder,
+}
+
+
+impl Default for WriterOptions {
+    fn default() -> WriterOptions {
+        WriterOptions {
+            key_order: KeyOrder::AsStored,
+        }
+    }
+}
+
+
+fn write_length(writer: &mut dyn Write, length: usize) -> Result<(), NbtWriteError> {
+    // The NBT standard says these lengths are a TAG_Int; the reader treats
+    // them as unsigned, but anything past i32::MAX wouldn't be read back by
+    // other implementations.
+    if length > i32::MAX as usize {
+        return Err(NbtWriteError::ArrayTooLong(length));
+    }
+    writer.write_u32::<BigEndian>(length as u32)?;
+    Ok(())
+}
+
+
+/// Encode `s` as Java's "modified UTF-8"; see `reader::decode_modified_utf8`.
+pub(super) fn encode_modified_utf8(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len());
+    for unit in s.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            },
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            },
+        }
+    }
+    bytes
+}
+
+
+fn write_nbt_string(writer: &mut dyn Write, s: &str) -> Result<(), NbtWriteError> {
+    let bytes = encode_modified_utf8(s);
+    if bytes.len() > u16::MAX as usize {
+        return Err(NbtWriteError::StringTooLong(bytes.len()));
+    }
+    writer.write_u16::<BigEndian>(bytes.len() as u16)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+
+fn write_nbt_byte_array(writer: &mut dyn Write, bytes: &[u8]) -> Result<(), NbtWriteError> {
+    write_length(writer, bytes.len())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+
+fn write_nbt_int_array(writer: &mut dyn Write, ints: &[i32]) -> Result<(), NbtWriteError> {
+    write_length(writer, ints.len())?;
+    for i in ints {
+        writer.write_i32::<BigEndian>(*i)?;
+    }
+    Ok(())
+}
+
+
+fn write_nbt_long_array(writer: &mut dyn Write, longs: &[i64]) -> Result<(), NbtWriteError> {
+    write_length(writer, longs.len())?;
+    for l in longs {
+        writer.write_i64::<BigEndian>(*l)?;
+    }
+    Ok(())
+}
+
+
+fn write_compound(writer: &mut dyn Write, compound: &Compound, options: &WriterOptions)
+        -> Result<(), NbtWriteError> {
+    let mut entries = compound.iter().collect::<Vec<_>>();
+    match options.key_order {
+        KeyOrder::AsStored => (),
+        KeyOrder::Sorted => entries.sort_by(|a, b| a.0.cmp(b.0)),
+        KeyOrder::Vanilla => {
+            let capacity = java_hash_capacity(entries.len());
+            entries.sort_by_cached_key(|(name, _)| (java_hash_bucket(name, capacity), *name));
+        },
+    }
+    for (name, value) in entries {
+        writer.write_u8(value.tag_type())?;
+        write_nbt_string(writer, name)?;
+        write_value(writer, value, options)?;
+    }
+    writer.write_u8(TAG_END)?;
+    Ok(())
+}
+
+
+macro_rules! write_list_items {
+    ($writer:ident, $items:ident, |$item:ident| $write:expr) => ({
+        write_length($writer, $items.len())?;
+        for $item in $items {
+            $write;
+        }
+    });
+}
+
+
+fn write_list(writer: &mut dyn Write, list: &List, options: &WriterOptions)
+        -> Result<(), NbtWriteError> {
+    writer.write_u8(list.element_tag_type())?;
+    match list {
+        List::Empty(_) => write_length(writer, 0)?,
+        List::Byte(items) => write_list_items!(writer, items, |i| writer.write_i8(*i)?),
+        List::Short(items) => write_list_items!(
+            writer, items, |i| writer.write_i16::<BigEndian>(*i)?
+        ),
+        List::Int(items) => write_list_items!(
+            writer, items, |i| writer.write_i32::<BigEndian>(*i)?
+        ),
+        List::Long(items) => write_list_items!(
+            writer, items, |i| writer.write_i64::<BigEndian>(*i)?
+        ),
+        List::Float(items) => write_list_items!(
+            writer, items, |i| writer.write_f32::<BigEndian>(*i)?
+        ),
+        List::Double(items) => write_list_items!(
+            writer, items, |i| writer.write_f64::<BigEndian>(*i)?
+        ),
+        List::ByteArray(items) => write_list_items!(
+            writer, items, |i| write_nbt_byte_array(writer, i)?
+        ),
+        List::String(items) => write_list_items!(
+            writer, items, |i| write_nbt_string(writer, i)?
+        ),
+        List::List(items) => write_list_items!(
+            writer, items, |i| write_list(writer, i, options)?
+        ),
+        List::Compound(items) => write_list_items!(
+            writer, items, |i| write_compound(writer, i, options)?
+        ),
+        List::IntArray(items) => write_list_items!(
+            writer, items, |i| write_nbt_int_array(writer, i)?
+        ),
+        List::LongArray(items) => write_list_items!(
+            writer, items, |i| write_nbt_long_array(writer, i)?
+        ),
+    }
+    Ok(())
+}
+
+
+fn write_value(writer: &mut dyn Write, value: &Value, options: &WriterOptions)
+        -> Result<(), NbtWriteError> {
+    match value {
+        Value::Byte(v) => writer.write_i8(*v)?,
+        Value::Short(v) => writer.write_i16::<BigEndian>(*v)?,
+        Value::Int(v) => writer.write_i32::<BigEndian>(*v)?,
+        Value::Long(v) => writer.write_i64::<BigEndian>(*v)?,
+        Value::Float(v) => writer.write_f32::<BigEndian>(*v)?,
+        Value::Double(v) => writer.write_f64::<BigEndian>(*v)?,
+        Value::ByteArray(v) => write_nbt_byte_array(writer, v)?,
+        Value::String(v) => write_nbt_string(writer, v)?,
+        Value::List(v) => write_list(writer, v, options)?,
+        Value::Compound(v) => write_compound(writer, v, options)?,
+        Value::IntArray(v) => write_nbt_int_array(writer, v)?,
+        Value::LongArray(v) => write_nbt_long_array(writer, v)?,
+    }
+    Ok(())
+}
+
+
+/// Write `root` as an (uncompressed) NBT stream; the inverse of
+/// `reader::parse_nbt_stream`.
+///
+/// Compound entries are written in the `Compound`'s iteration order.
+pub fn write_nbt_stream<W: Write>(writer: &mut W, root: &RootValue)
+        -> Result<(), NbtWriteError> {
+    write_nbt_stream_with_options(writer, root, &WriterOptions::default())
+}
+
+
+/// Like `write_nbt_stream`, but configurable.
+pub fn write_nbt_stream_with_options<W: Write>(
+        writer: &mut W, root: &RootValue, options: &WriterOptions)
+        -> Result<(), NbtWriteError> {
+    writer.write_u8(root.value.tag_type())?;
+    write_nbt_string(writer, &root.name)?;
+    write_value(writer, &root.value, options)
+}
@@ -0,0 +1,5 @@
+//! Pieces of the Minecraft network protocol.
+
+pub mod varint;
+#[cfg(test)]
+mod tests;
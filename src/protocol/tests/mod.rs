@@ -0,0 +1 @@
+mod varint_tests;
@@ -0,0 +1,170 @@
+use std::io::Cursor;
+
+use crate::protocol::varint::{self, VarIntError};
+
+
+const VAR_INTS: &[(i32, &[u8])] = &[
+    (0, &[0x00]),
+    (1, &[0x01]),
+    (127, &[0x7f]),
+    (128, &[0x80, 0x01]),
+    (255, &[0xff, 0x01]),
+    (25565, &[0xdd, 0xc7, 0x01]),
+    (2097151, &[0xff, 0xff, 0x7f]),
+    (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+    (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+    (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+];
+
+
+const VAR_LONGS: &[(i64, &[u8])] = &[
+    (0, &[0x00]),
+    (128, &[0x80, 0x01]),
+    (i32::MAX as i64, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+    (i64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+    (-1, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    (i64::MIN, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+];
+
+
+const ZIGZAG_VAR_INTS: &[(i32, &[u8])] = &[
+    (0, &[0x00]),
+    (-1, &[0x01]),
+    (1, &[0x02]),
+    (-64, &[0x7f]),
+    (64, &[0x80, 0x01]),
+    (i32::MAX, &[0xfe, 0xff, 0xff, 0xff, 0x0f]),
+    (i32::MIN, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+];
+
+
+const ZIGZAG_VAR_LONGS: &[(i64, &[u8])] = &[
+    (0, &[0x00]),
+    (-1, &[0x01]),
+    (300, &[0xd8, 0x04]),
+    (i64::MAX, &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    (i64::MIN, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+];
+
+
+#[test]
+fn test_var_int() {
+    for &(value, bytes) in VAR_INTS {
+        let mut written = Vec::new();
+        varint::write_var_int(&mut written, value).unwrap();
+        assert_eq!(bytes, &written[..]);
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_var_int(&mut reader).unwrap());
+        assert_eq!(bytes.len() as u64, reader.position());
+    }
+}
+
+
+#[test]
+fn test_var_long() {
+    for &(value, bytes) in VAR_LONGS {
+        let mut written = Vec::new();
+        varint::write_var_long(&mut written, value).unwrap();
+        assert_eq!(bytes, &written[..]);
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_var_long(&mut reader).unwrap());
+        assert_eq!(bytes.len() as u64, reader.position());
+    }
+}
+
+
+#[test]
+fn test_zigzag_var_int() {
+    for &(value, bytes) in ZIGZAG_VAR_INTS {
+        let mut written = Vec::new();
+        varint::write_zigzag_var_int(&mut written, value).unwrap();
+        assert_eq!(bytes, &written[..]);
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_zigzag_var_int(&mut reader).unwrap());
+        assert_eq!(bytes.len() as u64, reader.position());
+    }
+    match varint::read_zigzag_var_int(&mut Cursor::new([0xff; 6])) {
+        Err(VarIntError::TooLong) => (),
+        other => panic!("Expected TooLong, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_zigzag_var_long() {
+    for &(value, bytes) in ZIGZAG_VAR_LONGS {
+        let mut written = Vec::new();
+        varint::write_zigzag_var_long(&mut written, value).unwrap();
+        assert_eq!(bytes, &written[..]);
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_zigzag_var_long(&mut reader).unwrap());
+        assert_eq!(bytes.len() as u64, reader.position());
+    }
+}
+
+
+#[test]
+fn test_var_int_too_long() {
+    match varint::read_var_int(&mut Cursor::new([0x80, 0x80, 0x80, 0x80, 0x80, 0x01])) {
+        Err(VarIntError::TooLong) => (),
+        other => panic!("Expected TooLong, got {:?}", other),
+    }
+    match varint::read_var_long(&mut Cursor::new([0x80; 11])) {
+        Err(VarIntError::TooLong) => (),
+        other => panic!("Expected TooLong, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_var_int_eof() {
+    match varint::read_var_int(&mut Cursor::new([0x80, 0x80])) {
+        Err(VarIntError::IoError(_)) => (),
+        other => panic!("Expected IoError, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_minimal_var_int() {
+    for &(value, bytes) in VAR_INTS {
+        assert_eq!(value, varint::read_minimal_var_int(&mut Cursor::new(bytes)).unwrap());
+    }
+    for &(value, bytes) in VAR_LONGS {
+        assert_eq!(value, varint::read_minimal_var_long(&mut Cursor::new(bytes)).unwrap());
+    }
+    for &(value, bytes) in ZIGZAG_VAR_INTS {
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_minimal_zigzag_var_int(&mut reader).unwrap());
+    }
+    for &(value, bytes) in ZIGZAG_VAR_LONGS {
+        let mut reader = Cursor::new(bytes);
+        assert_eq!(value, varint::read_minimal_zigzag_var_long(&mut reader).unwrap());
+    }
+}
+
+
+#[test]
+fn test_non_minimal_var_int() {
+    // 1 with a trailing zero group, and -1 with bits past 32 set. The
+    // lenient readers take both.
+    let padded: &[u8] = &[0x81, 0x00];
+    let wide: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x7f];
+    assert_eq!(1, varint::read_var_int(&mut Cursor::new(padded)).unwrap());
+    assert_eq!(-1, varint::read_var_int(&mut Cursor::new(wide)).unwrap());
+    for bytes in &[padded, wide] {
+        match varint::read_minimal_var_int(&mut Cursor::new(bytes)) {
+            Err(VarIntError::NonMinimal) => (),
+            other => panic!("Expected NonMinimal, got {:?}", other),
+        }
+    }
+    let wide_long = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03];
+    match varint::read_minimal_var_long(&mut Cursor::new(wide_long)) {
+        Err(VarIntError::NonMinimal) => (),
+        other => panic!("Expected NonMinimal, got {:?}", other),
+    }
+    match varint::read_minimal_zigzag_var_long(&mut Cursor::new([0x80, 0x80, 0x00])) {
+        Err(VarIntError::NonMinimal) => (),
+        other => panic!("Expected NonMinimal, got {:?}", other),
+    }
+}
@@ -0,0 +1,159 @@
+//! VarInts and VarLongs: the protocol's variable-length integers, as used
+//! for packet lengths and ids among other things.
+//!
+//! Numbers are encoded seven bits to a byte, least significant first, with
+//! the high bit of each byte set if another byte follows. Negative numbers
+//! are encoded as their two's complement, so always take the maximum length:
+//! 5 bytes for a VarInt and 10 for a VarLong.
+//!
+//! Bedrock Edition also uses zig-zag encoded VarInts, which interleave
+//! negative and positive numbers (0, -1, 1, -2, …) so that small negative
+//! numbers stay short.
+
+use std::convert::From;
+use std::io;
+use std::io::{Read, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+
+
+const MAX_VAR_INT_BYTES: u32 = 5;
+const MAX_VAR_LONG_BYTES: u32 = 10;
+
+
+#[derive(Debug)]
+pub enum VarIntError {
+    IoError(io::Error),
+    /// The encoding went on past 5 bytes for a VarInt, or 10 for a VarLong.
+    TooLong,
+    /// The encoding wasn't the shortest one for its number: it ended in a
+    /// zero byte, or set bits past the width of the number. Only returned
+    /// by the `read_minimal_*` functions.
+    NonMinimal,
+}
+
+
+impl From<io::Error> for VarIntError {
+    fn from(err: io::Error) -> VarIntError {
+        VarIntError::IoError(err)
+    }
+}
+
+
+/// Read up to `max_bytes` seven-bit groups of a number `bits` wide. Bits
+/// past the width of the number are dropped, as the game does, unless
+/// `minimal` is set, when they and any other overlong encoding are errors.
+fn read_var<R: Read + ?Sized>(reader: &mut R, max_bytes: u32, bits: u32, minimal: bool)
+        -> Result<u64, VarIntError> {
+    let mut value = 0u64;
+    for i in 0..max_bytes {
+        let byte = reader.read_u8()?;
+        let group = byte & 0x7f;
+        if minimal && 7 * (i + 1) > bits && group >> (bits - 7 * i) != 0 {
+            return Err(VarIntError::NonMinimal);
+        }
+        value |= u64::from(group) << (7 * i);
+        if byte & 0x80 == 0 {
+            if minimal && i > 0 && byte == 0 {
+                return Err(VarIntError::NonMinimal);
+            }
+            return Ok(value);
+        }
+    }
+    Err(VarIntError::TooLong)
+}
+
+
+fn zigzag_32(value: u64) -> i32 {
+    let value = value as u32;
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+
+fn zigzag_64(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+
+pub fn read_var_int<R: Read + ?Sized>(reader: &mut R) -> Result<i32, VarIntError> {
+    read_var(reader, MAX_VAR_INT_BYTES, 32, false).map(|value| value as u32 as i32)
+}
+
+
+pub fn read_var_long<R: Read + ?Sized>(reader: &mut R) -> Result<i64, VarIntError> {
+    read_var(reader, MAX_VAR_LONG_BYTES, 64, false).map(|value| value as i64)
+}
+
+
+/// Read a zig-zag encoded VarInt.
+pub fn read_zigzag_var_int<R: Read + ?Sized>(reader: &mut R) -> Result<i32, VarIntError> {
+    read_var(reader, MAX_VAR_INT_BYTES, 32, false).map(zigzag_32)
+}
+
+
+/// Read a zig-zag encoded VarLong.
+pub fn read_zigzag_var_long<R: Read + ?Sized>(reader: &mut R) -> Result<i64, VarIntError> {
+    read_var(reader, MAX_VAR_LONG_BYTES, 64, false).map(zigzag_64)
+}
+
+
+/// Like `read_var_int`, but fails with `VarIntError::NonMinimal` unless
+/// the VarInt is encoded as `write_var_int` would have.
+pub fn read_minimal_var_int<R: Read + ?Sized>(reader: &mut R) -> Result<i32, VarIntError> {
+    read_var(reader, MAX_VAR_INT_BYTES, 32, true).map(|value| value as u32 as i32)
+}
+
+
+/// Like `read_var_long`, but fails with `VarIntError::NonMinimal` unless
+/// the VarLong is encoded as `write_var_long` would have.
+pub fn read_minimal_var_long<R: Read + ?Sized>(reader: &mut R) -> Result<i64, VarIntError> {
+    read_var(reader, MAX_VAR_LONG_BYTES, 64, true).map(|value| value as i64)
+}
+
+
+/// Like `read_zigzag_var_int`, but fails with `VarIntError::NonMinimal`
+/// unless the VarInt is encoded as `write_zigzag_var_int` would have.
+pub fn read_minimal_zigzag_var_int<R: Read + ?Sized>(reader: &mut R)
+        -> Result<i32, VarIntError> {
+    read_var(reader, MAX_VAR_INT_BYTES, 32, true).map(zigzag_32)
+}
+
+
+/// Like `read_zigzag_var_long`, but fails with `VarIntError::NonMinimal`
+/// unless the VarLong is encoded as `write_zigzag_var_long` would have.
+pub fn read_minimal_zigzag_var_long<R: Read + ?Sized>(reader: &mut R)
+        -> Result<i64, VarIntError> {
+    read_var(reader, MAX_VAR_LONG_BYTES, 64, true).map(zigzag_64)
+}
+
+
+fn write_var<W: Write + ?Sized>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_u8(byte);
+        }
+        writer.write_u8(byte | 0x80)?;
+    }
+}
+
+
+pub fn write_var_int<W: Write + ?Sized>(writer: &mut W, value: i32) -> io::Result<()> {
+    write_var(writer, u64::from(value as u32))
+}
+
+
+pub fn write_var_long<W: Write + ?Sized>(writer: &mut W, value: i64) -> io::Result<()> {
+    write_var(writer, value as u64)
+}
+
+
+pub fn write_zigzag_var_int<W: Write + ?Sized>(writer: &mut W, value: i32) -> io::Result<()> {
+    write_var(writer, u64::from(((value << 1) ^ (value >> 31)) as u32))
+}
+
+
+pub fn write_zigzag_var_long<W: Write + ?Sized>(writer: &mut W, value: i64) -> io::Result<()> {
+    write_var(writer, ((value << 1) ^ (value >> 63)) as u64)
+}
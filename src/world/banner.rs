@@ -0,0 +1,104 @@
+//! Banner patterns, as stored on banner block entities and banner (and
+//! shield) items.
+
+use crate::nbt::{Compound, Value};
+
+use super::{get_compound_list, get_int, get_string};
+
+
+/// The sixteen dye colors, in the game's numbering.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DyeColor {
+    White,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+}
+
+
+const DYE_COLORS: [(DyeColor, &str); 16] = [
+    (DyeColor::White, "white"),
+    (DyeColor::Orange, "orange"),
+    (DyeColor::Magenta, "magenta"),
+    (DyeColor::LightBlue, "light_blue"),
+    (DyeColor::Yellow, "yellow"),
+    (DyeColor::Lime, "lime"),
+    (DyeColor::Pink, "pink"),
+    (DyeColor::Gray, "gray"),
+    (DyeColor::LightGray, "light_gray"),
+    (DyeColor::Cyan, "cyan"),
+    (DyeColor::Purple, "purple"),
+    (DyeColor::Blue, "blue"),
+    (DyeColor::Brown, "brown"),
+    (DyeColor::Green, "green"),
+    (DyeColor::Red, "red"),
+    (DyeColor::Black, "black"),
+];
+
+
+impl DyeColor {
+    /// Decode the 0–15 numbering used before 1.20.5.
+    pub fn from_id(id: i32) -> Option<DyeColor> {
+        if id < 0 {
+            return None;
+        }
+        DYE_COLORS.get(id as usize).map(|(color, _)| *color)
+    }
+
+    /// Decode a color name, e.g. `"light_blue"`, as used since 1.20.5.
+    pub fn from_name(name: &str) -> Option<DyeColor> {
+        DYE_COLORS.iter().find(|(_, n)| *n == name).map(|(color, _)| *color)
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BannerPattern {
+    /// Before 1.20.5, a short code such as `"bs"`; since, a namespaced id
+    /// such as `"minecraft:stripe_bottom"`.
+    pub pattern: String,
+    pub color: DyeColor,
+}
+
+
+fn read_pattern(layer: &Compound) -> Option<BannerPattern> {
+    if let Some(pattern) = get_string(layer, "Pattern") {
+        return Some(BannerPattern {
+            pattern: pattern.to_string(),
+            color: DyeColor::from_id(get_int(layer, "Color")?)?,
+        });
+    }
+    let pattern = match layer.get("pattern")? {
+        Value::String(pattern) => pattern.clone(),
+        // A pattern defined inline, rather than by reference.
+        Value::Compound(pattern) => get_string(pattern, "asset_id")?.to_string(),
+        _ => return None,
+    };
+    Some(BannerPattern {
+        pattern,
+        color: DyeColor::from_name(get_string(layer, "color")?)?,
+    })
+}
+
+
+/// Read a banner's pattern layers, bottom first: `patterns` since 1.20.5,
+/// and `Patterns` before. `compound` is a banner block entity, or the
+/// `BlockEntityTag` of a banner item.
+pub fn read_banner_patterns(compound: &Compound) -> Vec<BannerPattern> {
+    ["patterns", "Patterns"].iter()
+        .flat_map(|key| get_compound_list(compound, key))
+        .filter_map(read_pattern)
+        .collect()
+}
@@ -0,0 +1,158 @@
+//! Chunk compounds, as stored in a world's `region/` files.
+
+use crate::nbt::{Compound, RootValue, Value, TAG_LONG_ARRAY};
+
+use super::{
+    BlockPos, WorldError, get_compound, get_compound_list, get_int, get_list, get_string,
+};
+
+
+/// The compound holding a chunk's data. Before 1.18 everything was wrapped
+/// in a `Level` compound; since, it sits directly in the root.
+fn chunk_data(chunk: &RootValue) -> Option<&Compound> {
+    match chunk.value {
+        Value::Compound(ref root) => Some(get_compound(root, "Level").unwrap_or(root)),
+        _ => None,
+    }
+}
+
+
+fn chunk_data_mut(chunk: &mut RootValue) -> Option<&mut Compound> {
+    let root = match chunk.value {
+        Value::Compound(ref mut root) => root,
+        _ => return None,
+    };
+    if !root.contains_key("Level") {
+        return Some(root);
+    }
+    match root.get_mut("Level") {
+        Some(Value::Compound(level)) => Some(level),
+        _ => None,
+    }
+}
+
+
+/// The chunk's generation status, e.g. `"minecraft:full"` (or, in older
+/// versions, `"postprocessed"`).
+pub fn chunk_status(chunk: &RootValue) -> Option<&str> {
+    get_string(chunk_data(chunk)?, "Status")
+}
+
+
+/// Set the chunk's generation status. Downgrading it (say, to
+/// `"minecraft:empty"`) makes the game regenerate the chunk when it is next
+/// loaded.
+///
+/// Does nothing if the chunk's root isn't a compound.
+pub fn set_chunk_status(chunk: &mut RootValue, status: &str) {
+    if let Some(data) = chunk_data_mut(chunk) {
+        data.insert("Status".to_string(), Value::String(status.to_string()));
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TickKind {
+    Block,
+    Fluid,
+}
+
+
+/// A block or fluid update scheduled for a future tick, e.g. a repeater
+/// about to change state or water about to flow.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScheduledTick {
+    pub kind: TickKind,
+    /// The block or fluid to update, e.g. `"minecraft:repeater"`.
+    pub id: String,
+    pub pos: BlockPos,
+    /// Ticks until the update, relative to when the chunk was saved.
+    pub delay: i32,
+    /// Lower values run first among updates due in the same tick.
+    pub priority: i32,
+}
+
+
+/// Read a chunk's scheduled block and fluid ticks.
+///
+/// Since 1.18 these are the chunk's `block_ticks` and `fluid_ticks`; before
+/// that, `Level.TileTicks` and `Level.LiquidTicks`. Ticks with numeric
+/// (pre-1.13) ids are skipped.
+pub fn read_scheduled_ticks(chunk: &RootValue) -> Vec<ScheduledTick> {
+    let data = match chunk_data(chunk) {
+        Some(data) => data,
+        None => return Vec::new(),
+    };
+    let lists = [
+        (TickKind::Block, "block_ticks"),
+        (TickKind::Block, "TileTicks"),
+        (TickKind::Fluid, "fluid_ticks"),
+        (TickKind::Fluid, "LiquidTicks"),
+    ];
+    lists.iter().flat_map(|&(kind, key)| {
+        get_compound_list(data, key).iter().filter_map(move |tick| {
+            Some(ScheduledTick {
+                kind,
+                id: get_string(tick, "i")?.to_string(),
+                pos: BlockPos {
+                    x: get_int(tick, "x")?,
+                    y: get_int(tick, "y")?,
+                    z: get_int(tick, "z")?,
+                },
+                delay: get_int(tick, "t").unwrap_or(0),
+                priority: get_int(tick, "p").unwrap_or(0),
+            })
+        })
+    }).collect()
+}
+
+
+/// The data version of 20w17a, from which a block index no longer spans two
+/// longs of `BlockStates`: any bits left over at the end of a long are
+/// unused.
+const DATA_VERSION_NON_SPANNING: i32 = 2529;
+
+
+/// Check that a chunk section's packed block states are as long as its
+/// palette size says they should be, for a chunk of `data_version`.
+///
+/// Since 1.18 these are the section's `block_states.palette` and
+/// `block_states.data`, and `data` is left out when the palette has one
+/// entry; before, `Palette` and `BlockStates`. Either way, each block takes
+/// at least 4 bits. A section without a palette has no blocks to check.
+pub fn validate_section_blockstates(section: &Compound, data_version: i32)
+        -> Result<(), WorldError> {
+    let (states, palette_key, data_key) = match get_compound(section, "block_states") {
+        Some(states) => (states, "palette", "data"),
+        None => (section, "Palette", "BlockStates"),
+    };
+    let palette_len = match get_list(states, palette_key) {
+        Some(palette) => palette.len(),
+        None => return Ok(()),
+    };
+    if palette_len == 0 {
+        return Err(WorldError::OutOfRange(palette_key));
+    }
+    let data = match states.get(data_key) {
+        Some(Value::LongArray(data)) => data,
+        Some(other) => {
+            return Err(WorldError::WrongType {
+                key: data_key,
+                expected: TAG_LONG_ARRAY,
+                found: other.tag_type(),
+            });
+        },
+        None if palette_len == 1 && data_key == "data" => return Ok(()),
+        None => return Err(WorldError::Missing(data_key)),
+    };
+    let bits = (usize::BITS - (palette_len - 1).leading_zeros()).max(4) as usize;
+    let expected = if data_version >= DATA_VERSION_NON_SPANNING {
+        4096usize.div_ceil(64 / bits)
+    } else {
+        (4096 * bits).div_ceil(64)
+    };
+    if data.len() != expected {
+        return Err(WorldError::WrongLength { key: data_key, expected, found: data.len() });
+    }
+    Ok(())
+}
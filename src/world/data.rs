@@ -0,0 +1,139 @@
+//! The saved-data files in a world's `data/` directory (`raids.dat`,
+//! `villages.dat`, `scoreboard.dat`, …).
+
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::nbt::{Compound, RootValue, Value};
+use crate::nbt::reader::{self, NbtReadError};
+
+use super::{BlockPos, get_byte, get_compound, get_compound_list, get_int, get_long, get_string};
+
+
+/// Read a world saved-data file. These are normally gzip-compressed, but
+/// uncompressed files are accepted too.
+pub fn read_world_data_file<P: AsRef<Path>>(path: P) -> Result<RootValue, NbtReadError> {
+    reader::parse_nbt_file(BufReader::new(File::open(path)?))
+}
+
+
+/// The saved-data directory for `dimension` (e.g. `"minecraft:the_nether"`;
+/// the namespace defaults to `minecraft`), in the world at `world_root`.
+///
+/// The vanilla dimensions keep their historical `DIM-1` and `DIM1` folders,
+/// and other dimensions are stored under `dimensions/<namespace>/<path>`.
+pub fn dimension_data_dir(world_root: &Path, dimension: &str) -> PathBuf {
+    let (namespace, path) = match dimension.find(':') {
+        Some(colon) => (&dimension[..colon], &dimension[colon + 1..]),
+        None => ("minecraft", dimension),
+    };
+    let dimension_root = match (namespace, path) {
+        ("minecraft", "overworld") => world_root.to_path_buf(),
+        ("minecraft", "the_nether") => world_root.join("DIM-1"),
+        ("minecraft", "the_end") => world_root.join("DIM1"),
+        _ => world_root.join("dimensions").join(namespace).join(path),
+    };
+    dimension_root.join("data")
+}
+
+
+/// Read the saved-data file `filename` (e.g. `"raids_nether.dat"`) of
+/// `dimension`; see `dimension_data_dir`.
+pub fn read_dimension_data_file(world_root: &Path, dimension: &str, filename: &str)
+        -> Result<RootValue, NbtReadError> {
+    read_world_data_file(dimension_data_dir(world_root, dimension).join(filename))
+}
+
+
+/// List the `.dat` files directly inside `dir`, in path order.
+fn dat_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension() == Some("dat".as_ref()) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+
+/// Parse every `.dat` file in `dir` (e.g. a world's `playerdata/`), in path
+/// order. A file failing to parse doesn't stop the others from being read;
+/// only failing to list the directory is an error.
+pub fn parse_dir(dir: &Path)
+        -> io::Result<Vec<(PathBuf, Result<RootValue, NbtReadError>)>> {
+    Ok(dat_files(dir)?.into_iter().map(|path| {
+        let result = read_world_data_file(&path);
+        (path, result)
+    }).collect())
+}
+
+
+/// Like `parse_dir`, but parses the files across rayon's thread pool. The
+/// results are in the same order `parse_dir` would return them.
+#[cfg(feature = "rayon")]
+pub fn parse_dir_parallel(dir: &Path)
+        -> io::Result<Vec<(PathBuf, Result<RootValue, NbtReadError>)>> {
+    Ok(dat_files(dir)?.into_par_iter().map(|path| {
+        let result = read_world_data_file(&path);
+        (path, result)
+    }).collect())
+}
+
+
+/// Saved-data files keep their contents in a `data` compound under the root.
+fn saved_data(root: &RootValue) -> Option<&Compound> {
+    match root.value {
+        Value::Compound(ref root) => get_compound(root, "data"),
+        _ => None,
+    }
+}
+
+
+/// A raid, as stored in `raids.dat`.
+#[derive(Debug)]
+pub struct Raid {
+    pub id: i32,
+    /// The raid's center (`CX`, `CY`, `CZ`).
+    pub center: BlockPos,
+    /// One of `"ongoing"`, `"victory"`, `"loss"` or `"stopped"`.
+    pub status: String,
+    pub started: bool,
+    pub active: bool,
+    pub ticks_active: i64,
+    pub bad_omen_level: i32,
+    pub groups_spawned: i32,
+    pub num_groups: i32,
+}
+
+
+/// Read the raids out of a parsed `raids.dat`.
+pub fn read_raids(root: &RootValue) -> Vec<Raid> {
+    let data = match saved_data(root) {
+        Some(data) => data,
+        None => return Vec::new(),
+    };
+    get_compound_list(data, "Raids").iter().filter_map(|raid| {
+        Some(Raid {
+            id: get_int(raid, "Id")?,
+            center: BlockPos {
+                x: get_int(raid, "CX")?,
+                y: get_int(raid, "CY")?,
+                z: get_int(raid, "CZ")?,
+            },
+            status: get_string(raid, "Status").unwrap_or("ongoing").to_string(),
+            started: get_byte(raid, "Started").unwrap_or(0) != 0,
+            active: get_byte(raid, "Active").unwrap_or(0) != 0,
+            ticks_active: get_long(raid, "TicksActive").unwrap_or(0),
+            bad_omen_level: get_int(raid, "BadOmenLevel").unwrap_or(0),
+            groups_spawned: get_int(raid, "GroupsSpawned").unwrap_or(0),
+            num_groups: get_int(raid, "NumGroups").unwrap_or(0),
+        })
+    }).collect()
+}
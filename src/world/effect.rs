@@ -0,0 +1,74 @@
+//! Status effects, as applied to living entities and carried by potions,
+//! splash potions, tipped arrows and so on.
+
+use crate::nbt::{Compound, Value};
+
+use super::{get_byte, get_compound_list, get_int, get_string};
+
+
+/// Effects were identified by number before 1.20.2, and by namespaced name
+/// (e.g. `"minecraft:speed"`) since.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EffectId {
+    Numeric(i32),
+    Named(String),
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PotionEffect {
+    pub id: EffectId,
+    /// Zero-based, so 0 is level I.
+    pub amplifier: i8,
+    /// In ticks; -1 for an infinite effect.
+    pub duration: i32,
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
+}
+
+
+fn read_effect(effect: &Compound) -> Option<PotionEffect> {
+    let id = match effect.get("id").or_else(|| effect.get("Id")) {
+        Some(Value::String(name)) => EffectId::Named(name.clone()),
+        Some(Value::Byte(number)) => EffectId::Numeric(i32::from(*number)),
+        Some(Value::Int(number)) => EffectId::Numeric(*number),
+        _ => return None,
+    };
+    // The keys were renamed to snake_case along with the switch to named ids.
+    let byte = |key, legacy_key| {
+        get_byte(effect, key).or_else(|| get_byte(effect, legacy_key))
+    };
+    Some(PotionEffect {
+        id,
+        amplifier: byte("amplifier", "Amplifier").unwrap_or(0),
+        duration: get_int(effect, "duration")
+            .or_else(|| get_int(effect, "Duration"))
+            .unwrap_or(0),
+        ambient: byte("ambient", "Ambient").unwrap_or(0) != 0,
+        show_particles: byte("show_particles", "ShowParticles").unwrap_or(1) != 0,
+        show_icon: byte("show_icon", "ShowIcon").unwrap_or(1) != 0,
+    })
+}
+
+
+/// Read the status effects on an entity (`active_effects`, or
+/// `ActiveEffects` before 1.20.2), or the custom effects in a potion item's
+/// `tag` (`custom_potion_effects`, or `CustomPotionEffects`).
+///
+/// A potion's base effects aren't included; they're implied by its
+/// `potion_type`.
+pub fn read_potion_effects(compound: &Compound) -> Vec<PotionEffect> {
+    ["active_effects", "ActiveEffects", "custom_potion_effects", "CustomPotionEffects"]
+        .iter()
+        .flat_map(|key| get_compound_list(compound, key))
+        .filter_map(read_effect)
+        .collect()
+}
+
+
+/// The potion type in a potion item's `tag`, e.g.
+/// `"minecraft:long_swiftness"`.
+pub fn potion_type(item_tag: &Compound) -> Option<&str> {
+    get_string(item_tag, "Potion")
+}
@@ -0,0 +1,98 @@
+//! Entity compounds, as found in a chunk's (or entity file's) `Entities`
+//! list.
+
+use crate::nbt::{Compound, List};
+
+use super::{Direction, get_byte, get_compound, get_compound_list, get_list, get_string};
+
+
+/// The entities riding `entity` directly (`Passengers`).
+pub fn entity_passengers(entity: &Compound) -> Vec<&Compound> {
+    get_compound_list(entity, "Passengers").iter().collect()
+}
+
+
+/// `entity`, followed by everything riding it, however deeply stacked, in
+/// depth-first order.
+pub fn flatten_entity_stack(entity: &Compound) -> Vec<&Compound> {
+    let mut stack = vec![entity];
+    let mut flattened = Vec::new();
+    while let Some(entity) = stack.pop() {
+        flattened.push(entity);
+        stack.extend(get_compound_list(entity, "Passengers").iter().rev());
+    }
+    flattened
+}
+
+
+/// Where a Paper server first spawned the entity (`Paper.Origin`).
+///
+/// Paper and Spigot store their extensions at the top level of the entity,
+/// under keys containing a literal `.`.
+pub fn read_paper_origin(entity: &Compound) -> Option<[f64; 3]> {
+    match get_list(entity, "Paper.Origin") {
+        Some(List::Double(pos)) if pos.len() == 3 => Some([pos[0], pos[1], pos[2]]),
+        _ => None,
+    }
+}
+
+
+/// Why a Paper server spawned the entity (`Paper.SpawnReason`), e.g.
+/// `"NATURAL"` or `"SPAWNER"`.
+pub fn read_paper_spawn_reason(entity: &Compound) -> Option<&str> {
+    get_string(entity, "Paper.SpawnReason")
+}
+
+
+/// Whether a Paper server recorded the entity as coming from a mob spawner
+/// (`Paper.FromMobSpawner`).
+pub fn read_paper_from_mob_spawner(entity: &Compound) -> Option<bool> {
+    get_byte(entity, "Paper.FromMobSpawner").map(|b| b != 0)
+}
+
+
+/// Data attached to the entity by Bukkit plugins (`BukkitValues`), keyed by
+/// namespaced plugin key.
+pub fn read_bukkit_values(entity: &Compound) -> Option<&Compound> {
+    get_compound(entity, "BukkitValues")
+}
+
+
+/// The data version of 1.13, when item frames could first face up or down.
+const DATA_VERSION_1_13: i32 = 1519;
+
+
+/// The direction an item frame faces, i.e. the direction from the block it
+/// hangs on towards the frame. `data_version` is that of the chunk or entity
+/// file holding the frame, or `None` for worlds from before 1.9, which don't
+/// record it.
+///
+/// Since 1.13 frames can face any direction, and `Facing` holds a 3D
+/// direction. Before that they could only hang on walls: from 1.8 `Facing`
+/// holds a horizontal direction, and before 1.8 `Direction` does.
+pub fn item_frame_facing(frame: &Compound, data_version: Option<i32>) -> Option<Direction> {
+    match get_byte(frame, "Facing") {
+        Some(facing) if data_version.is_some_and(|v| v >= DATA_VERSION_1_13) => {
+            Direction::from_3d_value(facing)
+        },
+        Some(facing) => Direction::from_2d_value(facing),
+        None => get_byte(frame, "Direction").and_then(Direction::from_2d_value),
+    }
+}
+
+
+/// The rotation of the item in an item frame, in eighths of a turn
+/// clockwise (0–7).
+pub fn item_frame_rotation(frame: &Compound) -> Option<u8> {
+    match get_byte(frame, "ItemRotation") {
+        Some(rotation) if (0..8).contains(&rotation) => Some(rotation as u8),
+        _ => None,
+    }
+}
+
+
+/// The painting's artwork, e.g. `"minecraft:kebab"`: `variant` since 1.19,
+/// `Motive` before.
+pub fn painting_variant(painting: &Compound) -> Option<&str> {
+    get_string(painting, "variant").or_else(|| get_string(painting, "Motive"))
+}
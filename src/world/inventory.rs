@@ -0,0 +1,128 @@
+//! Item containers: lists of item compounds each tagged with a `Slot`, such
+//! as a player's `Inventory` and `EnderItems`, or a chest's `Items`.
+
+use crate::nbt::{Compound, List, Value, TAG_BYTE, TAG_COMPOUND, TAG_END, TAG_STRING};
+
+use super::{get_byte, get_compound, get_list, get_string, WorldError};
+
+
+/// An item in a container slot.
+#[derive(Debug, Clone)]
+pub struct ItemSlot {
+    pub slot: i8,
+    pub id: String,
+    pub count: i8,
+    /// The item's extra data (enchantments, display name, …), if any.
+    pub tag: Option<Compound>,
+    /// Any other entries in the item compound, e.g. from a newer version,
+    /// kept so that writing the slot back doesn't drop them.
+    pub extra: Compound,
+}
+
+
+const ITEM_SLOT_KEYS: [&str; 4] = ["Slot", "id", "Count", "tag"];
+
+
+/// Read the items in a container list. Entries without a `Slot` or `id` are
+/// skipped.
+pub fn read_item_container(list: &List) -> Vec<ItemSlot> {
+    let items = match list {
+        List::Compound(items) => items,
+        _ => return Vec::new(),
+    };
+    items.iter().filter_map(|item| {
+        Some(ItemSlot {
+            slot: get_byte(item, "Slot")?,
+            id: get_string(item, "id")?.to_string(),
+            count: get_byte(item, "Count").unwrap_or(1),
+            tag: get_compound(item, "tag").cloned(),
+            extra: item.iter()
+                .filter(|(key, _)| !ITEM_SLOT_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        })
+    }).collect()
+}
+
+
+/// Build a container list from `slots`, the inverse of `read_item_container`.
+pub fn write_item_container(slots: &[ItemSlot]) -> List {
+    if slots.is_empty() {
+        return List::Empty(TAG_END);
+    }
+    List::Compound(slots.iter().map(|slot| {
+        let mut item = slot.extra.clone();
+        item.insert("Slot".to_string(), Value::Byte(slot.slot));
+        item.insert("id".to_string(), Value::String(slot.id.clone()));
+        item.insert("Count".to_string(), Value::Byte(slot.count));
+        if let Some(ref tag) = slot.tag {
+            item.insert("tag".to_string(), Value::Compound(tag.clone()));
+        }
+        item
+    }).collect())
+}
+
+
+struct FieldSchema {
+    key: &'static str,
+    tag_type: u8,
+    required: bool,
+}
+
+
+const ITEM_SCHEMA: [FieldSchema; 4] = [
+    FieldSchema { key: "id", tag_type: TAG_STRING, required: true },
+    FieldSchema { key: "Count", tag_type: TAG_BYTE, required: true },
+    FieldSchema { key: "tag", tag_type: TAG_COMPOUND, required: false },
+    FieldSchema { key: "Slot", tag_type: TAG_BYTE, required: false },
+];
+
+
+fn validate_fields(compound: &Compound, schema: &[FieldSchema], errors: &mut Vec<WorldError>) {
+    for field in schema {
+        match compound.get(field.key) {
+            None if field.required => errors.push(WorldError::Missing(field.key)),
+            None => (),
+            Some(value) if value.tag_type() != field.tag_type => {
+                errors.push(WorldError::WrongType {
+                    key: field.key,
+                    expected: field.tag_type,
+                    found: value.tag_type(),
+                });
+            },
+            Some(_) => (),
+        }
+    }
+}
+
+
+/// Check that `item` has the entries the game needs to load it as an item
+/// stack (in the layout used before 1.20.5): an `id` string and a positive
+/// `Count` byte, and if present, a `tag` compound and a `Slot` byte. Extra
+/// entries are allowed.
+pub fn validate_item(item: &Compound) -> Result<(), Vec<WorldError>> {
+    let mut errors = Vec::new();
+    validate_fields(item, &ITEM_SCHEMA, &mut errors);
+    if let Some(count) = get_byte(item, "Count") {
+        if count < 1 {
+            errors.push(WorldError::OutOfRange("Count"));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+
+/// A player's main inventory (hotbar, storage, armor and offhand slots).
+pub fn player_inventory(player: &Compound) -> Option<&List> {
+    get_list(player, "Inventory")
+}
+
+
+/// A player's ender chest contents.
+pub fn player_ender_chest(player: &Compound) -> Option<&List> {
+    get_list(player, "EnderItems")
+}
@@ -0,0 +1,145 @@
+//! The `tag` compound of an item stack, holding everything beyond its id and
+//! count.
+
+use crate::nbt::{Compound, Value};
+
+use super::{get_byte, get_compound, get_compound_list, get_int, get_short};
+
+
+/// Enchantments were identified by number before 1.13, and by namespaced
+/// name (e.g. `"minecraft:sharpness"`) since.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EnchantmentId {
+    Numeric(i16),
+    Named(String),
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Enchantment {
+    pub id: EnchantmentId,
+    pub level: i32,
+}
+
+
+/// Read an item's enchantments: those applied to it (`Enchantments`, or
+/// `ench` before 1.13) followed by those stored in it, as on an enchanted
+/// book (`StoredEnchantments`).
+pub fn read_enchantments(item_tag: &Compound) -> Vec<Enchantment> {
+    ["Enchantments", "ench", "StoredEnchantments"].iter()
+        .flat_map(|key| get_compound_list(item_tag, key))
+        .filter_map(|enchantment| {
+            let id = match enchantment.get("id") {
+                Some(Value::String(name)) => EnchantmentId::Named(name.clone()),
+                Some(Value::Short(number)) => EnchantmentId::Numeric(*number),
+                _ => return None,
+            };
+            let level = get_short(enchantment, "lvl").map(i32::from)
+                .or_else(|| get_int(enchantment, "lvl"))
+                .unwrap_or(1);
+            Some(Enchantment { id, level })
+        })
+        .collect()
+}
+
+
+/// A color stored as a packed `0xRRGGBB` int.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+
+impl Color {
+    pub fn from_rgb_int(rgb: i32) -> Color {
+        Color {
+            r: (rgb >> 16) as u8,
+            g: (rgb >> 8) as u8,
+            b: rgb as u8,
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FireworkShape {
+    SmallBall,
+    LargeBall,
+    Star,
+    Creeper,
+    Burst,
+}
+
+
+impl FireworkShape {
+    fn from_type(shape: i8) -> Option<FireworkShape> {
+        Some(match shape {
+            0 => FireworkShape::SmallBall,
+            1 => FireworkShape::LargeBall,
+            2 => FireworkShape::Star,
+            3 => FireworkShape::Creeper,
+            4 => FireworkShape::Burst,
+            _ => return None,
+        })
+    }
+}
+
+
+/// One explosion of a firework rocket, or a firework star's explosion.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FireworkExplosion {
+    pub shape: FireworkShape,
+    pub colors: Vec<Color>,
+    pub fade_colors: Vec<Color>,
+    pub flicker: bool,
+    pub trail: bool,
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fireworks {
+    /// The flight duration, in gunpowder used to craft the rocket.
+    pub flight: i8,
+    pub explosions: Vec<FireworkExplosion>,
+}
+
+
+fn read_colors(explosion: &Compound, key: &str) -> Vec<Color> {
+    match explosion.get(key) {
+        Some(Value::IntArray(colors)) => {
+            colors.iter().map(|c| Color::from_rgb_int(*c)).collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+
+fn read_explosion(explosion: &Compound) -> Option<FireworkExplosion> {
+    Some(FireworkExplosion {
+        shape: FireworkShape::from_type(get_byte(explosion, "Type").unwrap_or(0))?,
+        colors: read_colors(explosion, "Colors"),
+        fade_colors: read_colors(explosion, "FadeColors"),
+        flicker: get_byte(explosion, "Flicker").unwrap_or(0) != 0,
+        trail: get_byte(explosion, "Trail").unwrap_or(0) != 0,
+    })
+}
+
+
+/// Read a firework rocket's `Fireworks` compound.
+pub fn read_fireworks(item_tag: &Compound) -> Option<Fireworks> {
+    let fireworks = get_compound(item_tag, "Fireworks")?;
+    Some(Fireworks {
+        flight: get_byte(fireworks, "Flight").unwrap_or(0),
+        explosions: get_compound_list(fireworks, "Explosions").iter()
+            .filter_map(read_explosion)
+            .collect(),
+    })
+}
+
+
+/// Read a firework star's `Explosion` compound.
+pub fn read_firework_star(item_tag: &Compound) -> Option<FireworkExplosion> {
+    read_explosion(get_compound(item_tag, "Explosion")?)
+}
@@ -0,0 +1,398 @@
+use std::f64;
+
+use crate::nbt::{Compound, List, RootValue, Value};
+use crate::nbt::{TAG_BYTE, TAG_COMPOUND, TAG_INT, TAG_LONG, TAG_STRING};
+
+use super::{
+    get_block_pos, get_byte, get_compound, get_int, get_list, get_long, get_string, get_uuid,
+    BlockPos, WorldError,
+};
+
+
+/// The vertical extent of a dimension: the lowest block Y, and the number of
+/// blocks above it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HeightInfo {
+    pub min_y: i32,
+    pub height: i32,
+}
+
+
+impl HeightInfo {
+    /// One past the highest block Y.
+    pub fn max_y(&self) -> i32 {
+        self.min_y + self.height
+    }
+}
+
+
+const OVERWORLD_HEIGHT: HeightInfo = HeightInfo { min_y: -64, height: 384 };
+const NETHER_HEIGHT: HeightInfo = HeightInfo { min_y: 0, height: 256 };
+const END_HEIGHT: HeightInfo = HeightInfo { min_y: 0, height: 256 };
+
+
+/// `level.dat` wraps everything in a `Data` compound.
+fn level_data(level: &RootValue) -> Option<&Compound> {
+    match level.value {
+        Value::Compound(ref root) => get_compound(root, "Data"),
+        _ => None,
+    }
+}
+
+
+fn vanilla_height(dimension: &str) -> HeightInfo {
+    match dimension.trim_start_matches("minecraft:") {
+        "the_nether" => NETHER_HEIGHT,
+        "the_end" => END_HEIGHT,
+        _ => OVERWORLD_HEIGHT,
+    }
+}
+
+
+/// Get the height parameters of `dimension` (e.g. `"minecraft:the_nether"`).
+///
+/// If the world's generation settings define the dimension with an inline
+/// dimension type, its `min_y` and `height` are used. Otherwise this falls
+/// back to the vanilla values for the dimension's type, and to the
+/// overworld's for anything unrecognized.
+pub fn dimension_height(level: &RootValue, dimension: &str) -> HeightInfo {
+    let dimension_entry = level_data(level)
+        .and_then(|data| get_compound(data, "WorldGenSettings"))
+        .and_then(|settings| get_compound(settings, "dimensions"))
+        .and_then(|dimensions| get_compound(dimensions, dimension));
+    let dimension_entry = match dimension_entry {
+        Some(entry) => entry,
+        None => return vanilla_height(dimension),
+    };
+    match dimension_entry.get("type") {
+        Some(Value::String(dimension_type)) => vanilla_height(dimension_type),
+        Some(Value::Compound(dimension_type)) => {
+            let default = vanilla_height(dimension);
+            HeightInfo {
+                min_y: get_int(dimension_type, "min_y").unwrap_or(default.min_y),
+                height: get_int(dimension_type, "height").unwrap_or(default.height),
+            }
+        },
+        _ => vanilla_height(dimension),
+    }
+}
+
+
+/// Read the world seed: `Data.WorldGenSettings.seed` since 1.16, and
+/// `Data.RandomSeed` before that.
+pub fn read_seed(level: &RootValue) -> Option<i64> {
+    let data = level_data(level)?;
+    get_compound(data, "WorldGenSettings")
+        .and_then(|settings| get_long(settings, "seed"))
+        .or_else(|| get_long(data, "RandomSeed"))
+}
+
+
+/// The Minecraft version a world was last saved with.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VersionInfo {
+    /// The data version, as in `Data.DataVersion`.
+    pub id: i32,
+    /// The version's name, e.g. `"1.20.1"` or `"23w31a"`.
+    pub name: String,
+    /// The development branch, usually `"main"`. Added in 1.18.
+    pub series: Option<String>,
+    pub snapshot: bool,
+}
+
+
+/// Read `Data.Version`, present since 1.9.
+pub fn read_version_info(level: &RootValue) -> Option<VersionInfo> {
+    let version = get_compound(level_data(level)?, "Version")?;
+    Some(VersionInfo {
+        id: get_int(version, "Id")?,
+        name: get_string(version, "Name")?.to_string(),
+        series: get_string(version, "Series").map(str::to_string),
+        snapshot: get_byte(version, "Snapshot").unwrap_or(0) != 0,
+    })
+}
+
+
+/// The state of the fight with the Ender Dragon.
+#[derive(Debug, Clone)]
+pub struct DragonFight {
+    pub dragon_killed: bool,
+    pub previously_killed: bool,
+    /// The end gateways yet to be spawned, as indices 0–19 into the ring of
+    /// gateway positions. Each dragon kill spawns the next one.
+    pub gateways: Vec<i32>,
+    /// The UUID of the current dragon, if one is alive.
+    pub dragon_uuid: Option<u128>,
+    /// Where the exit portal is, once it has been placed.
+    pub exit_portal_location: Option<BlockPos>,
+}
+
+
+impl DragonFight {
+    /// The positions of the gateways yet to be spawned, in spawn order.
+    pub fn gateway_positions(&self) -> Vec<BlockPos> {
+        self.gateways.iter().map(|index| gateway_position(*index)).collect()
+    }
+}
+
+
+/// The position of end gateway `index` (0–19), on a ring of radius 96 around
+/// the exit portal; computed as the game does.
+pub fn gateway_position(index: i32) -> BlockPos {
+    let angle = 2.0 * (-f64::consts::PI + 0.15707963267948966 * f64::from(index));
+    BlockPos {
+        x: (96.0 * angle.cos()).floor() as i32,
+        y: 75,
+        z: (96.0 * angle.sin()).floor() as i32,
+    }
+}
+
+
+fn find_dragon_fight(data: &Compound) -> Option<&Compound> {
+    get_compound(data, "DragonFight").or_else(|| {
+        get_compound(data, "DimensionData")
+            .and_then(|dimensions| get_compound(dimensions, "1"))
+            .and_then(|end| get_compound(end, "DragonFight"))
+    })
+}
+
+
+fn find_dragon_fight_mut(level: &mut RootValue) -> Option<&mut Compound> {
+    let data = match level.value {
+        Value::Compound(ref mut root) => match root.get_mut("Data") {
+            Some(Value::Compound(data)) => data,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let in_dimension_data = !data.contains_key("DragonFight");
+    let fight = if in_dimension_data {
+        match data.get_mut("DimensionData") {
+            Some(Value::Compound(dimensions)) => match dimensions.get_mut("1") {
+                Some(Value::Compound(end)) => end.get_mut("DragonFight"),
+                _ => None,
+            },
+            _ => None,
+        }
+    } else {
+        data.get_mut("DragonFight")
+    };
+    match fight {
+        Some(Value::Compound(fight)) => Some(fight),
+        _ => None,
+    }
+}
+
+
+/// The exit portal's location: `{X, Y, Z}` in older versions, and
+/// `[I; x, y, z]` in newer.
+fn get_exit_portal_location(fight: &Compound) -> Option<BlockPos> {
+    match fight.get("ExitPortalLocation") {
+        Some(Value::Compound(pos)) => Some(BlockPos {
+            x: get_int(pos, "X")?,
+            y: get_int(pos, "Y")?,
+            z: get_int(pos, "Z")?,
+        }),
+        _ => get_block_pos(fight, "ExitPortalLocation"),
+    }
+}
+
+
+/// Read the Ender Dragon fight state from `level.dat`.
+///
+/// This lives at `Data.DragonFight` since 1.16, and at
+/// `Data.DimensionData.1.DragonFight` before that.
+pub fn read_dragon_fight(level: &RootValue) -> Option<DragonFight> {
+    let fight = find_dragon_fight(level_data(level)?)?;
+    let dragon_uuid = get_uuid(fight, "Dragon").or_else(|| {
+        let most = get_long(fight, "DragonUUIDMost")? as u64;
+        let least = get_long(fight, "DragonUUIDLeast")? as u64;
+        Some(u128::from(most) << 64 | u128::from(least))
+    });
+    Some(DragonFight {
+        dragon_killed: get_byte(fight, "DragonKilled").unwrap_or(0) != 0,
+        previously_killed: get_byte(fight, "PreviouslyKilled").unwrap_or(0) != 0,
+        gateways: match get_list(fight, "Gateways") {
+            Some(List::Int(gateways)) => gateways.clone(),
+            _ => Vec::new(),
+        },
+        dragon_uuid,
+        exit_portal_location: get_exit_portal_location(fight),
+    })
+}
+
+
+/// Replace the list of gateways yet to be spawned. Returns `false`, changing
+/// nothing, if `level` has no dragon fight.
+pub fn set_dragon_fight_gateways(level: &mut RootValue, gateways: &[i32]) -> bool {
+    match find_dragon_fight_mut(level) {
+        Some(fight) => {
+            let gateways = List::Int(gateways.to_vec());
+            fight.insert("Gateways".to_string(), Value::List(gateways));
+            true
+        },
+        None => false,
+    }
+}
+
+
+/// Set, or with `None` remove, the exit portal's location, keeping the
+/// format already in use. Returns `false`, changing nothing, if `level` has
+/// no dragon fight.
+pub fn set_exit_portal_location(level: &mut RootValue, location: Option<BlockPos>) -> bool {
+    let fight = match find_dragon_fight_mut(level) {
+        Some(fight) => fight,
+        None => return false,
+    };
+    let location = match location {
+        Some(location) => location,
+        None => {
+            fight.remove("ExitPortalLocation");
+            return true;
+        },
+    };
+    let value = match fight.get("ExitPortalLocation") {
+        Some(Value::IntArray(_)) => {
+            Value::IntArray(vec![location.x, location.y, location.z])
+        },
+        _ => {
+            let mut pos = Compound::new();
+            pos.insert("X".to_string(), Value::Int(location.x));
+            pos.insert("Y".to_string(), Value::Int(location.y));
+            pos.insert("Z".to_string(), Value::Int(location.z));
+            Value::Compound(pos)
+        },
+    };
+    fight.insert("ExitPortalLocation".to_string(), value);
+    true
+}
+
+
+/// A view of `level.dat`'s `Data` compound, with getters for commonly needed
+/// fields. Anything else can be read from `data()` directly.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelData<'a> {
+    data: &'a Compound,
+}
+
+
+impl<'a> LevelData<'a> {
+    /// Returns `None` if `level` has no `Data` compound.
+    pub fn new(level: &'a RootValue) -> Option<LevelData<'a>> {
+        level_data(level).map(|data| LevelData { data })
+    }
+
+    /// The underlying `Data` compound.
+    pub fn data(&self) -> &'a Compound {
+        self.data
+    }
+
+    pub fn level_name(&self) -> Option<&'a str> {
+        get_string(self.data, "LevelName")
+    }
+
+    pub fn data_version(&self) -> Option<i32> {
+        get_int(self.data, "DataVersion")
+    }
+
+    /// Whether the world has ever been opened by a modified client or
+    /// server.
+    pub fn was_modded(&self) -> Option<bool> {
+        get_byte(self.data, "WasModded").map(|b| b != 0)
+    }
+
+    /// The brands (e.g. `"vanilla"`, `"fabric"`) of every server that has
+    /// loaded the world.
+    pub fn server_brands(&self) -> Vec<&'a str> {
+        match get_list(self.data, "ServerBrands") {
+            Some(List::String(brands)) => brands.iter().map(|b| b.as_str()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The UUID of the wandering trader currently in the world, if any.
+    pub fn wandering_trader_id(&self) -> Option<u128> {
+        get_uuid(self.data, "WanderingTraderId")
+    }
+
+    /// The percent chance a wandering trader spawns when the delay next
+    /// runs out.
+    pub fn wandering_trader_spawn_chance(&self) -> Option<i32> {
+        get_int(self.data, "WanderingTraderSpawnChance")
+    }
+
+    /// Ticks until the next wandering trader spawn attempt.
+    pub fn wandering_trader_spawn_delay(&self) -> Option<i32> {
+        get_int(self.data, "WanderingTraderSpawnDelay")
+    }
+}
+
+
+/// The most commonly needed fields of `level.dat`, copied out of it. For
+/// anything else, see `LevelData`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LevelSummary {
+    pub level_name: String,
+    /// The world spawn point.
+    pub spawn: BlockPos,
+    /// The default game mode: 0 survival, 1 creative, 2 adventure,
+    /// 3 spectator.
+    pub game_type: i32,
+    /// Absent before 1.9.
+    pub data_version: Option<i32>,
+    pub hardcore: bool,
+    /// 0 peaceful to 3 hard. Absent before 1.8.
+    pub difficulty: Option<i8>,
+    /// When the world was last saved, in milliseconds since the Unix epoch.
+    pub last_played: Option<i64>,
+}
+
+
+/// Get `key` from `data` with `get`, which should accept exactly the values
+/// of tag type `expected`.
+fn typed_field<'a, T, F>(data: &'a Compound, key: &'static str, expected: u8, get: F)
+        -> Result<Option<T>, WorldError>
+        where F: Fn(&'a Value) -> Option<T> {
+    match data.get(key) {
+        Some(value) => match get(value) {
+            Some(v) => Ok(Some(v)),
+            None => Err(WorldError::WrongType { key, expected, found: value.tag_type() }),
+        },
+        None => Ok(None),
+    }
+}
+
+
+fn required_field<'a, T, F>(data: &'a Compound, key: &'static str, expected: u8, get: F)
+        -> Result<T, WorldError>
+        where F: Fn(&'a Value) -> Option<T> {
+    typed_field(data, key, expected, get)?.ok_or(WorldError::Missing(key))
+}
+
+
+impl LevelSummary {
+    /// Read the summary from `level.dat`. `LevelName`, `GameType` and
+    /// `SpawnX`/`Y`/`Z` are required; the rest take their defaults if they're
+    /// missing. Any field of the wrong type is an error.
+    pub fn from_root(level: &RootValue) -> Result<LevelSummary, WorldError> {
+        let data = match level.value {
+            Value::Compound(ref root) => {
+                required_field(root, "Data", TAG_COMPOUND, Value::as_compound)?
+            },
+            _ => return Err(WorldError::Missing("Data")),
+        };
+        Ok(LevelSummary {
+            level_name: required_field(data, "LevelName", TAG_STRING, Value::as_str)?.to_string(),
+            spawn: BlockPos {
+                x: required_field(data, "SpawnX", TAG_INT, Value::as_i32)?,
+                y: required_field(data, "SpawnY", TAG_INT, Value::as_i32)?,
+                z: required_field(data, "SpawnZ", TAG_INT, Value::as_i32)?,
+            },
+            game_type: required_field(data, "GameType", TAG_INT, Value::as_i32)?,
+            data_version: typed_field(data, "DataVersion", TAG_INT, Value::as_i32)?,
+            hardcore: typed_field(data, "hardcore", TAG_BYTE, Value::as_bool)?.unwrap_or(false),
+            difficulty: typed_field(data, "Difficulty", TAG_BYTE, Value::as_i8)?,
+            last_played: typed_field(data, "LastPlayed", TAG_LONG, Value::as_i64)?,
+        })
+    }
+}
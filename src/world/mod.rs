@@ -0,0 +1,202 @@
+//! Helpers for pulling typed game data out of parsed NBT.
+//!
+//! Minecraft's save format is "just NBT", but the interesting parts live at
+//! well-known keys inside compounds. The functions here know where to look.
+
+use crate::nbt::{Compound, List, Value};
+
+
+pub mod banner;
+pub mod chunk;
+pub mod data;
+pub mod effect;
+pub mod entity;
+pub mod inventory;
+pub mod item;
+pub mod level;
+pub mod player;
+pub mod poi;
+pub mod spawner;
+pub mod villager;
+#[cfg(test)]
+mod tests;
+
+
+/// The position of a block in the world.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+
+impl BlockPos {
+    fn from_ints(ints: &[i32]) -> Option<BlockPos> {
+        match *ints {
+            [x, y, z] => Some(BlockPos { x, y, z }),
+            _ => None,
+        }
+    }
+}
+
+
+/// One of the six directions a block face can point.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+
+impl Direction {
+    /// Decode the game's usual 0–5 numbering (down, up, north, south,
+    /// west, east).
+    pub fn from_3d_value(value: i8) -> Option<Direction> {
+        Some(match value {
+            0 => Direction::Down,
+            1 => Direction::Up,
+            2 => Direction::North,
+            3 => Direction::South,
+            4 => Direction::West,
+            5 => Direction::East,
+            _ => return None,
+        })
+    }
+
+    /// Decode the 0–3 numbering used for horizontal-only directions (south,
+    /// west, north, east).
+    pub fn from_2d_value(value: i8) -> Option<Direction> {
+        Some(match value {
+            0 => Direction::South,
+            1 => Direction::West,
+            2 => Direction::North,
+            3 => Direction::East,
+            _ => return None,
+        })
+    }
+}
+
+
+/// A problem with the shape of game data, found by
+/// `inventory::validate_item`, `level::LevelSummary::from_root` or
+/// `chunk::validate_section_blockstates`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WorldError {
+    Missing(&'static str),
+    /// The entry's tag type isn't the `TAG_*` type expected.
+    WrongType {
+        key: &'static str,
+        expected: u8,
+        found: u8,
+    },
+    OutOfRange(&'static str),
+    /// The array or list's length isn't the one its other entries imply.
+    WrongLength {
+        key: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+
+fn get_compound<'a>(compound: &'a Compound, key: &str) -> Option<&'a Compound> {
+    match compound.get(key) {
+        Some(Value::Compound(c)) => Some(c),
+        _ => None,
+    }
+}
+
+
+fn get_list<'a>(compound: &'a Compound, key: &str) -> Option<&'a List> {
+    match compound.get(key) {
+        Some(Value::List(l)) => Some(l),
+        _ => None,
+    }
+}
+
+
+fn get_compound_list<'a>(compound: &'a Compound, key: &str) -> &'a [Compound] {
+    match get_list(compound, key) {
+        Some(List::Compound(items)) => &items[..],
+        _ => &[],
+    }
+}
+
+
+fn get_string<'a>(compound: &'a Compound, key: &str) -> Option<&'a str> {
+    match compound.get(key) {
+        Some(Value::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+
+fn get_byte(compound: &Compound, key: &str) -> Option<i8> {
+    match compound.get(key) {
+        Some(Value::Byte(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+
+fn get_short(compound: &Compound, key: &str) -> Option<i16> {
+    match compound.get(key) {
+        Some(Value::Short(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+
+fn get_int(compound: &Compound, key: &str) -> Option<i32> {
+    match compound.get(key) {
+        Some(Value::Int(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+
+fn get_long(compound: &Compound, key: &str) -> Option<i64> {
+    match compound.get(key) {
+        Some(Value::Long(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+
+fn get_float(compound: &Compound, key: &str) -> Option<f32> {
+    match compound.get(key) {
+        Some(Value::Float(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+
+/// Decode a UUID stored as four ints, most significant first (1.16+).
+fn uuid_from_ints(ints: &[i32]) -> Option<u128> {
+    if ints.len() != 4 {
+        return None;
+    }
+    Some(ints.iter().fold(0u128, |acc, i| (acc << 32) | u128::from(*i as u32)))
+}
+
+
+/// Positions stored as `[I; x, y, z]`.
+fn get_block_pos(compound: &Compound, key: &str) -> Option<BlockPos> {
+    match compound.get(key) {
+        Some(Value::IntArray(ints)) => BlockPos::from_ints(ints),
+        _ => None,
+    }
+}
+
+
+fn get_uuid(compound: &Compound, key: &str) -> Option<u128> {
+    match compound.get(key) {
+        Some(Value::IntArray(ints)) => uuid_from_ints(ints),
+        _ => None,
+    }
+}
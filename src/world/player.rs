@@ -0,0 +1,55 @@
+//! Player data files, `playerdata/<uuid>.dat`. The same UUIDs name the
+//! player's JSON files under `stats/` and `advancements/`.
+
+use std::path::{Path, PathBuf};
+
+
+/// Format a UUID the way the game does in file names: lowercase hex, in
+/// 8-4-4-4-12 groups.
+pub fn format_uuid(uuid: u128) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        uuid >> 96,
+        (uuid >> 80) & 0xffff,
+        (uuid >> 64) & 0xffff,
+        (uuid >> 48) & 0xffff,
+        uuid & 0xffff_ffff_ffff,
+    )
+}
+
+
+/// Parse a hyphenated UUID, e.g. `"0b6f3e2a-6b3c-4c8f-9d1e-2f0a9b8c7d6e"`.
+pub fn parse_uuid(s: &str) -> Option<u128> {
+    let groups = s.split('-').collect::<Vec<_>>();
+    let lengths = groups.iter().map(|g| g.len()).collect::<Vec<_>>();
+    if lengths != [8, 4, 4, 4, 12] {
+        return None;
+    }
+    let hex = groups.concat();
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u128::from_str_radix(&hex, 16).ok()
+}
+
+
+/// Get the player UUID from a player data file's name, e.g.
+/// `playerdata/0b6f3e2a-6b3c-4c8f-9d1e-2f0a9b8c7d6e.dat`.
+pub fn player_uuid_from_dat_filename(path: &Path) -> Option<u128> {
+    if path.extension()? != "dat" {
+        return None;
+    }
+    parse_uuid(path.file_stem()?.to_str()?)
+}
+
+
+/// Find the data file for the player with `uuid` in a `playerdata/`
+/// directory, if there is one.
+pub fn find_player_dat(playerdata_dir: &Path, uuid: u128) -> Option<PathBuf> {
+    let path = playerdata_dir.join(format!("{}.dat", format_uuid(uuid)));
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
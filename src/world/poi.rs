@@ -0,0 +1,66 @@
+//! Points of interest (beds, workstations, bells, …), stored per chunk in a
+//! world's `poi/` region files.
+
+use std::collections::HashMap;
+
+use crate::nbt::{RootValue, Value};
+
+use super::{BlockPos, get_block_pos, get_byte, get_compound, get_compound_list};
+use super::{get_int, get_string};
+
+
+#[derive(Debug, Clone)]
+pub struct PoiRecord {
+    /// The POI type, e.g. `"minecraft:home"` or `"minecraft:farmer"`.
+    pub poi_type: String,
+    pub pos: BlockPos,
+    /// How many more villagers may claim this POI; 0 once it is taken.
+    pub free_tickets: i32,
+    /// Whether the record's chunk section was considered up to date. The
+    /// game re-scans invalid sections, discarding their records.
+    pub valid: bool,
+}
+
+
+/// Read every POI record in a POI chunk, across all of its sections.
+pub fn read_poi_records(chunk: &RootValue) -> Vec<PoiRecord> {
+    let sections = match chunk.value {
+        Value::Compound(ref root) => get_compound(root, "Sections"),
+        _ => None,
+    };
+    let sections = match sections {
+        Some(sections) => sections,
+        None => return Vec::new(),
+    };
+    let mut records = Vec::new();
+    for section in sections.values() {
+        let section = match section {
+            Value::Compound(section) => section,
+            _ => continue,
+        };
+        let valid = get_byte(section, "Valid").unwrap_or(0) != 0;
+        for record in get_compound_list(section, "Records") {
+            let poi_type = get_string(record, "type");
+            let pos = get_block_pos(record, "pos");
+            if let (Some(poi_type), Some(pos)) = (poi_type, pos) {
+                records.push(PoiRecord {
+                    poi_type: poi_type.to_string(),
+                    pos,
+                    free_tickets: get_int(record, "free_tickets").unwrap_or(0),
+                    valid,
+                });
+            }
+        }
+    }
+    records
+}
+
+
+/// Count a POI chunk's records by POI type.
+pub fn poi_summary(chunk: &RootValue) -> HashMap<String, usize> {
+    let mut summary = HashMap::new();
+    for record in read_poi_records(chunk) {
+        *summary.entry(record.poi_type).or_insert(0) += 1;
+    }
+    summary
+}
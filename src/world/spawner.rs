@@ -0,0 +1,76 @@
+//! Mob spawner block entities.
+
+use crate::nbt::Compound;
+
+use super::{get_compound, get_compound_list, get_int, get_short, get_string};
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpawnPotential {
+    pub entity_id: Option<String>,
+    pub weight: i32,
+}
+
+
+/// A spawner's timing and range settings, and what it spawns. Settings
+/// missing from the block entity take the game's defaults.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Spawner {
+    /// Ticks until the next spawn attempt.
+    pub delay: i16,
+    pub min_spawn_delay: i16,
+    pub max_spawn_delay: i16,
+    pub spawn_count: i16,
+    pub max_nearby_entities: i16,
+    pub required_player_range: i16,
+    pub spawn_range: i16,
+    /// The entity the next spawn will be, from `SpawnData`.
+    pub entity_id: Option<String>,
+    /// The weighted choices for the spawns after the next.
+    pub potentials: Vec<SpawnPotential>,
+}
+
+
+/// The entity id in spawn data: `{entity: {id}}` since 1.18, and the entity
+/// compound itself before.
+fn spawn_data_entity_id(spawn_data: &Compound) -> Option<String> {
+    let entity = get_compound(spawn_data, "entity").unwrap_or(spawn_data);
+    get_string(entity, "id").map(str::to_string)
+}
+
+
+fn read_potential(potential: &Compound) -> Option<SpawnPotential> {
+    // 1.18+: {weight, data: {entity: {...}}}; before: {Weight, Entity: {...}}.
+    if let Some(data) = get_compound(potential, "data") {
+        return Some(SpawnPotential {
+            entity_id: spawn_data_entity_id(data),
+            weight: get_int(potential, "weight").unwrap_or(1),
+        });
+    }
+    Some(SpawnPotential {
+        entity_id: spawn_data_entity_id(get_compound(potential, "Entity")?),
+        weight: get_int(potential, "Weight").unwrap_or(1),
+    })
+}
+
+
+pub fn read_spawner(block_entity: &Compound) -> Spawner {
+    let short = |key, default| get_short(block_entity, key).unwrap_or(default);
+    let entity_id = get_compound(block_entity, "SpawnData")
+        .and_then(spawn_data_entity_id)
+        // Before 1.9, the entity id was stored on its own.
+        .or_else(|| get_string(block_entity, "EntityId").map(str::to_string));
+    Spawner {
+        delay: short("Delay", 20),
+        min_spawn_delay: short("MinSpawnDelay", 200),
+        max_spawn_delay: short("MaxSpawnDelay", 800),
+        spawn_count: short("SpawnCount", 4),
+        max_nearby_entities: short("MaxNearbyEntities", 6),
+        required_player_range: short("RequiredPlayerRange", 16),
+        spawn_range: short("SpawnRange", 4),
+        entity_id,
+        potentials: get_compound_list(block_entity, "SpawnPotentials").iter()
+            .filter_map(read_potential)
+            .collect(),
+    }
+}
@@ -0,0 +1,68 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::banner::{self, BannerPattern, DyeColor};
+
+
+fn layer(entries: Vec<(&str, Value)>) -> Compound {
+    entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+
+#[test]
+fn test_read_banner_patterns_legacy() {
+    let mut banner = Compound::new();
+    banner.insert("id".to_string(), Value::String("minecraft:banner".to_string()));
+    banner.insert("Patterns".to_string(), Value::List(List::Compound(vec![
+        layer(vec![
+            ("Pattern", Value::String("bs".to_string())),
+            ("Color", Value::Int(14)),
+        ]),
+        layer(vec![
+            ("Pattern", Value::String("cre".to_string())),
+            ("Color", Value::Int(3)),
+        ]),
+    ])));
+    assert_eq!(
+        vec![
+            BannerPattern { pattern: "bs".to_string(), color: DyeColor::Red },
+            BannerPattern { pattern: "cre".to_string(), color: DyeColor::LightBlue },
+        ],
+        banner::read_banner_patterns(&banner),
+    );
+}
+
+
+#[test]
+fn test_read_banner_patterns() {
+    let mut inline = Compound::new();
+    inline.insert("asset_id".to_string(), Value::String("example:custom".to_string()));
+    let mut banner = Compound::new();
+    banner.insert("patterns".to_string(), Value::List(List::Compound(vec![
+        layer(vec![
+            ("pattern", Value::String("minecraft:stripe_bottom".to_string())),
+            ("color", Value::String("black".to_string())),
+        ]),
+        layer(vec![
+            ("pattern", Value::Compound(inline)),
+            ("color", Value::String("light_gray".to_string())),
+        ]),
+        // Unknown colors are skipped.
+        layer(vec![
+            ("pattern", Value::String("minecraft:cross".to_string())),
+            ("color", Value::String("chartreuse".to_string())),
+        ]),
+    ])));
+    assert_eq!(
+        vec![
+            BannerPattern {
+                pattern: "minecraft:stripe_bottom".to_string(),
+                color: DyeColor::Black,
+            },
+            BannerPattern {
+                pattern: "example:custom".to_string(),
+                color: DyeColor::LightGray,
+            },
+        ],
+        banner::read_banner_patterns(&banner),
+    );
+    assert!(banner::read_banner_patterns(&Compound::new()).is_empty());
+}
@@ -0,0 +1,150 @@
+use std::io::Cursor;
+
+use crate::nbt::{reader, Compound, List, RootValue, Value, TAG_LONG_ARRAY};
+use crate::world::chunk::{self, ScheduledTick, TickKind};
+use crate::world::WorldError;
+use crate::world::BlockPos;
+
+
+const CHUNK: &[u8] = include_bytes!("chunk.nbt");
+
+
+fn parse(bytes: &[u8]) -> RootValue {
+    match reader::parse_nbt_stream(&mut Cursor::new(bytes)) {
+        Ok(result) => result,
+        Err(err) => panic!("{:?}", err),
+    }
+}
+
+
+#[test]
+fn test_read_scheduled_ticks() {
+    let chunk = parse(CHUNK);
+    assert_eq!(
+        vec![ScheduledTick {
+            kind: TickKind::Block,
+            id: "minecraft:repeater".to_string(),
+            pos: BlockPos { x: 35, y: -12, z: -70 },
+            delay: 2,
+            priority: -1,
+        }],
+        chunk::read_scheduled_ticks(&chunk),
+    );
+}
+
+
+#[test]
+fn test_read_scheduled_ticks_pre_1_18() {
+    let mut tick = Compound::new();
+    tick.insert("i".to_string(), Value::String("minecraft:water".to_string()));
+    tick.insert("x".to_string(), Value::Int(1));
+    tick.insert("y".to_string(), Value::Int(2));
+    tick.insert("z".to_string(), Value::Int(3));
+    tick.insert("t".to_string(), Value::Int(5));
+    tick.insert("p".to_string(), Value::Int(0));
+    let mut level = Compound::new();
+    level.insert("LiquidTicks".to_string(), Value::List(List::Compound(vec![tick])));
+    let mut root = Compound::new();
+    root.insert("Level".to_string(), Value::Compound(level));
+    let chunk = RootValue { name: String::new(), value: Value::Compound(root) };
+
+    let ticks = chunk::read_scheduled_ticks(&chunk);
+    assert_eq!(1, ticks.len());
+    assert_eq!(TickKind::Fluid, ticks[0].kind);
+    assert_eq!("minecraft:water", ticks[0].id);
+    assert_eq!(BlockPos { x: 1, y: 2, z: 3 }, ticks[0].pos);
+    assert_eq!(5, ticks[0].delay);
+}
+
+
+#[test]
+fn test_chunk_status() {
+    let mut chunk = parse(CHUNK);
+    assert_eq!(Some("minecraft:full"), chunk::chunk_status(&chunk));
+    chunk::set_chunk_status(&mut chunk, "minecraft:empty");
+    assert_eq!(Some("minecraft:empty"), chunk::chunk_status(&chunk));
+}
+
+
+#[test]
+fn test_chunk_status_pre_1_18() {
+    let mut level = Compound::new();
+    level.insert("Status".to_string(), Value::String("postprocessed".to_string()));
+    let mut root = Compound::new();
+    root.insert("Level".to_string(), Value::Compound(level));
+    let mut chunk = RootValue { name: String::new(), value: Value::Compound(root) };
+
+    assert_eq!(Some("postprocessed"), chunk::chunk_status(&chunk));
+    chunk::set_chunk_status(&mut chunk, "empty");
+    assert_eq!(Some("empty"), chunk::chunk_status(&chunk));
+    match chunk.value {
+        Value::Compound(ref root) => assert!(!root.contains_key("Status")),
+        _ => panic!("Not a compound?"),
+    };
+}
+
+
+fn palette(len: usize) -> Value {
+    Value::List(List::Compound((0..len).map(|i| {
+        let mut block = Compound::new();
+        block.insert("Name".to_string(), Value::String(format!("test:block_{}", i)));
+        block
+    }).collect()))
+}
+
+
+fn section_1_18(palette_len: usize, data_len: usize) -> Compound {
+    let mut states = Compound::new();
+    states.insert("palette".to_string(), palette(palette_len));
+    states.insert("data".to_string(), Value::LongArray(vec![0; data_len]));
+    let mut section = Compound::new();
+    section.insert("block_states".to_string(), Value::Compound(states));
+    section
+}
+
+
+fn section_pre_1_18(palette_len: usize, data_len: usize) -> Compound {
+    let mut section = Compound::new();
+    section.insert("Palette".to_string(), palette(palette_len));
+    section.insert("BlockStates".to_string(), Value::LongArray(vec![0; data_len]));
+    section
+}
+
+
+#[test]
+fn test_validate_section_blockstates() {
+    // 16 entries take 4 bits, 16 to a long.
+    assert_eq!(Ok(()), chunk::validate_section_blockstates(&section_1_18(16, 256), 3465));
+    assert_eq!(
+        Err(WorldError::WrongLength { key: "data", expected: 256, found: 255 }),
+        chunk::validate_section_blockstates(&section_1_18(16, 255), 3465),
+    );
+    // A single block type needs no data, and fewer than 16 still take 4 bits.
+    let mut single = section_1_18(1, 0);
+    if let Some(Value::Compound(states)) = single.get_mut("block_states") {
+        states.remove("data");
+    }
+    assert_eq!(Ok(()), chunk::validate_section_blockstates(&single, 3465));
+    assert_eq!(Ok(()), chunk::validate_section_blockstates(&section_1_18(2, 256), 3465));
+
+    // 17 entries take 5 bits: 12 to a long since 20w17a, 64 bits spread over
+    // 5 longs before.
+    assert_eq!(Ok(()), chunk::validate_section_blockstates(&section_pre_1_18(17, 342), 2586));
+    assert_eq!(Ok(()), chunk::validate_section_blockstates(&section_pre_1_18(17, 320), 2230));
+    assert_eq!(
+        Err(WorldError::WrongLength { key: "BlockStates", expected: 320, found: 342 }),
+        chunk::validate_section_blockstates(&section_pre_1_18(17, 342), 2230),
+    );
+
+    let mut section = section_pre_1_18(4, 0);
+    section.insert("BlockStates".to_string(), Value::IntArray(vec![]));
+    assert_eq!(
+        Err(WorldError::WrongType { key: "BlockStates", expected: TAG_LONG_ARRAY, found: 11 }),
+        chunk::validate_section_blockstates(&section, 2230),
+    );
+    section.remove("BlockStates");
+    assert_eq!(
+        Err(WorldError::Missing("BlockStates")),
+        chunk::validate_section_blockstates(&section, 2230),
+    );
+}
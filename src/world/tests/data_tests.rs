@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::world::{data, BlockPos};
+
+
+const RAIDS_DAT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/world/tests/raids.dat");
+const PLAYERDATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/world/tests/playerdata");
+const WORLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/world/tests/world");
+
+
+#[test]
+fn test_read_raids() {
+    let root = match data::read_world_data_file(RAIDS_DAT) {
+        Ok(result) => result,
+        Err(err) => panic!("{:?}", err),
+    };
+    let raids = data::read_raids(&root);
+    assert_eq!(1, raids.len());
+    let raid = &raids[0];
+    assert_eq!(7, raid.id);
+    assert_eq!(BlockPos { x: -120, y: 64, z: 355 }, raid.center);
+    assert_eq!("ongoing", raid.status);
+    assert!(raid.started);
+    assert!(raid.active);
+    assert_eq!(1200, raid.ticks_active);
+    assert_eq!(2, raid.bad_omen_level);
+    assert_eq!(1, raid.groups_spawned);
+    assert_eq!(5, raid.num_groups);
+}
+
+
+#[test]
+fn test_parse_dir() {
+    let results = data::parse_dir(Path::new(PLAYERDATA)).unwrap();
+    let names = results.iter()
+        .map(|(path, _)| path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "0b6f3e2a-6b3c-4c8f-9d1e-2f0a9b8c7d6e.dat",
+            "5d1f7c3b-1111-4a2b-8c3d-4e5f6a7b8c9d.dat",
+        ],
+        names,
+    );
+    for (_, result) in &results {
+        assert!(result.is_ok());
+    }
+}
+
+
+#[test]
+fn test_dimension_data_dir() {
+    let world = Path::new("saves/world");
+    assert_eq!(
+        Path::new("saves/world/data"),
+        data::dimension_data_dir(world, "minecraft:overworld"),
+    );
+    assert_eq!(
+        Path::new("saves/world/DIM-1/data"),
+        data::dimension_data_dir(world, "minecraft:the_nether"),
+    );
+    assert_eq!(
+        Path::new("saves/world/DIM1/data"),
+        data::dimension_data_dir(world, "the_end"),
+    );
+    assert_eq!(
+        Path::new("saves/world/dimensions/example/mining/data"),
+        data::dimension_data_dir(world, "example:mining"),
+    );
+}
+
+
+#[test]
+fn test_read_dimension_data_file() {
+    let root = data::read_dimension_data_file(
+        Path::new(WORLD), "minecraft:the_nether", "raids_nether.dat",
+    ).unwrap();
+    assert_eq!(1, data::read_raids(&root).len());
+
+    assert!(data::read_dimension_data_file(
+        Path::new(WORLD), "minecraft:the_end", "raids_end.dat",
+    ).is_err());
+}
+
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parse_dir_parallel_matches_sequential() {
+    let sequential = data::parse_dir(Path::new(PLAYERDATA)).unwrap();
+    let parallel = data::parse_dir_parallel(Path::new(PLAYERDATA)).unwrap();
+    assert_eq!(sequential.len(), parallel.len());
+    for ((seq_path, seq), (par_path, par)) in sequential.iter().zip(&parallel) {
+        assert_eq!(seq_path, par_path);
+        assert_eq!(seq.as_ref().unwrap(), par.as_ref().unwrap());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::effect::{self, EffectId, PotionEffect};
+
+
+fn compound(entries: Vec<(&str, Value)>) -> Compound {
+    entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+
+#[test]
+fn test_read_potion_effects_item() {
+    // A 1.20.1 potion: numeric ids and CamelCase keys.
+    let tag = compound(vec![
+        ("Potion", Value::String("minecraft:long_swiftness".to_string())),
+        ("CustomPotionEffects", Value::List(List::Compound(vec![
+            compound(vec![
+                ("Id", Value::Byte(16)),
+                ("Amplifier", Value::Byte(0)),
+                ("Duration", Value::Int(3600)),
+            ]),
+            compound(vec![
+                ("Id", Value::Byte(5)),
+                ("Amplifier", Value::Byte(1)),
+                ("Duration", Value::Int(1800)),
+                ("ShowParticles", Value::Byte(0)),
+            ]),
+        ]))),
+    ]);
+    assert_eq!(Some("minecraft:long_swiftness"), effect::potion_type(&tag));
+    assert_eq!(
+        vec![
+            PotionEffect {
+                id: EffectId::Numeric(16),
+                amplifier: 0,
+                duration: 3600,
+                ambient: false,
+                show_particles: true,
+                show_icon: true,
+            },
+            PotionEffect {
+                id: EffectId::Numeric(5),
+                amplifier: 1,
+                duration: 1800,
+                ambient: false,
+                show_particles: false,
+                show_icon: true,
+            },
+        ],
+        effect::read_potion_effects(&tag),
+    );
+}
+
+
+#[test]
+fn test_read_potion_effects_entity() {
+    // A 1.20.2+ entity: named ids and snake_case keys.
+    let zombie = compound(vec![
+        ("id", Value::String("minecraft:zombie".to_string())),
+        ("active_effects", Value::List(List::Compound(vec![
+            compound(vec![
+                ("id", Value::String("minecraft:fire_resistance".to_string())),
+                ("amplifier", Value::Byte(0)),
+                ("duration", Value::Int(-1)),
+                ("ambient", Value::Byte(1)),
+                ("show_icon", Value::Byte(0)),
+            ]),
+        ]))),
+    ]);
+    assert_eq!(
+        vec![
+            PotionEffect {
+                id: EffectId::Named("minecraft:fire_resistance".to_string()),
+                amplifier: 0,
+                duration: -1,
+                ambient: true,
+                show_particles: true,
+                show_icon: false,
+            },
+        ],
+        effect::read_potion_effects(&zombie),
+    );
+    assert_eq!(None, effect::potion_type(&zombie));
+    assert!(effect::read_potion_effects(&Compound::new()).is_empty());
+}
@@ -0,0 +1,119 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::{entity, Direction};
+
+
+fn paper_zombie() -> Compound {
+    let mut bukkit_values = Compound::new();
+    bukkit_values.insert("myplugin:owner".to_string(), Value::String("Steve".to_string()));
+
+    let mut zombie = Compound::new();
+    zombie.insert("id".to_string(), Value::String("minecraft:zombie".to_string()));
+    zombie.insert(
+        "Paper.Origin".to_string(),
+        Value::List(List::Double(vec![10.5, 64.0, -20.5])),
+    );
+    zombie.insert("Paper.SpawnReason".to_string(), Value::String("SPAWNER".to_string()));
+    zombie.insert("Paper.FromMobSpawner".to_string(), Value::Byte(1));
+    zombie.insert("BukkitValues".to_string(), Value::Compound(bukkit_values));
+    zombie
+}
+
+
+#[test]
+fn test_read_paper_origin() {
+    let zombie = paper_zombie();
+    assert_eq!(Some([10.5, 64.0, -20.5]), entity::read_paper_origin(&zombie));
+    assert_eq!(None, entity::read_paper_origin(&Compound::new()));
+}
+
+
+#[test]
+fn test_read_server_extensions() {
+    let zombie = paper_zombie();
+    assert_eq!(Some("SPAWNER"), entity::read_paper_spawn_reason(&zombie));
+    assert_eq!(Some(true), entity::read_paper_from_mob_spawner(&zombie));
+    let values = entity::read_bukkit_values(&zombie).unwrap();
+    assert!(values.contains_key("myplugin:owner"));
+}
+
+
+#[test]
+fn test_item_frame_accessors() {
+    let mut frame = Compound::new();
+    frame.insert("id".to_string(), Value::String("minecraft:item_frame".to_string()));
+    frame.insert("Facing".to_string(), Value::Byte(1));
+    frame.insert("ItemRotation".to_string(), Value::Byte(3));
+    assert_eq!(Some(Direction::Up), entity::item_frame_facing(&frame, Some(3465)));
+    assert_eq!(Some(3), entity::item_frame_rotation(&frame));
+
+    // From 1.8 to 1.12, Facing was horizontal: 0 is south, not down.
+    let mut frame_1_12 = Compound::new();
+    frame_1_12.insert("Facing".to_string(), Value::Byte(0));
+    assert_eq!(Some(Direction::South), entity::item_frame_facing(&frame_1_12, Some(1343)));
+    assert_eq!(Some(Direction::South), entity::item_frame_facing(&frame_1_12, None));
+    assert_eq!(Some(Direction::Down), entity::item_frame_facing(&frame_1_12, Some(1519)));
+
+    let mut legacy_frame = Compound::new();
+    legacy_frame.insert("Direction".to_string(), Value::Byte(3));
+    legacy_frame.insert("ItemRotation".to_string(), Value::Byte(9));
+    assert_eq!(Some(Direction::East), entity::item_frame_facing(&legacy_frame, None));
+    assert_eq!(None, entity::item_frame_rotation(&legacy_frame));
+}
+
+
+#[test]
+fn test_painting_variant() {
+    let mut painting = Compound::new();
+    painting.insert("variant".to_string(), Value::String("minecraft:kebab".to_string()));
+    assert_eq!(Some("minecraft:kebab"), entity::painting_variant(&painting));
+
+    let mut legacy_painting = Compound::new();
+    legacy_painting.insert("Motive".to_string(), Value::String("Kebab".to_string()));
+    assert_eq!(Some("Kebab"), entity::painting_variant(&legacy_painting));
+}
+
+
+fn mob(id: &str, passengers: Vec<Compound>) -> Compound {
+    let mut mob = Compound::new();
+    mob.insert("id".to_string(), Value::String(id.to_string()));
+    if !passengers.is_empty() {
+        mob.insert("Passengers".to_string(), Value::List(List::Compound(passengers)));
+    }
+    mob
+}
+
+
+fn ids(entities: &[&Compound]) -> Vec<String> {
+    entities.iter().map(|e| match e.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        _ => panic!("Entity has no id."),
+    }).collect()
+}
+
+
+#[test]
+fn test_flatten_entity_stack() {
+    // A spider jockey riding a strider, with a chicken also on the strider.
+    let skeleton = mob("minecraft:skeleton", vec![]);
+    let spider = mob("minecraft:spider", vec![skeleton]);
+    let chicken = mob("minecraft:chicken", vec![]);
+    let strider = mob("minecraft:strider", vec![spider, chicken]);
+
+    assert_eq!(
+        vec!["minecraft:spider", "minecraft:chicken"],
+        ids(&entity::entity_passengers(&strider)),
+    );
+    assert_eq!(
+        vec![
+            "minecraft:strider",
+            "minecraft:spider",
+            "minecraft:skeleton",
+            "minecraft:chicken",
+        ],
+        ids(&entity::flatten_entity_stack(&strider)),
+    );
+
+    let lone = mob("minecraft:pig", vec![]);
+    assert!(entity::entity_passengers(&lone).is_empty());
+    assert_eq!(vec!["minecraft:pig"], ids(&entity::flatten_entity_stack(&lone)));
+}
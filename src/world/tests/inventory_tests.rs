@@ -0,0 +1,115 @@
+use crate::nbt::{Compound, List, Value};
+use crate::nbt::{TAG_BYTE, TAG_INT};
+use crate::world::inventory::{self, ItemSlot};
+use crate::world::WorldError;
+
+
+fn slot(slot: i8, id: &str, count: i8) -> Compound {
+    let mut item = Compound::new();
+    item.insert("Slot".to_string(), Value::Byte(slot));
+    item.insert("id".to_string(), Value::String(id.to_string()));
+    item.insert("Count".to_string(), Value::Byte(count));
+    item
+}
+
+
+fn player() -> Compound {
+    let mut sword = slot(0, "minecraft:diamond_sword", 1);
+    let mut tag = Compound::new();
+    tag.insert("Damage".to_string(), Value::Int(12));
+    sword.insert("tag".to_string(), Value::Compound(tag));
+    let inventory = vec![sword, slot(8, "minecraft:torch", 64)];
+
+    let mut player = Compound::new();
+    player.insert("Inventory".to_string(), Value::List(List::Compound(inventory)));
+    player.insert("EnderItems".to_string(), Value::List(List::Empty(0)));
+    player
+}
+
+
+fn summarize(slots: &[ItemSlot]) -> Vec<(i8, &str, i8)> {
+    slots.iter().map(|s| (s.slot, s.id.as_str(), s.count)).collect()
+}
+
+
+#[test]
+fn test_read_item_container() {
+    let player = player();
+    let slots = inventory::read_item_container(
+        inventory::player_inventory(&player).unwrap(),
+    );
+    assert_eq!(
+        vec![(0, "minecraft:diamond_sword", 1), (8, "minecraft:torch", 64)],
+        summarize(&slots),
+    );
+    match slots[0].tag.as_ref().and_then(|tag| tag.get("Damage")) {
+        Some(Value::Int(damage)) => assert_eq!(12, *damage),
+        _ => panic!("Sword lost its damage."),
+    };
+    assert!(slots[1].tag.is_none());
+
+    let ender_chest = inventory::read_item_container(
+        inventory::player_ender_chest(&player).unwrap(),
+    );
+    assert!(ender_chest.is_empty());
+}
+
+
+#[test]
+fn test_item_container_round_trip() {
+    let player = player();
+    let slots = inventory::read_item_container(
+        inventory::player_inventory(&player).unwrap(),
+    );
+    let rewritten = inventory::write_item_container(&slots);
+    let reread = inventory::read_item_container(&rewritten);
+    assert_eq!(summarize(&slots), summarize(&reread));
+    assert!(reread[0].tag.is_some());
+
+    match inventory::write_item_container(&[]) {
+        List::Empty(0) => (),
+        other => panic!("Expected an empty list, got {:?}", other),
+    };
+}
+
+
+#[test]
+fn test_item_container_keeps_unknown_keys() {
+    let mut item = slot(3, "minecraft:apple", 2);
+    item.insert("FutureField".to_string(), Value::Int(7));
+    let slots = inventory::read_item_container(&List::Compound(vec![item]));
+    assert_eq!(1, slots[0].extra.len());
+
+    let rewritten = inventory::write_item_container(&slots);
+    let item = match rewritten {
+        List::Compound(ref items) => &items[0],
+        other => panic!("Expected a compound list, got {:?}", other),
+    };
+    match item.get("FutureField") {
+        Some(Value::Int(7)) => (),
+        other => panic!("FutureField was lost: {:?}", other),
+    };
+    let reread = inventory::read_item_container(&rewritten);
+    assert_eq!(vec![(3, "minecraft:apple", 2)], summarize(&reread));
+}
+
+
+#[test]
+fn test_validate_item() {
+    let mut item = slot(0, "minecraft:diamond_sword", 1);
+    item.insert("tag".to_string(), Value::Compound(Compound::new()));
+    assert_eq!(Ok(()), inventory::validate_item(&item));
+
+    item.remove("id");
+    assert_eq!(Err(vec![WorldError::Missing("id")]), inventory::validate_item(&item));
+
+    let mut item = slot(0, "minecraft:stone", 0);
+    item.insert("Slot".to_string(), Value::Int(3));
+    assert_eq!(
+        Err(vec![
+            WorldError::WrongType { key: "Slot", expected: TAG_BYTE, found: TAG_INT },
+            WorldError::OutOfRange("Count"),
+        ]),
+        inventory::validate_item(&item),
+    );
+}
@@ -0,0 +1,123 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::item::{self, Color, Enchantment, EnchantmentId, FireworkShape};
+
+
+fn enchantment(id: Value, level: i16) -> Compound {
+    let mut enchantment = Compound::new();
+    enchantment.insert("id".to_string(), id);
+    enchantment.insert("lvl".to_string(), Value::Short(level));
+    enchantment
+}
+
+
+#[test]
+fn test_read_enchantments_tool() {
+    let mut tag = Compound::new();
+    tag.insert("Damage".to_string(), Value::Int(0));
+    tag.insert("Enchantments".to_string(), Value::List(List::Compound(vec![
+        enchantment(Value::String("minecraft:efficiency".to_string()), 5),
+        enchantment(Value::String("minecraft:unbreaking".to_string()), 3),
+    ])));
+    assert_eq!(
+        vec![
+            Enchantment {
+                id: EnchantmentId::Named("minecraft:efficiency".to_string()),
+                level: 5,
+            },
+            Enchantment {
+                id: EnchantmentId::Named("minecraft:unbreaking".to_string()),
+                level: 3,
+            },
+        ],
+        item::read_enchantments(&tag),
+    );
+}
+
+
+#[test]
+fn test_read_enchantments_book() {
+    let mut tag = Compound::new();
+    tag.insert("StoredEnchantments".to_string(), Value::List(List::Compound(vec![
+        enchantment(Value::String("minecraft:mending".to_string()), 1),
+    ])));
+    assert_eq!(
+        vec![Enchantment {
+            id: EnchantmentId::Named("minecraft:mending".to_string()),
+            level: 1,
+        }],
+        item::read_enchantments(&tag),
+    );
+}
+
+
+#[test]
+fn test_read_enchantments_numeric_ids() {
+    let mut tag = Compound::new();
+    tag.insert("ench".to_string(), Value::List(List::Compound(vec![
+        enchantment(Value::Short(16), 4),
+    ])));
+    assert_eq!(
+        vec![Enchantment { id: EnchantmentId::Numeric(16), level: 4 }],
+        item::read_enchantments(&tag),
+    );
+    assert!(item::read_enchantments(&Compound::new()).is_empty());
+}
+
+
+fn explosion(shape: i8, colors: Vec<i32>, fade_colors: Vec<i32>, trail: bool) -> Compound {
+    let mut explosion = Compound::new();
+    explosion.insert("Type".to_string(), Value::Byte(shape));
+    explosion.insert("Colors".to_string(), Value::IntArray(colors));
+    explosion.insert("FadeColors".to_string(), Value::IntArray(fade_colors));
+    explosion.insert("Flicker".to_string(), Value::Byte(1));
+    explosion.insert("Trail".to_string(), Value::Byte(trail as i8));
+    explosion
+}
+
+
+#[test]
+fn test_read_fireworks() {
+    let mut fireworks = Compound::new();
+    fireworks.insert("Flight".to_string(), Value::Byte(2));
+    fireworks.insert("Explosions".to_string(), Value::List(List::Compound(vec![
+        explosion(1, vec![0xff0000, 0x00ff00], vec![], true),
+        explosion(3, vec![0x3b511a], vec![0xf0f0f0], false),
+    ])));
+    let mut tag = Compound::new();
+    tag.insert("Fireworks".to_string(), Value::Compound(fireworks));
+
+    let fireworks = item::read_fireworks(&tag).unwrap();
+    assert_eq!(2, fireworks.flight);
+    assert_eq!(2, fireworks.explosions.len());
+
+    let first = &fireworks.explosions[0];
+    assert_eq!(FireworkShape::LargeBall, first.shape);
+    assert_eq!(
+        vec![Color { r: 0xff, g: 0, b: 0 }, Color { r: 0, g: 0xff, b: 0 }],
+        first.colors,
+    );
+    assert!(first.fade_colors.is_empty());
+    assert!(first.flicker);
+    assert!(first.trail);
+
+    let second = &fireworks.explosions[1];
+    assert_eq!(FireworkShape::Creeper, second.shape);
+    assert_eq!(vec![Color { r: 0x3b, g: 0x51, b: 0x1a }], second.colors);
+    assert_eq!(vec![Color { r: 0xf0, g: 0xf0, b: 0xf0 }], second.fade_colors);
+    assert!(!second.trail);
+
+    assert!(item::read_fireworks(&Compound::new()).is_none());
+}
+
+
+#[test]
+fn test_read_firework_star() {
+    let mut tag = Compound::new();
+    tag.insert(
+        "Explosion".to_string(),
+        Value::Compound(explosion(2, vec![0x0000ff], vec![], false)),
+    );
+    let star = item::read_firework_star(&tag).unwrap();
+    assert_eq!(FireworkShape::Star, star.shape);
+    assert_eq!(vec![Color { r: 0, g: 0, b: 0xff }], star.colors);
+}
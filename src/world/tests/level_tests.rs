@@ -0,0 +1,240 @@
+use std::io::Cursor;
+
+use crate::nbt::{reader, snbt, Compound, RootValue, Value, TAG_INT, TAG_STRING};
+use crate::world::BlockPos;
+use crate::world::WorldError;
+use crate::world::level::{self, HeightInfo, LevelData, LevelSummary, VersionInfo};
+
+
+const LEVEL: &[u8] = include_bytes!("level.nbt");
+const LEVEL_LEGACY: &[u8] = include_bytes!("level_legacy.nbt");
+const LEVEL_CUSTOM_DIMENSION: &[u8] = include_bytes!("level_custom_dimension.nbt");
+
+
+fn parse(bytes: &[u8]) -> RootValue {
+    match reader::parse_nbt_stream(&mut Cursor::new(bytes)) {
+        Ok(result) => result,
+        Err(err) => panic!("{:?}", err),
+    }
+}
+
+
+#[test]
+fn test_dimension_height_defaults() {
+    let level = parse(LEVEL_CUSTOM_DIMENSION);
+    assert_eq!(
+        HeightInfo { min_y: -64, height: 384 },
+        level::dimension_height(&level, "minecraft:overworld"),
+    );
+    assert_eq!(
+        HeightInfo { min_y: 0, height: 256 },
+        level::dimension_height(&level, "minecraft:the_nether"),
+    );
+    // Not listed in the level at all.
+    assert_eq!(
+        HeightInfo { min_y: 0, height: 256 },
+        level::dimension_height(&level, "minecraft:the_end"),
+    );
+}
+
+
+#[test]
+fn test_dimension_height_custom() {
+    let level = parse(LEVEL_CUSTOM_DIMENSION);
+    let height = level::dimension_height(&level, "example:deep");
+    assert_eq!(HeightInfo { min_y: -128, height: 512 }, height);
+    assert_eq!(384, height.max_y());
+}
+
+
+#[test]
+fn test_level_data_getters() {
+    let root = parse(LEVEL);
+    let level = LevelData::new(&root).unwrap();
+    assert_eq!(Some("New World"), level.level_name());
+    assert_eq!(Some(3465), level.data_version());
+    assert_eq!(Some(true), level.was_modded());
+    assert_eq!(vec!["vanilla", "fabric"], level.server_brands());
+    assert_eq!(
+        Some(0x12345678_87654321_0badf00d_ffffffff),
+        level.wandering_trader_id(),
+    );
+    assert_eq!(Some(50), level.wandering_trader_spawn_chance());
+    assert_eq!(Some(24000), level.wandering_trader_spawn_delay());
+}
+
+
+#[test]
+fn test_level_data_missing_fields() {
+    let root = parse(LEVEL_CUSTOM_DIMENSION);
+    let level = LevelData::new(&root).unwrap();
+    assert_eq!(None, level.wandering_trader_id());
+    assert!(level.server_brands().is_empty());
+}
+
+
+#[test]
+fn test_read_dragon_fight() {
+    let level = parse(LEVEL);
+    let fight = level::read_dragon_fight(&level).unwrap();
+    assert!(fight.dragon_killed);
+    assert!(fight.previously_killed);
+    assert_eq!(5, fight.gateways.len());
+    assert_eq!(vec![3, 17, 9, 0, 12], fight.gateways);
+    assert_eq!(Some(0x00000001_00000002_00000003_00000004), fight.dragon_uuid);
+    assert_eq!(Some(BlockPos { x: 0, y: 63, z: 0 }), fight.exit_portal_location);
+
+    assert!(level::read_dragon_fight(&parse(LEVEL_CUSTOM_DIMENSION)).is_none());
+}
+
+
+#[test]
+fn test_dragon_fight_gateway_positions() {
+    let fight = level::read_dragon_fight(&parse(LEVEL)).unwrap();
+    assert_eq!(
+        vec![
+            BlockPos { x: 56, y: 75, z: 77 },
+            BlockPos { x: 56, y: 75, z: -78 },
+            BlockPos { x: -92, y: 75, z: 29 },
+            BlockPos { x: 96, y: 75, z: 0 },
+            BlockPos { x: -78, y: 75, z: -57 },
+        ],
+        fight.gateway_positions(),
+    );
+}
+
+
+#[test]
+fn test_set_dragon_fight_fields() {
+    let mut level = parse(LEVEL);
+    assert!(level::set_dragon_fight_gateways(&mut level, &[0, 1, 2]));
+    assert!(level::set_exit_portal_location(&mut level, Some(BlockPos { x: 0, y: 70, z: 0 })));
+    let fight = level::read_dragon_fight(&level).unwrap();
+    assert_eq!(vec![0, 1, 2], fight.gateways);
+    assert_eq!(Some(BlockPos { x: 0, y: 70, z: 0 }), fight.exit_portal_location);
+
+    assert!(level::set_exit_portal_location(&mut level, None));
+    assert_eq!(None, level::read_dragon_fight(&level).unwrap().exit_portal_location);
+
+    let mut level = parse(LEVEL_CUSTOM_DIMENSION);
+    assert!(!level::set_dragon_fight_gateways(&mut level, &[]));
+}
+
+
+#[test]
+fn test_read_dragon_fight_pre_1_16() {
+    let mut fight = Compound::new();
+    fight.insert("DragonKilled".to_string(), Value::Byte(0));
+    fight.insert("DragonUUIDMost".to_string(), Value::Long(-1));
+    fight.insert("DragonUUIDLeast".to_string(), Value::Long(2));
+    let mut end = Compound::new();
+    end.insert("DragonFight".to_string(), Value::Compound(fight));
+    let mut dimensions = Compound::new();
+    dimensions.insert("1".to_string(), Value::Compound(end));
+    let mut data = Compound::new();
+    data.insert("DimensionData".to_string(), Value::Compound(dimensions));
+    let mut root = Compound::new();
+    root.insert("Data".to_string(), Value::Compound(data));
+    let level = RootValue { name: String::new(), value: Value::Compound(root) };
+
+    let fight = level::read_dragon_fight(&level).unwrap();
+    assert!(!fight.dragon_killed);
+    assert!(fight.gateways.is_empty());
+    assert_eq!(Some(0xffffffff_ffffffff_00000000_00000002), fight.dragon_uuid);
+}
+
+
+#[test]
+fn test_read_seed() {
+    assert_eq!(Some(-4172144997902289642), level::read_seed(&parse(LEVEL)));
+    assert_eq!(Some(8678942899319966093), level::read_seed(&parse(LEVEL_LEGACY)));
+}
+
+
+#[test]
+fn test_read_version_info() {
+    assert_eq!(
+        Some(VersionInfo {
+            id: 3465,
+            name: "1.20.1".to_string(),
+            series: Some("main".to_string()),
+            snapshot: false,
+        }),
+        level::read_version_info(&parse(LEVEL)),
+    );
+    assert_eq!(
+        Some(VersionInfo {
+            id: 1343,
+            name: "1.12.2".to_string(),
+            series: None,
+            snapshot: false,
+        }),
+        level::read_version_info(&parse(LEVEL_LEGACY)),
+    );
+
+    let mut version = Compound::new();
+    version.insert("Id".to_string(), Value::Int(3567));
+    version.insert("Name".to_string(), Value::String("23w31a".to_string()));
+    version.insert("Series".to_string(), Value::String("main".to_string()));
+    version.insert("Snapshot".to_string(), Value::Byte(1));
+    let mut data = Compound::new();
+    data.insert("Version".to_string(), Value::Compound(version));
+    let mut root = Compound::new();
+    root.insert("Data".to_string(), Value::Compound(data));
+    let snapshot = RootValue { name: String::new(), value: Value::Compound(root) };
+    let info = level::read_version_info(&snapshot).unwrap();
+    assert_eq!("23w31a", info.name);
+    assert!(info.snapshot);
+}
+
+
+#[test]
+fn test_level_summary() {
+    assert_eq!(
+        Ok(LevelSummary {
+            level_name: "New World".to_string(),
+            spawn: BlockPos { x: 16, y: 72, z: -48 },
+            game_type: 0,
+            data_version: Some(3465),
+            hardcore: false,
+            difficulty: None,
+            last_played: None,
+        }),
+        LevelSummary::from_root(&parse(LEVEL)),
+    );
+}
+
+
+fn level_from_snbt(data: &str) -> RootValue {
+    let value = snbt::parse_snbt(&format!("{{Data:{}}}", data)).unwrap();
+    RootValue { name: String::new(), value }
+}
+
+
+#[test]
+fn test_level_summary_optional_fields() {
+    let level = level_from_snbt(
+        "{LevelName:\"Hard\",SpawnX:0,SpawnY:64,SpawnZ:0,GameType:0,hardcore:1b,\
+         Difficulty:3b,LastPlayed:1700000000000L}");
+    let summary = LevelSummary::from_root(&level).unwrap();
+    assert_eq!(None, summary.data_version);
+    assert!(summary.hardcore);
+    assert_eq!(Some(3), summary.difficulty);
+    assert_eq!(Some(1_700_000_000_000), summary.last_played);
+}
+
+
+#[test]
+fn test_level_summary_errors() {
+    assert_eq!(
+        Err(WorldError::Missing("SpawnX")),
+        LevelSummary::from_root(&parse(LEVEL_CUSTOM_DIMENSION)),
+    );
+    let level = level_from_snbt("{LevelName:1,SpawnX:0,SpawnY:64,SpawnZ:0,GameType:0}");
+    assert_eq!(
+        Err(WorldError::WrongType { key: "LevelName", expected: TAG_STRING, found: TAG_INT }),
+        LevelSummary::from_root(&level),
+    );
+    let level = RootValue { name: String::new(), value: Value::Compound(Compound::new()) };
+    assert_eq!(Err(WorldError::Missing("Data")), LevelSummary::from_root(&level));
+}
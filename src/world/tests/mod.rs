@@ -0,0 +1,12 @@
+mod villager_tests;
+mod level_tests;
+mod data_tests;
+mod effect_tests;
+mod inventory_tests;
+mod entity_tests;
+mod poi_tests;
+mod item_tests;
+mod chunk_tests;
+mod player_tests;
+mod banner_tests;
+mod spawner_tests;
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::world::player;
+
+
+const PLAYERDATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/world/tests/playerdata");
+
+
+#[test]
+fn test_player_uuid_from_dat_filename() {
+    assert_eq!(
+        Some(0x0b6f3e2a_6b3c_4c8f_9d1e_2f0a9b8c7d6e),
+        player::player_uuid_from_dat_filename(
+            Path::new("playerdata/0b6f3e2a-6b3c-4c8f-9d1e-2f0a9b8c7d6e.dat"),
+        ),
+    );
+    assert_eq!(
+        None,
+        player::player_uuid_from_dat_filename(
+            Path::new("playerdata/0b6f3e2a-6b3c-4c8f-9d1e-2f0a9b8c7d6e.dat_old"),
+        ),
+    );
+    assert_eq!(
+        None, player::player_uuid_from_dat_filename(Path::new("playerdata/notes.dat")),
+    );
+    assert_eq!(
+        None,
+        player::player_uuid_from_dat_filename(
+            Path::new("0b6f3e2a6b3c4c8f9d1e2f0a9b8c7d6e.dat"),
+        ),
+    );
+}
+
+
+#[test]
+fn test_find_player_dat() {
+    let dir = Path::new(PLAYERDATA);
+    let uuid = 0x5d1f7c3b_1111_4a2b_8c3d_4e5f6a7b8c9d;
+    let path = player::find_player_dat(dir, uuid).unwrap();
+    assert_eq!(dir.join("5d1f7c3b-1111-4a2b-8c3d-4e5f6a7b8c9d.dat"), path);
+    assert_eq!(Some(uuid), player::player_uuid_from_dat_filename(&path));
+
+    assert_eq!(None, player::find_player_dat(dir, 1));
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::nbt::reader;
+use crate::world::{poi, BlockPos};
+
+
+const POI: &[u8] = include_bytes!("poi.nbt");
+
+
+#[test]
+fn test_poi_summary() {
+    let chunk = reader::parse_nbt_stream(&mut Cursor::new(POI)).unwrap();
+    let mut expected = HashMap::new();
+    expected.insert("minecraft:home".to_string(), 2);
+    expected.insert("minecraft:farmer".to_string(), 1);
+    expected.insert("minecraft:meeting".to_string(), 1);
+    assert_eq!(expected, poi::poi_summary(&chunk));
+}
+
+
+#[test]
+fn test_read_poi_records() {
+    let chunk = reader::parse_nbt_stream(&mut Cursor::new(POI)).unwrap();
+    let records = poi::read_poi_records(&chunk);
+    assert_eq!(4, records.len());
+
+    let bell = records.iter().find(|r| r.poi_type == "minecraft:meeting").unwrap();
+    assert_eq!(BlockPos { x: 8, y: 85, z: 8 }, bell.pos);
+    assert_eq!(32, bell.free_tickets);
+    assert!(!bell.valid);
+
+    let claimed_bed = records.iter()
+        .find(|r| r.pos == BlockPos { x: 1, y: 70, z: 2 })
+        .unwrap();
+    assert_eq!(0, claimed_bed.free_tickets);
+    assert!(claimed_bed.valid);
+}
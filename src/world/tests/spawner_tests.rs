@@ -0,0 +1,79 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::spawner::{self, SpawnPotential};
+
+
+fn compound(entries: Vec<(&str, Value)>) -> Compound {
+    entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+
+fn entity(id: &str) -> Value {
+    Value::Compound(compound(vec![("id", Value::String(id.to_string()))]))
+}
+
+
+/// Spawn data as stored since 1.18.
+fn spawn_data(id: &str) -> Value {
+    Value::Compound(compound(vec![("entity", entity(id))]))
+}
+
+
+#[test]
+fn test_read_spawner() {
+    let block_entity = compound(vec![
+        ("id", Value::String("minecraft:mob_spawner".to_string())),
+        ("Delay", Value::Short(143)),
+        ("MinSpawnDelay", Value::Short(100)),
+        ("SpawnData", spawn_data("minecraft:zombie")),
+        ("SpawnPotentials", Value::List(List::Compound(vec![
+            compound(vec![
+                ("weight", Value::Int(3)),
+                ("data", spawn_data("minecraft:zombie")),
+            ]),
+            compound(vec![
+                ("weight", Value::Int(1)),
+                ("data", spawn_data("minecraft:husk")),
+            ]),
+        ]))),
+    ]);
+    let spawner = spawner::read_spawner(&block_entity);
+    assert_eq!(143, spawner.delay);
+    assert_eq!(100, spawner.min_spawn_delay);
+    assert_eq!(800, spawner.max_spawn_delay);
+    assert_eq!(Some("minecraft:zombie".to_string()), spawner.entity_id);
+    assert_eq!(
+        vec![
+            SpawnPotential { entity_id: Some("minecraft:zombie".to_string()), weight: 3 },
+            SpawnPotential { entity_id: Some("minecraft:husk".to_string()), weight: 1 },
+        ],
+        spawner.potentials,
+    );
+}
+
+
+#[test]
+fn test_read_spawner_pre_1_18() {
+    let block_entity = compound(vec![
+        ("id", Value::String("minecraft:mob_spawner".to_string())),
+        ("Delay", Value::Short(20)),
+        ("SpawnCount", Value::Short(2)),
+        ("SpawnData", entity("minecraft:skeleton")),
+        ("SpawnPotentials", Value::List(List::Compound(vec![
+            compound(vec![
+                ("Weight", Value::Int(5)),
+                ("Entity", entity("minecraft:skeleton")),
+            ]),
+        ]))),
+    ]);
+    let spawner = spawner::read_spawner(&block_entity);
+    assert_eq!(2, spawner.spawn_count);
+    assert_eq!(Some("minecraft:skeleton".to_string()), spawner.entity_id);
+    assert_eq!(
+        vec![SpawnPotential { entity_id: Some("minecraft:skeleton".to_string()), weight: 5 }],
+        spawner.potentials,
+    );
+
+    let empty = spawner::read_spawner(&Compound::new());
+    assert_eq!(None, empty.entity_id);
+    assert!(empty.potentials.is_empty());
+}
@@ -0,0 +1,99 @@
+use crate::nbt::{Compound, List, Value};
+use crate::world::{villager, BlockPos};
+
+
+fn item(id: &str, count: i8) -> Compound {
+    let mut item = Compound::new();
+    item.insert("id".to_string(), Value::String(id.to_string()));
+    item.insert("Count".to_string(), Value::Byte(count));
+    item
+}
+
+
+fn emerald_villager() -> Compound {
+    let mut recipe = Compound::new();
+    recipe.insert("buy".to_string(), Value::Compound(item("minecraft:wheat", 20)));
+    recipe.insert("buyB".to_string(), Value::Compound(item("minecraft:air", 0)));
+    recipe.insert("sell".to_string(), Value::Compound(item("minecraft:emerald", 1)));
+    recipe.insert("uses".to_string(), Value::Int(3));
+    recipe.insert("maxUses".to_string(), Value::Int(16));
+    recipe.insert("rewardExp".to_string(), Value::Byte(1));
+    recipe.insert("xp".to_string(), Value::Int(2));
+    recipe.insert("priceMultiplier".to_string(), Value::Float(0.05));
+    recipe.insert("specialPrice".to_string(), Value::Int(-1));
+    recipe.insert("demand".to_string(), Value::Int(4));
+
+    let mut offers = Compound::new();
+    offers.insert(
+        "Recipes".to_string(), Value::List(List::Compound(vec![recipe])),
+    );
+    let mut villager = Compound::new();
+    villager.insert("id".to_string(), Value::String("minecraft:villager".to_string()));
+    villager.insert("Offers".to_string(), Value::Compound(offers));
+    villager
+}
+
+
+#[test]
+fn test_read_villager_offers() {
+    let villager = emerald_villager();
+    let trades = villager::read_villager_offers(&villager);
+    assert_eq!(1, trades.len());
+    let trade = &trades[0];
+    match trade.sell.get("id") {
+        Some(Value::String(id)) => assert_eq!("minecraft:emerald", id),
+        _ => panic!("Sell item has no id."),
+    };
+    match trade.buy.get("Count") {
+        Some(Value::Byte(count)) => assert_eq!(20, *count),
+        _ => panic!("Buy item has no count."),
+    };
+    assert!(trade.buy_b.is_some());
+    assert_eq!(3, trade.uses);
+    assert_eq!(16, trade.max_uses);
+    assert!(trade.reward_exp);
+    assert_eq!(2, trade.xp);
+    assert_eq!(0.05, trade.price_multiplier);
+    assert_eq!(-1, trade.special_price);
+    assert_eq!(4, trade.demand);
+}
+
+
+#[test]
+fn test_read_villager_offers_without_offers() {
+    assert!(villager::read_villager_offers(&Compound::new()).is_empty());
+}
+
+
+fn position_memory(x: i32, y: i32, z: i32) -> Value {
+    let mut global_pos = Compound::new();
+    global_pos.insert("pos".to_string(), Value::IntArray(vec![x, y, z]));
+    global_pos.insert(
+        "dimension".to_string(), Value::String("minecraft:overworld".to_string()),
+    );
+    let mut memory = Compound::new();
+    memory.insert("value".to_string(), Value::Compound(global_pos));
+    Value::Compound(memory)
+}
+
+
+#[test]
+fn test_read_villager_memories() {
+    let mut last_slept = Compound::new();
+    last_slept.insert("value".to_string(), Value::Long(123456));
+    let mut memories = Compound::new();
+    memories.insert("minecraft:home".to_string(), position_memory(10, 64, -5));
+    memories.insert("minecraft:job_site".to_string(), position_memory(12, 64, -3));
+    memories.insert("minecraft:last_slept".to_string(), Value::Compound(last_slept));
+    let mut brain = Compound::new();
+    brain.insert("memories".to_string(), Value::Compound(memories));
+    let mut villager = emerald_villager();
+    villager.insert("Brain".to_string(), Value::Compound(brain));
+
+    let memories = villager::read_villager_memories(&villager);
+    assert_eq!(2, memories.len());
+    assert_eq!(BlockPos { x: 10, y: 64, z: -5 }, memories["minecraft:home"]);
+    assert_eq!(BlockPos { x: 12, y: 64, z: -3 }, memories["minecraft:job_site"]);
+
+    assert!(villager::read_villager_memories(&emerald_villager()).is_empty());
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::nbt::{Compound, Value};
+
+use super::{BlockPos, get_block_pos, get_byte, get_compound, get_compound_list};
+use super::{get_float, get_int};
+
+
+/// A single entry from a villager's `Offers.Recipes` list.
+///
+/// The item fields borrow the raw item compounds (`id`, `Count`, `tag`, …)
+/// from the villager. Fields that older versions don't store default to zero.
+#[derive(Debug)]
+pub struct VillagerTrade<'a> {
+    pub buy: &'a Compound,
+    /// The optional second item the villager wants. Recent versions always
+    /// store this, using an air item when the trade only needs one.
+    pub buy_b: Option<&'a Compound>,
+    pub sell: &'a Compound,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub reward_exp: bool,
+    pub xp: i32,
+    pub price_multiplier: f32,
+    pub special_price: i32,
+    pub demand: i32,
+}
+
+
+/// Read the trades offered by a villager (or wandering trader) entity.
+///
+/// Recipes missing their `buy` or `sell` item are skipped; an entity without
+/// an `Offers` compound has no trades.
+pub fn read_villager_offers(villager: &Compound) -> Vec<VillagerTrade<'_>> {
+    let offers = match get_compound(villager, "Offers") {
+        Some(offers) => offers,
+        None => return Vec::new(),
+    };
+    get_compound_list(offers, "Recipes").iter().filter_map(|recipe| {
+        Some(VillagerTrade {
+            buy: get_compound(recipe, "buy")?,
+            buy_b: get_compound(recipe, "buyB"),
+            sell: get_compound(recipe, "sell")?,
+            uses: get_int(recipe, "uses").unwrap_or(0),
+            max_uses: get_int(recipe, "maxUses").unwrap_or(0),
+            reward_exp: get_byte(recipe, "rewardExp").unwrap_or(0) != 0,
+            xp: get_int(recipe, "xp").unwrap_or(0),
+            price_multiplier: get_float(recipe, "priceMultiplier").unwrap_or(0.0),
+            special_price: get_int(recipe, "specialPrice").unwrap_or(0),
+            demand: get_int(recipe, "demand").unwrap_or(0),
+        })
+    }).collect()
+}
+
+
+/// Read the positions a villager remembers (`Brain.memories`), keyed by
+/// memory id, e.g. `"minecraft:home"`, `"minecraft:job_site"` and
+/// `"minecraft:meeting_point"`. Memories that aren't positions are skipped.
+///
+/// Since 1.16 each memory wraps its position in a `value` compound; 1.14
+/// and 1.15 stored the position directly.
+pub fn read_villager_memories(villager: &Compound) -> HashMap<String, BlockPos> {
+    let memories = get_compound(villager, "Brain")
+        .and_then(|brain| get_compound(brain, "memories"));
+    let memories = match memories {
+        Some(memories) => memories,
+        None => return HashMap::new(),
+    };
+    memories.iter().filter_map(|(id, memory)| {
+        let memory = match memory {
+            Value::Compound(memory) => memory,
+            _ => return None,
+        };
+        let global_pos = get_compound(memory, "value").unwrap_or(memory);
+        Some((id.clone(), get_block_pos(global_pos, "pos")?))
+    }).collect()
+}